@@ -0,0 +1,41 @@
+#![no_main]
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use nbt::{
+    io::{Encoding, Nbt},
+    types::Tag,
+    NbtLimits, NbtRef,
+};
+
+/// Compares through the encoded bytes since NaN floats never compare equal
+fn encode(nbt: &Nbt) -> Vec<u8> {
+    let mut bytes = vec![];
+    block_on(nbt.to_writer(&mut bytes)).expect("decoded NBT can be encoded again");
+    bytes
+}
+
+fuzz_target!(|data: &[u8]| {
+    // Without limits the fuzzer spends its time on huge length prefixes rather than on the parser
+    let limits = NbtLimits::network();
+
+    let from_reader = block_on(Nbt::from_reader_with_limits(&mut &data[..], limits));
+    let from_slice = Nbt::from_slice_with_limits(data, limits);
+    let borrowed = NbtRef::with_limits(data, limits).and_then(|nbt| nbt.to_nbt());
+
+    if let (Ok(a), Ok(b), Ok(c)) = (&from_reader, &from_slice, &borrowed) {
+        assert_eq!(encode(a), encode(b));
+        assert_eq!(encode(a), encode(c));
+
+        let mut written = vec![];
+        a.write(&mut written).expect("decoded NBT can be written again");
+        assert_eq!(written, encode(a));
+    }
+
+    for encoding in [Encoding::BedrockLittleEndian, Encoding::BedrockNetwork] {
+        let _ = block_on(Nbt::from_encoded_reader_with_limits(&mut &data[..], encoding, limits));
+    }
+
+    let _ = block_on(Tag::from_networked_reader_with_limits(&mut &data[..], limits));
+    let _ = Tag::from_networked_slice_with_limits(data, limits);
+});
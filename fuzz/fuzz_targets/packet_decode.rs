@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use server::{
+    decoder::DecoderReadExt,
+    packets::serverbound::{ConfigurationPacket, HandshakePacket, LoginPacket, PlayPacket, StatusPacket},
+};
+
+fuzz_target!(|data: &[u8]| {
+    let mut payload = data;
+    let Ok(id) = payload.read_var_i32() else { return };
+
+    let _ = HandshakePacket::decode(id, payload);
+    let _ = StatusPacket::decode(id, payload);
+    let _ = LoginPacket::decode(id, payload);
+    let _ = ConfigurationPacket::decode(id, payload);
+    let _ = PlayPacket::decode(id, payload);
+});
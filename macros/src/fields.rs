@@ -0,0 +1,372 @@
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Error, Expr, Field, Fields, GenericArgument, LitInt, Member, PathArguments, Result, Type};
+
+/// How a value is put on the wire, picked by the field's attributes
+enum Codec {
+    /// The type's own `Encoder` and `Decoder`
+    Default,
+    /// `#[varint]` on an `i32`
+    VarInt,
+    /// `#[varlong]` on an `i64`
+    VarLong,
+    /// `#[max_len(16)]` on a `String`
+    MaxLen(LitInt),
+    /// `#[rest]` on a `Vec<u8>`, everything up to the end of the packet without a length prefix
+    Rest,
+    /// `#[fixed_len]` on an array, its elements without a length prefix
+    FixedLen,
+    /// `#[nbt]` on any serde type, written as a networked NBT tag
+    Nbt,
+}
+
+/// Presence of an `Option` field
+enum Presence {
+    Always,
+    /// `#[prefixed]`, a boolean tells whether the value follows
+    Prefixed,
+    /// `#[skip_if(expr)]`, the value is left out when `expr` holds, earlier fields are in scope as references
+    /// (named `field_0` and so on in tuple structs)
+    SkipIf(Expr),
+}
+
+struct FieldCodec<'a> {
+    field: &'a Field,
+    member: Member,
+    /// The field name, or `field_0` and so on for tuple structs
+    name: Ident,
+    /// The field type, or the type inside the `Option` for `#[prefixed]` and `#[skip_if]`
+    ty: Type,
+    codec: Codec,
+    presence: Presence,
+}
+
+impl<'a> FieldCodec<'a> {
+    fn parse(index: usize, field: &'a Field) -> Result<Self> {
+        let (member, name) = match &field.ident {
+            Some(ident) => (Member::Named(ident.clone()), ident.clone()),
+            None => (Member::Unnamed(index.into()), format_ident!("field_{index}")),
+        };
+        let mut codec = Codec::Default;
+        let mut presence = Presence::Always;
+
+        for attribute in &field.attrs {
+            let path = attribute.path();
+            let parsed = if path.is_ident("varint") {
+                Some(Codec::VarInt)
+            } else if path.is_ident("varlong") {
+                Some(Codec::VarLong)
+            } else if path.is_ident("max_len") {
+                Some(Codec::MaxLen(attribute.parse_args().map_err(|err| {
+                    Error::new(err.span(), "expected a maximum length, e.g. #[max_len(16)]")
+                })?))
+            } else if path.is_ident("rest") {
+                Some(Codec::Rest)
+            } else if path.is_ident("nbt") {
+                Some(Codec::Nbt)
+            } else if path.is_ident("fixed_len") {
+                Some(Codec::FixedLen)
+            } else {
+                let parsed = if path.is_ident("prefixed") {
+                    Some(Presence::Prefixed)
+                } else if path.is_ident("skip_if") {
+                    Some(Presence::SkipIf(attribute.parse_args().map_err(|err| {
+                        Error::new(err.span(), "expected a condition, e.g. #[skip_if(!is_signed)]")
+                    })?))
+                } else {
+                    None
+                };
+
+                if let Some(parsed) = parsed {
+                    if !matches!(presence, Presence::Always) {
+                        return Err(Error::new_spanned(attribute, "#[prefixed] and #[skip_if] can't be combined"));
+                    }
+                    presence = parsed;
+                }
+                None
+            };
+
+            if let Some(parsed) = parsed {
+                if !matches!(codec, Codec::Default) {
+                    return Err(Error::new_spanned(
+                        attribute,
+                        "only one of #[varint], #[varlong], #[max_len], #[rest], #[fixed_len] and #[nbt] can be used on a field",
+                    ));
+                }
+                codec = parsed;
+            }
+        }
+
+        let ty = match presence {
+            Presence::Always => field.ty.clone(),
+            _ => option_inner(&field.ty).ok_or_else(|| Error::new_spanned(&field.ty, "#[prefixed] and #[skip_if] expect an Option"))?,
+        };
+
+        if matches!(codec, Codec::FixedLen) && !matches!(ty, Type::Array(_)) {
+            return Err(Error::new_spanned(&ty, "#[fixed_len] expects an array"));
+        }
+
+        Ok(Self {
+            field,
+            member,
+            name,
+            ty,
+            codec,
+            presence,
+        })
+    }
+
+    /// Statements writing the value behind the reference `value`
+    fn encode_value(&self, value: TokenStream, writer: &Ident) -> TokenStream {
+        let ty = &self.ty;
+
+        match &self.codec {
+            Codec::Default => quote! {
+                crate::encoder::Encoder::encode(#value, #writer)?;
+            },
+            Codec::VarInt => quote! {
+                crate::encoder::EncoderWriteExt::write_var_i32(#writer, crate::types::VarInt(*#value));
+            },
+            Codec::VarLong => quote! {
+                crate::encoder::EncoderWriteExt::write_var_i64(#writer, crate::types::VarLong(*#value));
+            },
+            Codec::MaxLen(max_len) => quote! {
+                crate::encoder::EncoderWriteExt::write_string(#writer, #value, #max_len)?;
+            },
+            Codec::Rest => quote! {
+                bytes::BufMut::put_slice(#writer, #value);
+            },
+            Codec::FixedLen => {
+                let Type::Array(array) = ty else { unreachable!() };
+                let element = &array.elem;
+                quote! {
+                    <#element as crate::encoder::Encoder>::encode_slice(#value, #writer)?;
+                }
+            }
+            Codec::Nbt => quote! {
+                nbt::to_tag::<#ty>(#value)?.write_networked(&mut bytes::BufMut::writer(&mut *#writer))?;
+            },
+        }
+    }
+
+    /// An expression guessing the size of the value behind the reference `value`, see `Encoder::size_hint`
+    fn size_hint_value(&self, value: TokenStream) -> TokenStream {
+        match &self.codec {
+            Codec::Default | Codec::MaxLen(_) => quote! {
+                crate::encoder::Encoder::size_hint(#value)
+            },
+            Codec::VarInt => quote! {
+                crate::types::VarInt(*#value).encoded_len()
+            },
+            Codec::VarLong => quote! {
+                crate::types::VarLong(*#value).encoded_len()
+            },
+            Codec::Rest => quote! {
+                (#value).len()
+            },
+            Codec::FixedLen => quote! {
+                (#value).iter().map(crate::encoder::Encoder::size_hint).sum::<usize>()
+            },
+            Codec::Nbt => quote! { 0 },
+        }
+    }
+
+    /// An expression reading the value
+    fn decode_value(&self, reader: &Ident) -> TokenStream {
+        let ty = &self.ty;
+
+        match &self.codec {
+            Codec::Default => quote! {
+                <#ty as crate::decoder::Decoder>::decode(#reader)?
+            },
+            Codec::VarInt => quote! {
+                crate::decoder::DecoderReadExt::read_var_i32(#reader)?
+            },
+            Codec::VarLong => quote! {
+                crate::decoder::DecoderReadExt::read_var_i64(#reader)?
+            },
+            Codec::MaxLen(max_len) => quote! {
+                crate::decoder::DecoderReadExt::read_string(#reader, #max_len)?
+            },
+            Codec::Rest => quote! {
+                crate::decoder::DecoderReadExt::read_bytes(#reader, bytes::Buf::remaining(#reader))?
+            },
+            Codec::FixedLen => {
+                let Type::Array(array) = ty else { unreachable!() };
+                let (element, len) = (&array.elem, &array.len);
+                quote! {
+                    match <#ty>::try_from(<#element as crate::decoder::Decoder>::decode_vec(#len, #reader)?) {
+                        Ok(array) => array,
+                        Err(_) => unreachable!(),
+                    }
+                }
+            }
+            Codec::Nbt => quote! {
+                match <Option<nbt::types::Tag> as crate::decoder::Decoder>::decode(#reader)? {
+                    Some(tag) => nbt::from_tag::<#ty>(tag)?,
+                    None => return Err(nbt::Error::InvalidRoot(0x00).into()),
+                }
+            },
+        }
+    }
+}
+
+/// Returns `T` for a type spelled `Option<T>`
+fn option_inner(ty: &Type) -> Option<Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+            GenericArgument::Type(inner) => Some(inner.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The fields of a struct with the codec picked for each of them
+pub struct StructFields<'a> {
+    fields: &'a Fields,
+    codecs: Vec<FieldCodec<'a>>,
+}
+
+impl<'a> StructFields<'a> {
+    pub fn parse(fields: &'a Fields) -> Result<Self> {
+        let codecs = fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| FieldCodec::parse(i, field))
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(position) = codecs.iter().position(|field| matches!(field.codec, Codec::Rest)) {
+            if position != codecs.len() - 1 {
+                let field = codecs[position].field;
+                let message = "#[rest] can only be used on the last field";
+                return Err(match field.attrs.iter().find(|a| a.path().is_ident("rest")) {
+                    Some(attribute) => Error::new_spanned(attribute, message),
+                    None => Error::new_spanned(field, message),
+                });
+            }
+        }
+
+        Ok(Self { fields, codecs })
+    }
+
+    /// `Self { a, b }`, `Self(field_0, field_1)` or `Self`, binding every field to a local of its name
+    ///
+    /// Works both as a pattern destructuring `self` and as the expression building the decoded value.
+    pub fn constructor(&self) -> TokenStream {
+        let names = self.codecs.iter().map(|field| &field.name);
+
+        match self.fields {
+            Fields::Named(_) => quote! { Self { #(#names),* } },
+            Fields::Unnamed(_) => quote! { Self(#(#names),*) },
+            Fields::Unit => quote! { Self },
+        }
+    }
+
+    /// Statements writing every field of `self` to `writer`
+    pub fn encode(&self, writer: &Ident) -> TokenStream {
+        let constructor = self.constructor();
+
+        let statements = self.codecs.iter().map(|field| {
+            let member = &field.member;
+
+            match &field.presence {
+                Presence::Always => field.encode_value(quote!(&self.#member), writer),
+                Presence::Prefixed => {
+                    let encode = field.encode_value(quote!(value), writer);
+                    quote! {
+                        match &self.#member {
+                            Some(value) => {
+                                crate::encoder::EncoderWriteExt::write_bool(#writer, true);
+                                #encode
+                            }
+                            None => crate::encoder::EncoderWriteExt::write_bool(#writer, false),
+                        }
+                    }
+                }
+                Presence::SkipIf(condition) => {
+                    let encode = field.encode_value(quote!(value), writer);
+                    let name = &field.name;
+                    quote! {
+                        let skip = {
+                            #[allow(unused_variables)]
+                            let #constructor = self;
+                            #condition
+                        };
+                        if !skip {
+                            match &self.#member {
+                                Some(value) => { #encode }
+                                None => return Err(crate::errors::EncodeError::MissingField(stringify!(#name))),
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        quote! { #(#statements)* }
+    }
+
+    /// An expression adding up the size hints of every field of `self`
+    pub fn size_hint(&self) -> TokenStream {
+        let hints = self.codecs.iter().map(|field| {
+            let member = &field.member;
+
+            match &field.presence {
+                Presence::Always => field.size_hint_value(quote!(&self.#member)),
+                Presence::Prefixed => {
+                    let hint = field.size_hint_value(quote!(value));
+                    quote!(1 + self.#member.as_ref().map_or(0, |value| #hint))
+                }
+                Presence::SkipIf(_) => {
+                    let hint = field.size_hint_value(quote!(value));
+                    quote!(self.#member.as_ref().map_or(0, |value| #hint))
+                }
+            }
+        });
+
+        quote! { 0 #(+ #hints)* }
+    }
+
+    /// Statements reading every field from `reader` into a local of its name, see [`Self::constructor`]
+    pub fn decode(&self, reader: &Ident) -> TokenStream {
+        let statements = self.codecs.iter().enumerate().map(|(i, field)| {
+            let name = &field.name;
+            let decode = field.decode_value(reader);
+
+            match &field.presence {
+                Presence::Always => quote! {
+                    let #name = #decode;
+                },
+                Presence::Prefixed => quote! {
+                    let #name = if crate::decoder::DecoderReadExt::read_bool(#reader)? {
+                        Some(#decode)
+                    } else {
+                        None
+                    };
+                },
+                Presence::SkipIf(condition) => {
+                    let earlier = self.codecs[..i].iter().map(|field| &field.name);
+                    quote! {
+                        let skip = {
+                            #(
+                                #[allow(unused_variables)]
+                                let #earlier = &#earlier;
+                            )*
+                            #condition
+                        };
+                        let #name = if skip { None } else { Some(#decode) };
+                    }
+                }
+            }
+        });
+
+        quote! { #(#statements)* }
+    }
+}
@@ -1,146 +1,438 @@
+mod fields;
+
+use fields::StructFields;
 use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
 use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DataEnum, DataStruct, DeriveInput, Error, Fields, Generics, LitInt, LitStr, Result,
+    Type, Variant,
+};
 
 /// # Information
-/// Makes the struct serializable for T & Vec<T>
-#[proc_macro_derive(Serializable, attributes(serialize_as))]
+/// Implements `Encoder` for structs and for fieldless enums
+///
+/// Fieldless enums are written as their discriminant, `#[serialize_as(VarInt)]` picks the wire type.
+/// With `#[serialize_as(String)]` variants are written by name, see [`derive_deserializable`].
+#[proc_macro_derive(
+    Serializable,
+    attributes(serialize_as, varint, varlong, max_len, rest, fixed_len, nbt, prefixed, skip_if)
+)]
 pub fn derive_serializable(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
+    expand(serializable(&ast))
+}
+
+fn serializable(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
     let struct_name = &ast.ident;
-    let attributes = &ast.attrs;
-
-    match ast.data {
-        Data::Struct(data_struct) => match data_struct.fields {
-            Fields::Named(fields) => {
-                let field_names = fields.named.iter().map(|field| &field.ident);
-                let field_names_2 = field_names.clone();
-                quote! {
-                    impl Encoder for #struct_name {
-                        async fn encode<W: tokio::io::AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-                            #(self.#field_names.encode(writer).await?;)*
-                            Ok(())
-                        }
-                    }
-
-                    impl Encoder for Vec<#struct_name> {
-                        async fn encode<W: tokio::io::AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-                            writer.write_var_i32(VarInt::from(self.len())).await?;
-
-                            for x in self.to_vec() {
-                                #(x.#field_names_2.encode(writer).await?;)*
-                            }
-
-                            Ok(())
-                        }
-                    }
-                }
+    let (encode, size_hint) = match &ast.data {
+        Data::Struct(data_struct) => {
+            let fields = StructFields::parse(&data_struct.fields)?;
+            let encode = fields.encode(&Ident::new("writer", Span::call_site()));
+            let encode = quote! {
+                #encode
+                Ok(())
+            };
+            (encode, fields.size_hint())
+        }
+        Data::Enum(data_enum) => serialize_enum(struct_name, &ast.attrs, data_enum)?,
+        Data::Union(data_union) => {
+            return Err(Error::new_spanned(
+                data_union.union_token,
+                "Serializable can't be derived for unions",
+            ))
+        }
+    };
+
+    let generics = with_bound(&ast.generics, |_| quote!(crate::encoder::Encoder));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::encoder::Encoder for #struct_name #ty_generics #where_clause {
+            fn encode<B: bytes::BufMut>(&self, writer: &mut B) -> Result<(), crate::errors::EncodeError> {
+                #encode
             }
-            .into(),
-            _ => panic!("Expected a named field"),
-        },
-        // Data::Enum(data_enum) => {
-        // let attribute = attributes
-        //     .iter()
-        //     .filter(|a| a.path().is_ident("serialize_as"))
-        //     .nth(0)
-        //     .expect("Expected a Type (#[serialize_as(i32)]");
-
-        // let serialize_type: syn::Type = {
-        //     let ty: syn::Type = attribute.parse_args().expect("Expected a Type");
-        //     ty
-        // };
-
-        // let variants = data_enum.variants.iter().map(|variant| &variant.ident);
-        // let gen = quote! {};
-
-        // gen.into()
-        // }
-        _ => panic!("Expected a struct or an enum with named fields"),
-    }
+
+            fn size_hint(&self) -> usize {
+                #size_hint
+            }
+        }
+    })
 }
 
-#[proc_macro_derive(Streamable, attributes(packet_id))]
+/// # Information
+/// Implements `Decoder` for structs and for fieldless enums
+///
+/// Enums take the same `#[serialize_as(...)]` attribute as [`derive_serializable`] and fail with
+/// `DecodeError::UnknownVariant` on values no variant maps to. String variants are named in snake case
+/// unless a variant carries its own name, e.g. `#[serialize_as("minecraft:overworld")]`.
+#[proc_macro_derive(
+    Deserializable,
+    attributes(serialize_as, varint, varlong, max_len, rest, fixed_len, nbt, prefixed, skip_if)
+)]
+pub fn derive_deserializable(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    expand(deserializable(&ast))
+}
+
+fn deserializable(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let struct_name = &ast.ident;
+    let decode = match &ast.data {
+        Data::Struct(data_struct) => {
+            let fields = StructFields::parse(&data_struct.fields)?;
+            let decode = fields.decode(&Ident::new("reader", Span::call_site()));
+            let constructor = fields.constructor();
+            quote! {
+                #decode
+                Ok(#constructor)
+            }
+        }
+        Data::Enum(data_enum) => deserialize_enum(struct_name, &ast.attrs, data_enum)?,
+        Data::Union(data_union) => {
+            return Err(Error::new_spanned(
+                data_union.union_token,
+                "Deserializable can't be derived for unions",
+            ))
+        }
+    };
+
+    let generics = with_bound(&ast.generics, |param| quote!(crate::decoder::Decoder<Output = #param>));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::decoder::Decoder for #struct_name #ty_generics #where_clause {
+            type Output = Self;
+
+            fn decode<B: bytes::Buf>(reader: &mut B) -> Result<Self, crate::errors::DecodeError> {
+                #decode
+            }
+        }
+    })
+}
+
+/// # Information
+/// Implements `Packet`, whose `encode_body` and `decode_body` write and read the fields like the `Encoder` and `Decoder`
+/// derives do, so the packet can be written and read from both sides
+///
+/// `#[packet(id = 0x29, state = Play, direction = Clientbound)]` names the `State` and `Direction` variants the packet belongs to.
+#[proc_macro_derive(Packet, attributes(packet, varint, varlong, max_len, rest, fixed_len, nbt, prefixed, skip_if))]
+pub fn derive_packet(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    expand(packet(&ast))
+}
+
+fn packet(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let struct_name = &ast.ident;
+    let data_struct = expect_struct(ast, "Packet")?;
+
+    let attribute = ast
+        .attrs
+        .iter()
+        .find(|a| a.path().is_ident("packet"))
+        .ok_or_else(|| Error::new_spanned(struct_name, "expected #[packet(id = 0x00, state = Play, direction = Clientbound)]"))?;
+
+    let (mut id, mut state, mut direction) = (None, None, None);
+    attribute.parse_nested_meta(|meta| {
+        if meta.path.is_ident("id") {
+            id = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<i32>()?);
+        } else if meta.path.is_ident("state") {
+            state = Some(meta.value()?.parse::<Ident>()?);
+        } else if meta.path.is_ident("direction") {
+            direction = Some(meta.value()?.parse::<Ident>()?);
+        } else {
+            return Err(meta.error("expected `id`, `state` or `direction`"));
+        }
+        Ok(())
+    })?;
+
+    let missing = |key: &str, example: &str| Error::new_spanned(attribute, format!("missing the packet {key}, e.g. #[packet({example})]"));
+    let id = id.ok_or_else(|| missing("id", "id = 0x00"))?;
+    let state = state.ok_or_else(|| missing("state", "state = Play"))?;
+    let direction = direction.ok_or_else(|| missing("direction", "direction = Clientbound"))?;
+
+    let fields = StructFields::parse(&data_struct.fields)?;
+    let encode = fields.encode(&Ident::new("writer", Span::call_site()));
+    let decode = fields.decode(&Ident::new("reader", Span::call_site()));
+    let constructor = fields.constructor();
+    let size_hint = fields.size_hint();
+
+    let generics = with_bound(
+        &ast.generics,
+        |param| quote!(crate::encoder::Encoder + crate::decoder::Decoder<Output = #param> + Send + Sync),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::packets::Packet for #struct_name #ty_generics #where_clause {
+            const ID: i32 = #id;
+            const STATE: crate::packets::State = crate::packets::State::#state;
+            const DIRECTION: crate::packets::Direction = crate::packets::Direction::#direction;
+
+            fn encode_body<B: bytes::BufMut>(&self, writer: &mut B) -> Result<(), crate::errors::EncodeError> {
+                #encode
+                Ok(())
+            }
+
+            fn decode_body<B: bytes::Buf>(reader: &mut B) -> Result<Self, crate::errors::DecodeError> {
+                #decode
+                Ok(#constructor)
+            }
+
+            fn body_size_hint(&self) -> usize {
+                #size_hint
+            }
+        }
+    })
+}
+
+/// # Information
+/// Implements `Encoder` and `DynPacket` for structs carrying only an id, e.g. `#[packet_id(0x00)]`
+///
+/// Unlike [`derive_packet`] there is no state, direction or decoding, the struct can only be handed to
+/// `Connection::send` or `FrameCodec::encode`, which frame it like any other packet.
+#[proc_macro_derive(
+    Streamable,
+    attributes(packet_id, varint, varlong, max_len, rest, fixed_len, nbt, prefixed, skip_if)
+)]
 pub fn derive_streamable(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
+    expand(streamable(&ast))
+}
+
+fn streamable(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
     let struct_name = &ast.ident;
-    let attributes = &ast.attrs;
+    let data_struct = expect_struct(ast, "Streamable")?;
 
-    let attribute = attributes
+    let attribute = ast
+        .attrs
         .iter()
-        .filter(|a| a.path().is_ident("packet_id"))
-        .nth(0)
-        .expect("Expected a single numeric literal (#[packet_id(0x00)]");
-
-    let packet_id: i32 = {
-        let lit: &syn::LitInt = &attribute.parse_args().unwrap();
-        let n: i32 = lit.base10_parse().expect("Expected a single numeric literal");
-        n
-    };
+        .find(|a| a.path().is_ident("packet_id"))
+        .ok_or_else(|| Error::new_spanned(struct_name, "expected a packet id, e.g. #[packet_id(0x00)]"))?;
+    let packet_id: i32 = attribute.parse_args::<LitInt>()?.base10_parse()?;
 
-    match ast.data {
-        Data::Struct(data_struct) => match data_struct.fields {
-            Fields::Named(fields) => {
-                let field_names = fields.named.iter().map(|field| &field.ident);
-
-                let gen = quote! {
-                    impl #struct_name {
-                        #[inline(always)]
-                        fn packet_id(&self) -> crate::types::VarInt {
-                            crate::types::VarInt(#packet_id)
-                        }
-                    }
-
-                    impl crate::encoder::SendToWriter for #struct_name {
-                        async fn send<W>(&self, stream: &mut W) -> Result<(), crate::errors::EncodeError>
-                        where
-                            W: AsyncWrite + Unpin {
-                            let mut buffer = vec![];
-
-                            #(self.#field_names.encode(&mut buffer).await?;)*
-                            let buffer = crate::utils::prepare_response(crate::types::VarInt(#packet_id), buffer).await;
-                            Ok(stream.write_all(&buffer).await?)
-                        }
-                    }
-                };
-
-                gen.into()
-            }
-            _ => panic!("Expected a named field"),
-        },
-        _ => panic!("Expected a struct"),
-    }
+    let fields = StructFields::parse(&data_struct.fields)?;
+    let encode = fields.encode(&Ident::new("writer", Span::call_site()));
+    let size_hint = fields.size_hint();
+
+    let generics = with_bound(&ast.generics, |_| quote!(crate::encoder::Encoder + Send + Sync));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::encoder::Encoder for #struct_name #ty_generics #where_clause {
+            fn encode<B: bytes::BufMut>(&self, writer: &mut B) -> Result<(), crate::errors::EncodeError> {
+                #encode
+                Ok(())
+            }
+
+            fn size_hint(&self) -> usize {
+                #size_hint
+            }
+        }
+
+        impl #impl_generics crate::packets::DynPacket for #struct_name #ty_generics #where_clause {
+            fn id(&self) -> i32 {
+                #packet_id
+            }
+
+            fn size_hint(&self) -> usize {
+                crate::encoder::Encoder::size_hint(self)
+            }
+
+            fn write_body(&self, buf: &mut Vec<u8>) -> Result<(), crate::errors::EncodeError> {
+                crate::encoder::Encoder::encode(self, buf)
+            }
+        }
+    })
 }
 
-#[proc_macro_derive(Receivable)]
+/// # Information
+/// Implements `ReceiveFromStream` for structs that are read without being a full `Packet`
+#[proc_macro_derive(Receivable, attributes(varint, varlong, max_len, rest, fixed_len, nbt, prefixed, skip_if))]
 pub fn derive_receivable(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
+    expand(receivable(&ast))
+}
+
+fn receivable(ast: &DeriveInput) -> Result<proc_macro2::TokenStream> {
     let struct_name = &ast.ident;
+    let fields = StructFields::parse(&expect_struct(ast, "Receivable")?.fields)?;
+    let decode = fields.decode(&Ident::new("cursor", Span::call_site()));
+    let constructor = fields.constructor();
 
-    match ast.data {
-        Data::Struct(data_struct) => match data_struct.fields {
-            Fields::Named(fields) => {
-                let fields = fields.named.iter().map(|field| {
-                    let field_name = &field.ident.clone().unwrap();
-                    let field_type = &field.ty;
-
-                    quote! {
-                        #field_name: <#field_type>::decode(cursor).await?,
-                    }
-                });
-
-                let gen = quote! {
-                    impl crate::decoder::ReceiveFromStream for #struct_name {
-                        async fn receive(cursor: &mut std::io::Cursor<Vec<u8>>) -> Result<Self, crate::errors::DecodeError> {
-                            Ok(Self { #(#fields)* })
-                        }
-                    }
-                };
-
-                gen.into()
-            }
-            _ => panic!("Expected a named field"),
-        },
-        _ => panic!("Expected a struct with named fields"),
+    let generics = with_bound(&ast.generics, |param| quote!(crate::decoder::Decoder<Output = #param>));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics crate::decoder::ReceiveFromStream for #struct_name #ty_generics #where_clause {
+            fn receive(cursor: &mut std::io::Cursor<Vec<u8>>) -> Result<Self, crate::errors::DecodeError> {
+                #decode
+                Ok(#constructor)
+            }
+        }
+    })
+}
+
+/// Turns an error into a `compile_error!` pointing at the offending tokens
+fn expand(result: Result<proc_macro2::TokenStream>) -> TokenStream {
+    result.unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expect_struct<'a>(ast: &'a DeriveInput, derive: &str) -> Result<&'a DataStruct> {
+    match &ast.data {
+        Data::Struct(data_struct) => Ok(data_struct),
+        Data::Enum(data_enum) => Err(Error::new_spanned(
+            data_enum.enum_token,
+            format!("{derive} can only be derived for structs"),
+        )),
+        Data::Union(data_union) => Err(Error::new_spanned(
+            data_union.union_token,
+            format!("{derive} can only be derived for structs"),
+        )),
     }
 }
+
+/// Requires `bound(T)` of every type parameter `T`
+fn with_bound(generics: &Generics, bound: impl Fn(&Ident) -> proc_macro2::TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<_> = generics.type_params().map(|param| param.ident.clone()).collect();
+
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        let bound = bound(&param);
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+
+    generics
+}
+
+/// Wire representation of a fieldless enum, taken from its `#[serialize_as(...)]` attribute
+enum EnumRepr {
+    /// Written as `wire`, which wraps the discriminant cast to `raw` for `VarInt` and `VarLong`
+    Integer {
+        wire: proc_macro2::TokenStream,
+        raw: proc_macro2::TokenStream,
+        wrapped: bool,
+    },
+    /// Written as the name of each variant
+    String(Vec<String>),
+}
+
+impl EnumRepr {
+    fn parse(enum_name: &Ident, attributes: &[Attribute], data_enum: &DataEnum) -> Result<Self> {
+        let attribute = attributes
+            .iter()
+            .find(|a| a.path().is_ident("serialize_as"))
+            .ok_or_else(|| Error::new_spanned(enum_name, "expected the wire type of the enum, e.g. #[serialize_as(VarInt)]"))?;
+        let wire: Type = attribute.parse_args()?;
+
+        for variant in &data_enum.variants {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new_spanned(&variant.fields, "only fieldless enums can be serialized"));
+            }
+        }
+
+        Ok(match &wire {
+            Type::Path(path) if path.path.is_ident("String") => {
+                EnumRepr::String(data_enum.variants.iter().map(variant_name).collect::<Result<_>>()?)
+            }
+            Type::Path(path) if path.path.is_ident("VarInt") => EnumRepr::Integer {
+                wire: quote!(crate::types::VarInt),
+                raw: quote!(i32),
+                wrapped: true,
+            },
+            Type::Path(path) if path.path.is_ident("VarLong") => EnumRepr::Integer {
+                wire: quote!(crate::types::VarLong),
+                raw: quote!(i64),
+                wrapped: true,
+            },
+            _ => EnumRepr::Integer {
+                raw: quote!(#wire),
+                wire: quote!(#wire),
+                wrapped: false,
+            },
+        })
+    }
+}
+
+/// The name given with `#[serialize_as("...")]`, or the variant in snake case
+fn variant_name(variant: &Variant) -> Result<String> {
+    if let Some(attribute) = variant.attrs.iter().find(|a| a.path().is_ident("serialize_as")) {
+        let name: LitStr = attribute
+            .parse_args()
+            .map_err(|err| Error::new(err.span(), "expected the name of the variant, e.g. #[serialize_as(\"name\")]"))?;
+        return Ok(name.value());
+    }
+
+    let mut name = String::new();
+    for (i, c) in variant.ident.to_string().chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.extend(c.to_lowercase());
+    }
+    Ok(name)
+}
+
+/// The bodies of `Encoder::encode` and `Encoder::size_hint` for a fieldless enum
+fn serialize_enum(
+    enum_name: &Ident,
+    attributes: &[Attribute],
+    data_enum: &DataEnum,
+) -> Result<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    let variants: Vec<_> = data_enum.variants.iter().map(|variant| &variant.ident).collect();
+
+    let value = match EnumRepr::parse(enum_name, attributes, data_enum)? {
+        EnumRepr::Integer { wire, raw, wrapped } => {
+            let value = if wrapped { quote!(#wire(value)) } else { quote!(value) };
+            quote! {
+                let value = match self { #(Self::#variants => Self::#variants as #raw,)* };
+                let value = #value;
+            }
+        }
+        EnumRepr::String(names) => quote! {
+            let value = match self { #(Self::#variants => #names,)* };
+        },
+    };
+
+    Ok((
+        quote! {
+            #value
+            crate::encoder::Encoder::encode(&value, writer)
+        },
+        quote! {
+            #value
+            crate::encoder::Encoder::size_hint(&value)
+        },
+    ))
+}
+
+/// The body of `Decoder::decode` for a fieldless enum
+fn deserialize_enum(enum_name: &Ident, attributes: &[Attribute], data_enum: &DataEnum) -> Result<proc_macro2::TokenStream> {
+    let variants: Vec<_> = data_enum.variants.iter().map(|variant| &variant.ident).collect();
+
+    Ok(match EnumRepr::parse(enum_name, attributes, data_enum)? {
+        EnumRepr::Integer { wire, raw, wrapped } => {
+            let value = if wrapped { quote!(.0) } else { quote!() };
+            quote! {
+                let value: #raw = <#wire as crate::decoder::Decoder>::decode(reader)?#value;
+                match value {
+                    #(value if value == Self::#variants as #raw => Ok(Self::#variants),)*
+                    value => Err(crate::errors::DecodeError::UnknownVariant {
+                        name: stringify!(#enum_name),
+                        value: value.to_string(),
+                    }),
+                }
+            }
+        }
+        EnumRepr::String(names) => quote! {
+            let value = <String as crate::decoder::Decoder>::decode(reader)?;
+            match value.as_str() {
+                #(#names => Ok(Self::#variants),)*
+                _ => Err(crate::errors::DecodeError::UnknownVariant {
+                    name: stringify!(#enum_name),
+                    value,
+                }),
+            }
+        },
+    })
+}
@@ -0,0 +1,25 @@
+// The derives refer to the server through `crate::`, so every case mirrors its modules at the root.
+#[allow(unused_imports)]
+mod decoder {
+    pub use server::decoder::*;
+}
+#[allow(unused_imports)]
+mod encoder {
+    pub use server::encoder::*;
+}
+#[allow(unused_imports)]
+mod errors {
+    pub use server::errors::*;
+}
+#[allow(unused_imports)]
+mod packets {
+    pub use server::packets::*;
+}
+#[allow(unused_imports)]
+mod types {
+    pub use server::types::*;
+}
+#[allow(unused_imports)]
+mod utils {
+    pub use server::utils::*;
+}
@@ -0,0 +1,29 @@
+use macros::{Deserializable, Serializable};
+
+include!("../crate_root.rs");
+
+#[derive(Serializable)]
+enum MissingRepr {
+    A,
+}
+
+#[derive(Deserializable)]
+#[serialize_as(VarInt)]
+enum WithFields {
+    A,
+    B(i32),
+}
+
+#[derive(Deserializable)]
+#[serialize_as(String)]
+enum UnnamedVariant {
+    #[serialize_as(nether)]
+    Nether,
+}
+
+#[derive(Serializable)]
+union Union {
+    a: u8,
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+use macros::{Deserializable, Serializable};
+
+include!("../crate_root.rs");
+
+#[derive(Serializable)]
+struct ConflictingCodecs {
+    #[varint]
+    #[varlong]
+    value: i32,
+}
+
+#[derive(Serializable)]
+struct PrefixedNotOption {
+    #[prefixed]
+    value: i32,
+}
+
+#[derive(Deserializable)]
+struct FixedLenNotArray(#[fixed_len] Vec<u8>);
+
+#[derive(Deserializable)]
+struct RestNotLast {
+    #[rest]
+    data: Vec<u8>,
+    after: bool,
+}
+
+#[derive(Serializable)]
+struct MaxLenWithoutLength {
+    #[max_len]
+    name: String,
+}
+
+#[derive(Serializable)]
+struct PrefixedAndSkipped {
+    flag: bool,
+    #[prefixed]
+    #[skip_if(!flag)]
+    value: Option<u8>,
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+use macros::Packet;
+
+include!("../crate_root.rs");
+
+#[derive(Packet)]
+struct MissingAttribute;
+
+#[derive(Packet)]
+#[packet(state = Play, direction = Clientbound)]
+struct MissingId;
+
+#[derive(Packet)]
+#[packet(id = 0x00, state = Play, direction = Clientbound, compressed = true)]
+struct UnknownKey;
+
+#[derive(Packet)]
+#[packet(id = "0x00", state = Play, direction = Clientbound)]
+struct StringId;
+
+#[derive(Packet)]
+#[packet(id = 0x00, state = Play, direction = Clientbound)]
+enum NotAStruct {
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use macros::Packet;
+
+include!("../crate_root.rs");
+
+#[derive(Packet)]
+#[packet(id = 0x00, state = Playing, direction = Clientbound)]
+struct UnknownState;
+
+fn main() {}
@@ -0,0 +1,33 @@
+use macros::{Deserializable, Serializable};
+use server::{decoder::Decoder, encoder::Encoder};
+
+include!("../crate_root.rs");
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+#[serialize_as(VarInt)]
+enum Hand {
+    Left,
+    Right = 300,
+}
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+#[serialize_as(String)]
+enum Dimension {
+    Overworld,
+    #[serialize_as("the_nether")]
+    Nether,
+}
+
+fn main() {
+    let mut buffer = vec![];
+    Hand::Right.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, [0xAC, 0x02]);
+    assert_eq!(Hand::Right.size_hint(), buffer.len());
+    assert_eq!(Hand::decode(&mut buffer.as_slice()).unwrap(), Hand::Right);
+
+    let mut buffer = vec![];
+    Dimension::Nether.encode(&mut buffer).unwrap();
+    assert_eq!(&buffer[1..], b"the_nether");
+    assert_eq!(Dimension::Nether.size_hint(), buffer.len());
+    assert_eq!(Dimension::decode(&mut buffer.as_slice()).unwrap(), Dimension::Nether);
+}
@@ -0,0 +1,30 @@
+use macros::{Deserializable, Serializable};
+use server::{decoder::Decoder, encoder::Encoder};
+use std::fmt::Debug;
+
+include!("../crate_root.rs");
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+struct Pair<K, V>
+where
+    K: Debug,
+{
+    key: K,
+    #[prefixed]
+    value: Option<V>,
+}
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+struct Wrapper<T>(Pair<T, T>)
+where
+    T: Debug;
+
+fn main() {
+    let wrapper = Wrapper(Pair { key: 1u8, value: Some(2u8) });
+
+    let mut buffer = vec![];
+    wrapper.encode(&mut buffer).unwrap();
+    assert_eq!(buffer, [1, 1, 2]);
+    assert_eq!(wrapper.size_hint(), buffer.len());
+    assert_eq!(Wrapper::decode(&mut buffer.as_slice()).unwrap(), wrapper);
+}
@@ -0,0 +1,28 @@
+use macros::Packet;
+use server::packets::{Direction, Packet, State};
+
+include!("../crate_root.rs");
+
+#[derive(Packet, Debug, PartialEq)]
+#[packet(id = 0x24, state = Play, direction = Clientbound)]
+struct KeepAlive(i64);
+
+#[derive(Packet, Debug, PartialEq)]
+#[packet(id = 0x00, state = Status, direction = Serverbound)]
+struct StatusRequest;
+
+fn main() {
+    assert_eq!(KeepAlive::ID, 0x24);
+    assert_eq!(KeepAlive::STATE, State::Play);
+    assert_eq!(KeepAlive::DIRECTION, Direction::Clientbound);
+
+    let mut buffer = vec![];
+    KeepAlive(42).encode_body(&mut buffer).unwrap();
+    assert_eq!(KeepAlive(42).body_size_hint(), 8);
+    assert_eq!(KeepAlive::decode_body(&mut buffer.as_slice()).unwrap(), KeepAlive(42));
+
+    let mut buffer = vec![];
+    StatusRequest.encode_body(&mut buffer).unwrap();
+    assert!(buffer.is_empty());
+    assert_eq!(StatusRequest::decode_body(&mut buffer.as_slice()).unwrap(), StatusRequest);
+}
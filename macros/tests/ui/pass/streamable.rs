@@ -0,0 +1,30 @@
+use macros::Streamable;
+use server::{
+    encoder::Encoder,
+    packets::DynPacket,
+    tcp::codec::FrameCodec,
+};
+
+include!("../crate_root.rs");
+
+#[derive(Streamable)]
+#[packet_id(0x1B)]
+struct Disconnect {
+    #[max_len(16)]
+    reason: String,
+}
+
+fn main() {
+    let packet = Disconnect { reason: String::from("bye") };
+    assert_eq!(packet.id(), 0x1B);
+
+    let mut body = vec![];
+    packet.encode(&mut body).unwrap();
+    assert_eq!(body, [3, b'b', b'y', b'e']);
+    assert_eq!(Encoder::size_hint(&packet), body.len());
+
+    // Sent like any other packet, compressed once the codec has a threshold
+    assert_eq!(FrameCodec::default().encode(&packet).unwrap(), [5, 0x1B, 3, b'b', b'y', b'e']);
+    let compressed = FrameCodec { compression: Some(256) }.encode(&packet).unwrap();
+    assert_eq!(compressed, [6, 0, 0x1B, 3, b'b', b'y', b'e']);
+}
@@ -0,0 +1,40 @@
+use macros::{Deserializable, Serializable};
+use server::{decoder::Decoder, encoder::Encoder};
+
+include!("../crate_root.rs");
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+struct Named {
+    #[varint]
+    id: i32,
+    #[max_len(16)]
+    name: String,
+}
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+struct Tuple(#[varint] i32, bool, #[skip_if(!*field_1)] Option<u8>);
+
+#[derive(Serializable, Deserializable, Debug, PartialEq)]
+struct Unit;
+
+fn round_trip<T: Encoder + Decoder<Output = T>>(value: &T) -> T {
+    let mut buffer = vec![];
+    value.encode(&mut buffer).unwrap();
+    assert_eq!(value.size_hint(), buffer.len());
+    T::decode(&mut buffer.as_slice()).unwrap()
+}
+
+fn main() {
+    let named = Named { id: 300, name: "Steve".to_string() };
+    assert_eq!(round_trip(&named), named);
+
+    let tuple = Tuple(-1, true, Some(7));
+    assert_eq!(round_trip(&tuple), tuple);
+    let tuple = Tuple(-1, false, None);
+    assert_eq!(round_trip(&tuple), tuple);
+
+    let mut buffer = vec![];
+    Unit.encode(&mut buffer).unwrap();
+    assert!(buffer.is_empty());
+    assert_eq!(round_trip(&Unit), Unit);
+}
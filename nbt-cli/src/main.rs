@@ -0,0 +1,280 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use nbt::{
+    io::{Compression, Nbt},
+    json::{Hint, JsonOptions},
+    path::write_name,
+    types::{Compound, Tag},
+    NbtPath,
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Nbt(#[from] nbt::Error),
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Inspects and converts NBT files
+#[derive(Parser)]
+#[command(name = "nbt", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints a NBT file, its compression is detected automatically
+    Dump {
+        file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t)]
+        format: Format,
+        /// Prints SNBT and JSON on a single line
+        #[arg(short, long)]
+        compact: bool,
+        #[command(flatten)]
+        json: JsonArgs,
+    },
+    /// Converts a SNBT or JSON file to NBT
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Format of the input, guessed from its extension when omitted
+        #[arg(long, value_enum)]
+        from: Option<Format>,
+        #[arg(long, value_enum, default_value_t)]
+        compression: CompressionArg,
+        /// Title of the root compound
+        #[arg(long, default_value = "")]
+        title: String,
+        #[command(flatten)]
+        json: JsonArgs,
+    },
+    /// Prints every tag a NBT path such as `foo.bar[0]` selects
+    Get {
+        file: PathBuf,
+        path: NbtPath,
+        #[arg(short, long, value_enum, default_value_t)]
+        format: Format,
+        #[command(flatten)]
+        json: JsonArgs,
+    },
+    /// Compares two NBT files structurally, exiting with 1 when they differ
+    Diff { a: PathBuf, b: PathBuf },
+}
+
+#[derive(Args)]
+struct JsonArgs {
+    /// Tag type for JSON values stored under a key, e.g. `Count=byte`, repeatable
+    #[arg(long = "hint", value_name = "KEY=TYPE", value_parser = parse_hint)]
+    hints: Vec<(String, Hint)>,
+    /// Converts JSON arrays of integers to int and long arrays instead of lists
+    #[arg(long)]
+    typed_arrays: bool,
+    /// Fails on JSON values without an obvious NBT counterpart instead of guessing
+    #[arg(long)]
+    strict: bool,
+}
+
+impl From<JsonArgs> for JsonOptions {
+    fn from(args: JsonArgs) -> Self {
+        JsonOptions {
+            hints: args.hints.into_iter().collect(),
+            typed_arrays: args.typed_arrays,
+            strict: args.strict,
+        }
+    }
+}
+
+fn parse_hint(value: &str) -> std::result::Result<(String, Hint), String> {
+    let (key, hint) = value.split_once('=').ok_or("expected KEY=TYPE")?;
+    Ok((key.to_string(), hint.parse().map_err(|err: nbt::Error| err.to_string())?))
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum Format {
+    #[default]
+    Snbt,
+    Json,
+    /// The indented layout of `Nbt`'s `Display` implementation
+    Tree,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum CompressionArg {
+    #[default]
+    None,
+    Gzip,
+    Zlib,
+}
+
+impl From<CompressionArg> for Compression {
+    fn from(value: CompressionArg) -> Self {
+        match value {
+            CompressionArg::None => Compression::Uncompressed,
+            CompressionArg::Gzip => Compression::Gzip,
+            CompressionArg::Zlib => Compression::Zlib,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command).await {
+        Ok(code) => code,
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+async fn run(command: Command) -> Result<ExitCode> {
+    match command {
+        Command::Dump {
+            file,
+            format,
+            compact,
+            json,
+        } => {
+            let nbt = read_nbt(&file).await?;
+            let output = match format {
+                Format::Tree => nbt.to_string(),
+                _ => format_tag(&Tag::from(nbt), format, compact, &json.into())?,
+            };
+            writeln!(io::stdout(), "{output}")?;
+        }
+        Command::Convert {
+            input,
+            output,
+            from,
+            compression,
+            title,
+            json,
+        } => {
+            let text = tokio::fs::read_to_string(&input).await?;
+            let format = from.unwrap_or_else(|| match input.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) if ext.eq_ignore_ascii_case("json") => Format::Json,
+                _ => Format::Snbt,
+            });
+            let mut nbt = match format {
+                Format::Json => Nbt::from_json(&serde_json::from_str(&text)?, &json.into())?,
+                _ => Nbt::from_snbt(&text)?,
+            };
+            nbt.set_title(title);
+            let mut bytes = Vec::new();
+            nbt.to_compressed_writer(&mut bytes, compression.into()).await?;
+            tokio::fs::write(&output, bytes).await?;
+        }
+        Command::Get { file, path, format, json } => {
+            let options = json.into();
+            let nbt = read_nbt(&file).await?;
+            let matches = nbt.get_path(&path);
+            if matches.is_empty() {
+                eprintln!("Nothing matched {path}");
+                return Ok(ExitCode::FAILURE);
+            }
+            for tag in matches {
+                writeln!(io::stdout(), "{}", format_tag(tag, format, false, &options)?)?;
+            }
+        }
+        Command::Diff { a, b } => {
+            let (a, b) = (read_nbt(&a).await?, read_nbt(&b).await?);
+            let mut differences = Vec::new();
+            if a.title() != b.title() {
+                differences.push(format!("~ (title): {:?} -> {:?}", a.title(), b.title()));
+            }
+            diff_compounds("", a.content(), b.content(), &mut differences);
+            for line in &differences {
+                writeln!(io::stdout(), "{line}")?;
+            }
+            if !differences.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn read_nbt(file: &Path) -> Result<Nbt> {
+    let bytes = tokio::fs::read(file).await?;
+    Ok(Nbt::from_any_reader(&mut bytes.as_slice()).await?)
+}
+
+fn format_tag(tag: &Tag, format: Format, compact: bool, options: &JsonOptions) -> Result<String> {
+    Ok(match (format, compact) {
+        (Format::Json, true) => serde_json::to_string(&tag.to_json(options)?)?,
+        (Format::Json, false) => serde_json::to_string_pretty(&tag.to_json(options)?)?,
+        (Format::Snbt, true) => tag.to_snbt(),
+        (Format::Snbt, false) => tag.to_snbt_pretty(),
+        (Format::Tree, _) => TreeTag(tag).to_string(),
+    })
+}
+
+/// A tag selected by `get` in the tree layout, unnamed like the elements of a list
+struct TreeTag<'a>(&'a Tag);
+
+impl fmt::Display for TreeTag<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(None): ", self.0.tag_name())?;
+        self.0.print(f, 0)
+    }
+}
+
+fn diff_tags(path: &str, a: &Tag, b: &Tag, out: &mut Vec<String>) {
+    match (a, b) {
+        (Tag::Compound(a), Tag::Compound(b)) => diff_compounds(path, a, b, out),
+        (Tag::List(a), Tag::List(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                let path = format!("{path}[{index}]");
+                match (a.get(index), b.get(index)) {
+                    (Some(a), Some(b)) => diff_tags(&path, a, b, out),
+                    (Some(a), None) => out.push(format!("- {path}: {}", a.to_snbt())),
+                    (None, Some(b)) => out.push(format!("+ {path}: {}", b.to_snbt())),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        _ if a != b => out.push(format!("~ {path}: {} -> {}", a.to_snbt(), b.to_snbt())),
+        _ => {}
+    }
+}
+
+fn diff_compounds(path: &str, a: &Compound, b: &Compound, out: &mut Vec<String>) {
+    for (key, a_value) in a {
+        let child = child_path(path, key);
+        match b.get(key) {
+            Some(b_value) => diff_tags(&child, a_value, b_value, out),
+            None => out.push(format!("- {child}: {}", a_value.to_snbt())),
+        }
+    }
+    for (key, b_value) in b {
+        if !a.contains_key(key) {
+            out.push(format!("+ {}: {}", child_path(path, key), b_value.to_snbt()));
+        }
+    }
+}
+
+/// Appends `key` to `path` using NBT path syntax, so the result can be passed to `get`
+fn child_path(path: &str, key: &str) -> String {
+    let mut child = path.to_string();
+    if !child.is_empty() {
+        child.push('.');
+    }
+    write_name(&mut child, key);
+    child
+}
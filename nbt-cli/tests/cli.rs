@@ -0,0 +1,141 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+use tempfile::TempDir;
+
+const PLAYER: &str = r#"{name: "Steve", "x y": 3, items: [{id: "stone", count: 1b}, {id: "dirt", count: 2b}], heights: [L; 1L, 2L]}"#;
+
+fn nbt(args: &[&str], dir: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_nbt"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// Writes `contents` to `name` and converts it to `<name>.nbt` with the extra `args`
+fn fixture(dir: &TempDir, name: &str, contents: &str, args: &[&str]) -> PathBuf {
+    fs::write(dir.path().join(name), contents).unwrap();
+    let output = format!("{name}.nbt");
+    stdout(&nbt(&[&["convert", name, &output], args].concat(), dir.path()));
+    dir.path().join(output)
+}
+
+#[test]
+fn convert_writes_the_requested_compression_and_title() {
+    let dir = TempDir::new().unwrap();
+    let file = fixture(&dir, "player.snbt", PLAYER, &["--compression", "gzip", "--title", "Player"]);
+    assert_eq!(fs::read(&file).unwrap()[..2], [0x1f, 0x8b]);
+
+    let tree = stdout(&nbt(&["dump", "player.snbt.nbt", "--format", "tree"], dir.path()));
+    assert!(tree.starts_with("TAG_Compound(\"Player\"): 4 entry(ies)\n"), "{tree}");
+    assert!(tree.contains("  TAG_LongArray(\"heights\"): [1, 2]\n"), "{tree}");
+}
+
+#[test]
+fn dump_prints_snbt_and_json() {
+    let dir = TempDir::new().unwrap();
+    fixture(&dir, "player.snbt", PLAYER, &[]);
+
+    assert_eq!(
+        stdout(&nbt(&["dump", "player.snbt.nbt", "-c"], dir.path())),
+        "{name:\"Steve\",\"x y\":3,items:[{id:\"stone\",count:1b},{id:\"dirt\",count:2b}],heights:[L;1L,2L]}\n"
+    );
+    assert_eq!(
+        stdout(&nbt(&["dump", "player.snbt.nbt", "-c", "-f", "json"], dir.path())),
+        "{\"name\":\"Steve\",\"x y\":3,\"items\":[{\"id\":\"stone\",\"count\":1},{\"id\":\"dirt\",\"count\":2}],\"heights\":[1,2]}\n"
+    );
+}
+
+#[test]
+fn convert_applies_json_options() {
+    let dir = TempDir::new().unwrap();
+    let json = r#"{"items": [{"id": "stone", "count": 1}], "heights": [1, 2, 3], "new": true}"#;
+    fixture(&dir, "player.json", json, &["--hint", "count=byte", "--typed-arrays"]);
+
+    assert_eq!(
+        stdout(&nbt(&["dump", "player.json.nbt", "-c"], dir.path())),
+        "{items:[{id:\"stone\",count:1b}],heights:[I;1,2,3],new:1b}\n"
+    );
+
+    let strict = nbt(&["convert", "player.json", "out.nbt", "--strict", "--from", "json"], dir.path());
+    assert!(strict.status.success());
+    fs::write(dir.path().join("null.json"), r#"{"a": null}"#).unwrap();
+    let strict = nbt(&["convert", "null.json", "out.nbt", "--strict"], dir.path());
+    assert_eq!(strict.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&strict.stderr).starts_with("error: "));
+}
+
+#[test]
+fn get_prints_every_match() {
+    let dir = TempDir::new().unwrap();
+    fixture(&dir, "player.snbt", PLAYER, &["--compression", "zlib"]);
+    let get = |args: &[&str]| stdout(&nbt(&[&["get", "player.snbt.nbt"], args].concat(), dir.path()));
+
+    assert_eq!(get(&["items[].id"]), "\"stone\"\n\"dirt\"\n");
+    assert_eq!(get(&["items[{id:\"dirt\"}].count"]), "2b\n");
+    assert_eq!(get(&["items[-1].id", "-f", "json"]), "\"dirt\"\n");
+    assert_eq!(get(&["\"x y\""]), "3\n");
+    assert_eq!(
+        get(&["items[0]", "-f", "tree"]),
+        "TAG_Compound(None): 2 entry(ies)\n{\n  TAG_String(id): stone\n  TAG_Byte(count): 1\n}\n"
+    );
+
+    let missing = nbt(&["get", "player.snbt.nbt", "items[5]"], dir.path());
+    assert_eq!(missing.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&missing.stderr), "Nothing matched items[5]\n");
+}
+
+#[test]
+fn diff_lists_changes_with_paths_get_accepts() {
+    let dir = TempDir::new().unwrap();
+    fixture(&dir, "a.snbt", PLAYER, &[]);
+    fixture(
+        &dir,
+        "b.snbt",
+        r#"{name: "Alex", items: [{id: "stone", count: 1b}], heights: [I; 1, 2], "a.b": 1b}"#,
+        &[],
+    );
+
+    let same = nbt(&["diff", "a.snbt.nbt", "a.snbt.nbt"], dir.path());
+    assert_eq!(same.status.code(), Some(0));
+    assert!(same.stdout.is_empty());
+
+    let diff = nbt(&["diff", "a.snbt.nbt", "b.snbt.nbt"], dir.path());
+    assert_eq!(diff.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(diff.stdout).unwrap(),
+        [
+            "~ name: \"Steve\" -> \"Alex\"",
+            "- \"x y\": 3",
+            "- items[1]: {id:\"dirt\",count:2b}",
+            "~ heights: [L;1L,2L] -> [I;1,2]",
+            "+ \"a.b\": 1b",
+            "",
+        ]
+        .join("\n")
+    );
+
+    // The paths of the changes select the changed tags
+    assert_eq!(stdout(&nbt(&["get", "a.snbt.nbt", "\"x y\""], dir.path())), "3\n");
+    assert_eq!(stdout(&nbt(&["get", "b.snbt.nbt", "\"a.b\""], dir.path())), "1b\n");
+}
+
+#[test]
+fn unreadable_files_are_errors() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("garbage.nbt"), [0x0a, 0x00]).unwrap();
+
+    for args in [["dump", "missing.nbt"], ["dump", "garbage.nbt"]] {
+        let output = nbt(&args, dir.path());
+        assert_eq!(output.status.code(), Some(2));
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    }
+}
@@ -0,0 +1,62 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use nbt::{io::Nbt, types::Tag, NbtRef};
+
+const DIMENSION_CODEC: &[u8] = include_bytes!("../../dimension_codec.nbt");
+
+/// Roughly shaped like an anvil chunk: a few sections with large packed long arrays
+fn chunk() -> Vec<u8> {
+    let sections = (0..24)
+        .map(|y| {
+            Tag::Compound(
+                [
+                    ("Y".to_string(), Tag::Byte(y)),
+                    ("BlockStates".to_string(), Tag::LongArray((0..256).collect())),
+                    ("BlockLight".to_string(), Tag::ByteArray(vec![0; 2048])),
+                    ("SkyLight".to_string(), Tag::ByteArray(vec![15; 2048])),
+                ]
+                .into_iter()
+                .collect(),
+            )
+        })
+        .collect();
+    let nbt = Nbt::new(
+        "",
+        [
+            ("DataVersion", Tag::Int(3700)),
+            ("Status", Tag::String("minecraft:full".to_string())),
+            ("Heightmaps", Tag::IntArray(vec![64; 256])),
+            ("sections", Tag::List(sections)),
+        ],
+    );
+
+    let mut bytes = Vec::new();
+    runtime().block_on(nbt.to_writer(&mut bytes)).unwrap();
+    bytes
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread().build().unwrap()
+}
+
+fn parse(c: &mut Criterion) {
+    let runtime = runtime();
+    let inputs = [("dimension_codec", DIMENSION_CODEC.to_vec()), ("chunk", chunk())];
+    let mut group = c.benchmark_group("parse");
+
+    for (name, bytes) in &inputs {
+        group.bench_with_input(BenchmarkId::new("from_reader", name), bytes, |b, bytes| {
+            b.iter(|| runtime.block_on(Nbt::from_reader(&mut black_box(&bytes[..]))).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("from_slice", name), bytes, |b, bytes| {
+            b.iter(|| Nbt::from_slice(black_box(bytes)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("NbtRef::new", name), bytes, |b, bytes| {
+            b.iter(|| NbtRef::new(black_box(bytes)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
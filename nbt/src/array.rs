@@ -0,0 +1,62 @@
+//! Serde has no notion of NBT's typed arrays, a plain `Vec<i32>` becomes a `TAG_List` of `TAG_Int`s.
+//! Wrap the field in one of these types, or use `#[serde(with = "nbt::array::int_array")]`, to get a `TAG_Int_Array` instead.
+use crate::types::Tag;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub(crate) const BYTE_ARRAY_TOKEN: &str = "__nbt_byte_array";
+pub(crate) const INT_ARRAY_TOKEN: &str = "__nbt_int_array";
+pub(crate) const LONG_ARRAY_TOKEN: &str = "__nbt_long_array";
+
+macro_rules! typed_array (
+    ($name: ident, $module: ident, $type: ty, $token: ident) => (
+        #[derive(Clone, Debug, Default, PartialEq, Eq)]
+        pub struct $name(pub Vec<$type>);
+
+        impl From<Vec<$type>> for $name {
+            fn from(value: Vec<$type>) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for Vec<$type> {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl From<$name> for Tag {
+            fn from(value: $name) -> Self {
+                Tag::$name(value.0)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $module::serialize(&self.0, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $module::deserialize(deserializer).map(Self)
+            }
+        }
+
+        pub mod $module {
+            use super::$token;
+            use serde::{Deserialize, Deserializer, Serializer};
+
+            pub fn serialize<S: Serializer>(value: &[$type], serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_newtype_struct($token, value)
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<$type>, D::Error> {
+                Vec::<$type>::deserialize(deserializer)
+            }
+        }
+    )
+);
+
+typed_array!(ByteArray, byte_array, i8, BYTE_ARRAY_TOKEN);
+typed_array!(IntArray, int_array, i32, INT_ARRAY_TOKEN);
+typed_array!(LongArray, long_array, i64, LONG_ARRAY_TOKEN);
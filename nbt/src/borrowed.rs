@@ -0,0 +1,365 @@
+//! Zero-copy view over NBT bytes. Nothing is allocated when the view is created, strings and arrays are only
+//! decoded once they are accessed.
+use crate::{
+    error::{Error, Result},
+    io::Nbt,
+    limits::NbtLimits,
+    slice::{decode_string, SliceReader},
+    types::{Compound, Tag},
+};
+use std::{borrow::Cow, fmt, marker::PhantomData};
+
+/// Element types of the typed arrays
+pub trait ArrayElement: Copy + 'static {
+    const WIDTH: usize;
+
+    fn from_be_chunk(chunk: &[u8]) -> Self;
+}
+
+macro_rules! array_element (
+    ($type: ty) => (
+        impl ArrayElement for $type {
+            const WIDTH: usize = std::mem::size_of::<$type>();
+
+            #[inline]
+            fn from_be_chunk(chunk: &[u8]) -> Self {
+                <$type>::from_be_bytes(chunk.try_into().expect("chunks_exact yields chunks of WIDTH bytes"))
+            }
+        }
+    )
+);
+
+array_element!(i8);
+array_element!(i32);
+array_element!(i64);
+
+/// Decodes a whole big-endian array at once instead of element by element
+pub(crate) fn decode_array<T: ArrayElement>(bytes: &[u8]) -> Vec<T> {
+    bytes.chunks_exact(T::WIDTH).map(T::from_be_chunk).collect()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct NbtRef<'a> {
+    title: StrRef<'a>,
+    root: CompoundRef<'a>,
+}
+
+impl<'a> NbtRef<'a> {
+    /// Walks the input once to validate its structure, so that the view can be traversed without errors afterwards.
+    /// Strings are not validated until they are accessed.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        Self::with_limits(bytes, NbtLimits::default())
+    }
+
+    /// Input accepted with some limits can be decoded into owned tags with the same limits
+    pub fn with_limits(bytes: &'a [u8], limits: NbtLimits) -> Result<Self> {
+        let mut reader = SliceReader::new(bytes, limits);
+        let id = reader.read_u8()?;
+
+        if id != 0x0a {
+            return Err(Error::InvalidRoot(id));
+        }
+
+        let title = StrRef(reader.read_string_bytes()?);
+        let root = CompoundRef::read(&mut reader)?;
+
+        Ok(Self { title, root })
+    }
+
+    pub fn title(&self) -> StrRef<'a> {
+        self.title
+    }
+
+    pub fn root(&self) -> CompoundRef<'a> {
+        self.root
+    }
+
+    pub fn get(&self, name: &str) -> Option<TagRef<'a>> {
+        self.root.get(name)
+    }
+
+    pub fn to_nbt(&self) -> Result<Nbt> {
+        Ok(Nbt {
+            title: self.title.to_str()?.into_owned(),
+            content: self.root.to_compound()?,
+        })
+    }
+}
+
+/// Modified UTF-8 bytes of a string
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct StrRef<'a>(&'a [u8]);
+
+impl<'a> StrRef<'a> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Only allocates if the string contains characters that are encoded differently in modified UTF-8
+    pub fn to_str(&self) -> Result<Cow<'a, str>> {
+        decode_string(self.0)
+    }
+}
+
+impl fmt::Debug for StrRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to_str() {
+            Ok(s) => write!(f, "{s:?}"),
+            Err(_) => write!(f, "{:?}", self.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TagRef<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(StrRef<'a>),
+    List(ListRef<'a>),
+    Compound(CompoundRef<'a>),
+    ByteArray(ArrayRef<'a, i8>),
+    IntArray(ArrayRef<'a, i32>),
+    LongArray(ArrayRef<'a, i64>),
+}
+
+impl<'a> TagRef<'a> {
+    fn read(reader: &mut SliceReader<'a>, id: u8) -> Result<Self> {
+        Ok(match id {
+            0x01 => TagRef::Byte(reader.read_i8()?),
+            0x02 => TagRef::Short(reader.read_i16()?),
+            0x03 => TagRef::Int(reader.read_i32()?),
+            0x04 => TagRef::Long(reader.read_i64()?),
+            0x05 => TagRef::Float(reader.read_f32()?),
+            0x06 => TagRef::Double(reader.read_f64()?),
+            0x07 => TagRef::ByteArray(ArrayRef::new(reader.read_array_bytes(1)?)),
+            0x08 => TagRef::String(StrRef(reader.read_string_bytes()?)),
+            0x09 => TagRef::List(ListRef::read(reader)?),
+            0x0a => TagRef::Compound(CompoundRef::read(reader)?),
+            0x0b => TagRef::IntArray(ArrayRef::new(reader.read_array_bytes(4)?)),
+            0x0c => TagRef::LongArray(ArrayRef::new(reader.read_array_bytes(8)?)),
+            _ => return Err(Error::UnknownTagId(id)),
+        })
+    }
+
+    pub fn id(&self) -> u8 {
+        match self {
+            TagRef::Byte(_) => 0x01,
+            TagRef::Short(_) => 0x02,
+            TagRef::Int(_) => 0x03,
+            TagRef::Long(_) => 0x04,
+            TagRef::Float(_) => 0x05,
+            TagRef::Double(_) => 0x06,
+            TagRef::ByteArray(_) => 0x07,
+            TagRef::String(_) => 0x08,
+            TagRef::List(_) => 0x09,
+            TagRef::Compound(_) => 0x0A,
+            TagRef::IntArray(_) => 0x0B,
+            TagRef::LongArray(_) => 0x0C,
+        }
+    }
+
+    /// Decodes the whole subtree into an owned `Tag`
+    pub fn to_tag(&self) -> Result<Tag> {
+        Ok(match self {
+            TagRef::Byte(v) => Tag::Byte(*v),
+            TagRef::Short(v) => Tag::Short(*v),
+            TagRef::Int(v) => Tag::Int(*v),
+            TagRef::Long(v) => Tag::Long(*v),
+            TagRef::Float(v) => Tag::Float(*v),
+            TagRef::Double(v) => Tag::Double(*v),
+            TagRef::String(v) => Tag::String(v.to_str()?.into_owned()),
+            TagRef::List(v) => SliceReader::new(v.bytes, NbtLimits::UNLIMITED).decode_list(v.id, v.len)?,
+            TagRef::Compound(v) => Tag::Compound(v.to_compound()?),
+            TagRef::ByteArray(v) => Tag::ByteArray(v.to_vec()),
+            TagRef::IntArray(v) => Tag::IntArray(v.to_vec()),
+            TagRef::LongArray(v) => Tag::LongArray(v.to_vec()),
+        })
+    }
+}
+
+/// Big-endian bytes of a typed array
+#[derive(Clone, Copy)]
+pub struct ArrayRef<'a, T> {
+    bytes: &'a [u8],
+    _element: PhantomData<T>,
+}
+
+impl<'a, T: ArrayElement> ArrayRef<'a, T> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            _element: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len() / T::WIDTH
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<T> {
+        let start = index.checked_mul(T::WIDTH)?;
+        self.bytes.get(start..start.checked_add(T::WIDTH)?).map(T::from_be_chunk)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        self.bytes.chunks_exact(T::WIDTH).map(T::from_be_chunk)
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        decode_array(self.bytes)
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<T: ArrayElement + fmt::Debug> fmt::Debug for ArrayRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ListRef<'a> {
+    id: u8,
+    len: usize,
+    bytes: &'a [u8],
+}
+
+impl<'a> ListRef<'a> {
+    fn read(reader: &mut SliceReader<'a>) -> Result<Self> {
+        let id = reader.read_u8()?;
+        let len = reader.read_len()?;
+        let start = reader.position();
+
+        if len > 0 && id == 0x00 {
+            return Err(Error::UnknownTagId(id));
+        }
+
+        for _ in 0..len {
+            reader.skip(id)?;
+        }
+
+        Ok(Self {
+            id,
+            len,
+            bytes: reader.since(start),
+        })
+    }
+
+    /// Tag id of the elements, `0x00` for empty lists
+    pub fn element_id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Each call walks the elements from the start, lists and compounds among them are walked once more when they
+    /// are accessed
+    pub fn iter(&self) -> ListIter<'a> {
+        ListIter {
+            id: self.id,
+            remaining: self.len,
+            // Already validated against the limits the view was created with
+            reader: SliceReader::new(self.bytes, NbtLimits::UNLIMITED),
+        }
+    }
+}
+
+pub struct ListIter<'a> {
+    id: u8,
+    remaining: usize,
+    reader: SliceReader<'a>,
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = TagRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        // The bytes have been validated when the list was read
+        TagRef::read(&mut self.reader, self.id).ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Entries of a compound, including the trailing `TAG_End`
+#[derive(Clone, Copy, Debug)]
+pub struct CompoundRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> CompoundRef<'a> {
+    fn read(reader: &mut SliceReader<'a>) -> Result<Self> {
+        let start = reader.position();
+        reader.skip(0x0a)?;
+
+        Ok(Self {
+            bytes: reader.since(start),
+        })
+    }
+
+    /// Each call walks the entries from the start, skipping over the values before the ones returned
+    pub fn iter(&self) -> CompoundIter<'a> {
+        CompoundIter {
+            // Already validated against the limits the view was created with
+            reader: SliceReader::new(self.bytes, NbtLimits::UNLIMITED),
+        }
+    }
+
+    /// Looks up an entry by comparing the raw bytes of the names, nothing is decoded except the match.
+    ///
+    /// Offsets of the entries are not kept, so a lookup walks every entry before the match, including their nested
+    /// values. Chained lookups like `a.get("b")?.get("c")` walk the subtree of `b` once for each level, which gets
+    /// quadratic for deeply nested input. Decode into a [`Compound`] when looking up many entries.
+    pub fn get(&self, name: &str) -> Option<TagRef<'a>> {
+        let name = cesu8::to_java_cesu8(name);
+        self.iter().find(|(key, _)| key.as_bytes() == &name[..]).map(|(_, value)| value)
+    }
+
+    pub fn to_compound(&self) -> Result<Compound> {
+        match SliceReader::new(self.bytes, NbtLimits::UNLIMITED).decode(0x0a)? {
+            Tag::Compound(compound) => Ok(compound),
+            other => Err(Error::InvalidRoot(other.id())),
+        }
+    }
+}
+
+pub struct CompoundIter<'a> {
+    reader: SliceReader<'a>,
+}
+
+impl<'a> Iterator for CompoundIter<'a> {
+    type Item = (StrRef<'a>, TagRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The bytes have been validated when the compound was read
+        let id = self.reader.read_u8().ok().filter(|&id| id != 0x00)?;
+        let name = StrRef(self.reader.read_string_bytes().ok()?);
+        let value = TagRef::read(&mut self.reader, id).ok()?;
+
+        Some((name, value))
+    }
+}
@@ -0,0 +1,365 @@
+//! Serde `Deserializer` reading any `Deserialize` type out of a `Tag`, mirroring the rules in [`crate::ser`]
+use crate::{
+    array::{BYTE_ARRAY_TOKEN, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN},
+    error::{Error, Result},
+    io::Nbt,
+    limits::MAX_PREALLOCATION,
+    types::{Compound, Tag},
+};
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any, Deserialize,
+};
+use std::fmt::Display;
+
+pub fn from_tag<T>(tag: Tag) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(tag)
+}
+
+/// The title of the `Nbt` is ignored
+pub fn from_nbt<T>(nbt: Nbt) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(Tag::Compound(nbt.content))
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl Tag {
+    fn invalid_type(&self, expected: &dyn de::Expected) -> Error {
+        de::Error::invalid_type(de::Unexpected::Other(self.tag_name()), expected)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Tag {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! deserialize_unsigned (
+    ($name: ident, $visit: ident, $variant: ident, $type: ty) => (
+        fn $name<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+            match self {
+                Tag::$variant(v) => visitor.$visit(v as $type),
+                other => other.deserialize_any(visitor),
+            }
+        }
+    )
+);
+
+/// Typed arrays visit a single entry map keyed by their array token when deserialized through `deserialize_any`,
+/// so that `Tag`'s own `Deserialize` impl can tell them apart from lists. Everything else asking for a sequence
+/// gets a plain sequence of the elements.
+impl<'de> de::Deserializer<'de> for Tag {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::Byte(v) => visitor.visit_i8(v),
+            Tag::Short(v) => visitor.visit_i16(v),
+            Tag::Int(v) => visitor.visit_i32(v),
+            Tag::Long(v) => visitor.visit_i64(v),
+            Tag::Float(v) => visitor.visit_f32(v),
+            Tag::Double(v) => visitor.visit_f64(v),
+            Tag::String(v) => visitor.visit_string(v),
+            Tag::List(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            Tag::Compound(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            array @ (Tag::ByteArray(_) | Tag::IntArray(_) | Tag::LongArray(_)) => visitor.visit_map(ArrayAccess { array: Some(array) }),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::Byte(v) => visitor.visit_bool(v != 0),
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    deserialize_unsigned!(deserialize_u8, visit_u8, Byte, u8);
+    deserialize_unsigned!(deserialize_u16, visit_u16, Short, u16);
+    deserialize_unsigned!(deserialize_u32, visit_u32, Int, u32);
+    deserialize_unsigned!(deserialize_u64, visit_u64, Long, u64);
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::String(ref v) => {
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => visitor.visit_char(c),
+                    _ => Err(de::Error::invalid_value(de::Unexpected::Str(v), &visitor)),
+                }
+            }
+            other => other.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::ByteArray(v) => visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect()),
+            other => other.deserialize_seq(visitor),
+        }
+    }
+
+    /// Missing compound fields are turned into `None` by serde itself, so a present tag is always `Some`
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::Compound(_) => visitor.visit_unit(),
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::List(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter())),
+            Tag::ByteArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(Tag::Byte))),
+            Tag::IntArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(Tag::Int))),
+            Tag::LongArray(v) => visitor.visit_seq(SeqDeserializer::new(v.into_iter().map(Tag::Long))),
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::Compound(v) => visitor.visit_map(MapDeserializer::new(v.into_iter())),
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        match self {
+            Tag::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Tag::Compound(map) if map.len() == 1 => {
+                let (variant, value) = map.into_iter().next().expect("Compound has exactly one entry");
+                visitor.visit_enum(Variant { variant, value })
+            }
+            other => Err(other.invalid_type(&visitor)),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 str string identifier ignored_any
+    }
+}
+
+struct ArrayAccess {
+    array: Option<Tag>,
+}
+
+impl<'de> MapAccess<'de> for ArrayAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let token = match self.array {
+            Some(Tag::ByteArray(_)) => BYTE_ARRAY_TOKEN,
+            Some(Tag::IntArray(_)) => INT_ARRAY_TOKEN,
+            Some(Tag::LongArray(_)) => LONG_ARRAY_TOKEN,
+            _ => return Ok(None),
+        };
+
+        seed.deserialize(IntoDeserializer::<Error>::into_deserializer(token)).map(Some)
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        match self.array.take() {
+            Some(array) => seed.deserialize(SeqOnly(array)),
+            None => Err(Error::Message("next_value_seed called before next_key_seed".into())),
+        }
+    }
+}
+
+/// Hands out the elements of a typed array even to `deserialize_any`
+struct SeqOnly(Tag);
+
+impl<'de> de::Deserializer<'de> for SeqOnly {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.0.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Variant {
+    variant: String,
+    value: Tag,
+}
+
+impl<'de> EnumAccess<'de> for Variant {
+    type Error = Error;
+    type Variant = Tag;
+
+    fn variant_seed<V: de::DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Tag {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+struct TagVisitor;
+
+impl<'de> Visitor<'de> for TagVisitor {
+    type Value = Tag;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any NBT value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> std::result::Result<Tag, E> {
+        Ok(Tag::Byte(v as i8))
+    }
+
+    fn visit_i8<E>(self, v: i8) -> std::result::Result<Tag, E> {
+        Ok(Tag::Byte(v))
+    }
+
+    fn visit_i16<E>(self, v: i16) -> std::result::Result<Tag, E> {
+        Ok(Tag::Short(v))
+    }
+
+    fn visit_i32<E>(self, v: i32) -> std::result::Result<Tag, E> {
+        Ok(Tag::Int(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> std::result::Result<Tag, E> {
+        Ok(Tag::Long(v))
+    }
+
+    fn visit_u8<E>(self, v: u8) -> std::result::Result<Tag, E> {
+        Ok(Tag::Byte(v as i8))
+    }
+
+    fn visit_u16<E>(self, v: u16) -> std::result::Result<Tag, E> {
+        Ok(Tag::Short(v as i16))
+    }
+
+    fn visit_u32<E>(self, v: u32) -> std::result::Result<Tag, E> {
+        Ok(Tag::Int(v as i32))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> std::result::Result<Tag, E> {
+        Ok(Tag::Long(v as i64))
+    }
+
+    fn visit_f32<E>(self, v: f32) -> std::result::Result<Tag, E> {
+        Ok(Tag::Float(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> std::result::Result<Tag, E> {
+        Ok(Tag::Double(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> std::result::Result<Tag, E> {
+        Ok(Tag::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> std::result::Result<Tag, E> {
+        Ok(Tag::String(v))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> std::result::Result<Tag, E> {
+        Ok(Tag::ByteArray(v.iter().map(|&b| b as i8).collect()))
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> std::result::Result<Tag, D::Error> {
+        Tag::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Tag, A::Error> {
+        // The hint comes from whatever is being deserialized and isn't trusted any more than a list length is
+        let mut values = Vec::with_capacity(seq.size_hint().unwrap_or_default().min(MAX_PREALLOCATION));
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+
+        Ok(Tag::List(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Tag, A::Error> {
+        let mut compound = Compound::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                BYTE_ARRAY_TOKEN => return Ok(Tag::ByteArray(map.next_value()?)),
+                INT_ARRAY_TOKEN => return Ok(Tag::IntArray(map.next_value()?)),
+                LONG_ARRAY_TOKEN => return Ok(Tag::LongArray(map.next_value()?)),
+                _ => {
+                    compound.insert(key, map.next_value()?);
+                }
+            }
+        }
+
+        Ok(Tag::Compound(compound))
+    }
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(TagVisitor)
+    }
+}
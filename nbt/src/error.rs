@@ -0,0 +1,39 @@
+use crate::limits::Limit;
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error while reading or writing data")]
+    IOError(#[from] io::Error),
+    #[error("Unknown tag id {0:#04x}")]
+    UnknownTagId(u8),
+    #[error("Unexpected root tag id {0:#04x}")]
+    InvalidRoot(u8),
+    #[error("String is not valid modified UTF-8 (CESU-8)")]
+    InvalidMutf8,
+    #[error("String is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("VarInt is too long")]
+    InvalidVarInt,
+    #[error("List elements must all be of the same type, expected tag id {expected:#04x} but found {found:#04x}")]
+    MixedListTypes { expected: u8, found: u8 },
+    #[error("String of {0} bytes is too long for its length prefix")]
+    StringTooLong(usize),
+    #[error("List or array of {0} elements is too long for its length prefix")]
+    ListTooLong(usize),
+    #[error("Input ended unexpectedly at byte offset {offset}")]
+    UnexpectedEof { offset: u64 },
+    #[error("Invalid SNBT at line {line}, column {column}: {reason}")]
+    InvalidSnbt { line: usize, column: usize, reason: String },
+    #[error("Invalid NBT path at column {column}: {reason}")]
+    InvalidPath { column: usize, reason: String },
+    #[error("Cannot convert JSON at {path}: {reason}")]
+    InvalidJson { path: String, reason: String },
+    #[error("{limit} exceeds the limit of {max}")]
+    LimitExceeded { limit: Limit, max: u64 },
+    #[error("{0}")]
+    Message(String),
+}
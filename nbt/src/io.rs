@@ -1,27 +1,75 @@
 #![allow(private_bounds)]
-use super::types::{NbtReader, NbtWriter, Tag};
-use async_recursion::async_recursion;
-use flate2::{
-    read::{GzDecoder, ZlibDecoder},
-    write::{GzEncoder, ZlibEncoder},
-    Compression,
+use super::types::{list_id, Compound, NbtReader, NbtWriter, Tag};
+use crate::{
+    error::{Error, Result},
+    limits::NbtLimits,
+    visit::{EventReader, TreeBuilder},
+};
+use async_compression::tokio::{
+    bufread::{GzipDecoder, ZlibDecoder},
+    write::{GzipEncoder, ZlibEncoder},
 };
+use async_recursion::async_recursion;
 use std::{
-    collections::HashMap,
-    fmt,
-    io::{self, Result},
+    fmt, io,
+    pin::Pin,
+    task::{ready, Context, Poll},
 };
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, ReadBuf};
+
+/// Compression formats NBT files are commonly stored in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    Uncompressed,
+    /// Used by `level.dat`, `playerdata/*.dat` and structure files
+    Gzip,
+    /// Used by chunks inside region files
+    Zlib,
+}
+
+impl Compression {
+    /// Guesses the compression from the first bytes of a file, falling back to `Uncompressed`
+    pub fn detect(header: &[u8]) -> Self {
+        match header {
+            [0x1f, 0x8b, ..] => Compression::Gzip,
+            [cmf, flg, ..] if cmf & 0x0f == 0x08 && ((*cmf as u16) << 8 | *flg as u16) % 31 == 0 => Compression::Zlib,
+            _ => Compression::Uncompressed,
+        }
+    }
+}
+
+macro_rules! get_accessor (
+    ($name: ident, $accessor: ident, $type: ty) => (
+        pub fn $name(&self, name: &str) -> Option<$type> {
+            self.get(name)?.$accessor()
+        }
+    )
+);
+
+/// Byte order and integer encoding of the binary format. The synchronous [`Nbt::from_slice`] and borrowed
+/// [`NbtRef`](crate::NbtRef) readers only support `Java`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Big-endian with modified UTF-8 strings
+    #[default]
+    Java,
+    /// Little-endian with UTF-8 strings, used by Bedrock worlds
+    BedrockLittleEndian,
+    /// Like `BedrockLittleEndian`, but ints, longs and lengths are zigzag varints. Used by Bedrock packets.
+    BedrockNetwork,
+}
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Nbt {
-    title: String,
-    content: HashMap<String, Tag>,
+    pub(crate) title: String,
+    pub(crate) content: Compound,
 }
 impl Nbt {
-    pub fn new<S>(title: S, content: HashMap<S, Tag>) -> Self
+    pub fn new<S, I>(title: S, content: I) -> Self
     where
         S: Into<String>,
+        I: IntoIterator<Item = (S, Tag)>,
     {
         Self {
             title: title.into(),
@@ -29,24 +77,111 @@ impl Nbt {
         }
     }
 
-    pub async fn to_writer<W>(&self, writer: &mut W) -> io::Result<()>
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn set_title<S: Into<String>>(&mut self, title: S) {
+        self.title = title.into();
+    }
+
+    pub fn content(&self) -> &Compound {
+        &self.content
+    }
+
+    pub fn into_content(self) -> Compound {
+        self.content
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.content.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.content.get_mut(name)
+    }
+
+    get_accessor!(get_i8, as_i8, i8);
+    get_accessor!(get_i16, as_i16, i16);
+    get_accessor!(get_i32, as_i32, i32);
+    get_accessor!(get_i64, as_i64, i64);
+    get_accessor!(get_f32, as_f32, f32);
+    get_accessor!(get_f64, as_f64, f64);
+    get_accessor!(get_bool, as_bool, bool);
+    get_accessor!(get_str, as_str, &str);
+    get_accessor!(get_list, as_list, &Vec<Tag>);
+    get_accessor!(get_compound, as_compound, &Compound);
+    get_accessor!(get_byte_array, as_byte_array, &Vec<i8>);
+    get_accessor!(get_int_array, as_int_array, &Vec<i32>);
+    get_accessor!(get_long_array, as_long_array, &Vec<i64>);
+
+    pub fn get_list_mut(&mut self, name: &str) -> Option<&mut Vec<Tag>> {
+        self.get_mut(name)?.as_list_mut()
+    }
+
+    pub fn get_compound_mut(&mut self, name: &str) -> Option<&mut Compound> {
+        self.get_mut(name)?.as_compound_mut()
+    }
+
+    /// Replaces and returns the previous value if there was one, new entries are appended
+    pub fn insert<S, T>(&mut self, name: S, value: T) -> Option<Tag>
+    where
+        S: Into<String>,
+        T: Into<Tag>,
+    {
+        self.content.insert(name.into(), value.into())
+    }
+
+    /// Keeps the order of the remaining entries
+    pub fn remove(&mut self, name: &str) -> Option<Tag> {
+        self.content.shift_remove(name)
+    }
+
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.content.contains_key(name)
+    }
+
+    pub fn iter(&self) -> indexmap::map::Iter<'_, String, Tag> {
+        self.content.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> indexmap::map::IterMut<'_, String, Tag> {
+        self.content.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    pub async fn to_writer<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + NbtWriter + Unpin + Send,
+    {
+        self.to_encoded_writer(writer, Encoding::Java).await
+    }
+
+    pub async fn to_encoded_writer<W>(&self, writer: &mut W, encoding: Encoding) -> Result<()>
     where
         W: AsyncWrite + NbtWriter + Unpin + Send,
     {
         writer.write_u8(0x0a).await?;
-        writer.write_bare_string(&self.title).await?;
+        writer.write_bare_string(&self.title, encoding).await?;
 
         for (name, nbt) in self.content.iter() {
             writer.write_u8(nbt.id()).await?;
-            writer.write_bare_string(name).await?;
-            nbt.encode(writer).await?;
+            writer.write_bare_string(name, encoding).await?;
+            nbt.encode(writer, encoding).await?;
         }
 
-        Ok(writer.close_nbt().await?)
+        writer.close_nbt().await
     }
 
     /// Networked NBT's are missing the title of the root `TAG_COMPOUND`
-    pub async fn to_networked_writer<W>(&self, writer: &mut W) -> io::Result<()>
+    pub async fn to_networked_writer<W>(&self, writer: &mut W) -> Result<()>
     where
         W: AsyncWrite + NbtWriter + Unpin + Send,
     {
@@ -54,47 +189,191 @@ impl Nbt {
 
         for (name, nbt) in self.content.iter() {
             writer.write_u8(nbt.id()).await?;
-            writer.write_bare_string(name).await?;
-            nbt.encode(writer).await?;
+            writer.write_bare_string(name, Encoding::Java).await?;
+            nbt.encode(writer, Encoding::Java).await?;
         }
 
-        Ok(writer.close_nbt().await?)
+        writer.close_nbt().await
+    }
+
+    /// The whole stream is compressed in memory first, so `writer` is never shut down
+    pub async fn to_gzip_writer<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut encoder = GzipEncoder::new(vec![]);
+        self.to_writer(&mut encoder).await?;
+        encoder.shutdown().await?;
+
+        Ok(writer.write_all(&encoder.into_inner()).await?)
+    }
+
+    /// The whole stream is compressed in memory first, so `writer` is never shut down
+    pub async fn to_zlib_writer<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut encoder = ZlibEncoder::new(vec![]);
+        self.to_writer(&mut encoder).await?;
+        encoder.shutdown().await?;
+
+        Ok(writer.write_all(&encoder.into_inner()).await?)
     }
 
-    // pub async fn to_zlib_writer<W: AsyncWrite + NbtWriter>(&mut self, writer: &mut W) -> io::Result<()> {
-    //     Ok(self.to_writer(&mut GzEncoder::new(writer, Compression::default())).await?)
-    // }
+    pub async fn to_compressed_writer<W>(&self, writer: &mut W, compression: Compression) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        match compression {
+            Compression::Uncompressed => self.to_writer(writer).await,
+            Compression::Gzip => self.to_gzip_writer(writer).await,
+            Compression::Zlib => self.to_zlib_writer(writer).await,
+        }
+    }
 
-    // pub async fn to_gzip_writer<W: AsyncWrite + NbtWriter>(&self, writer: &mut W) -> io::Result<()> {
-    //     Ok(self.to_writer(&mut ZlibEncoder::new(writer, Compression::default())).await?)
-    // }
+    pub async fn from_reader<R>(reader: &mut R) -> Result<Nbt>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        Self::from_reader_with_limits(reader, NbtLimits::default()).await
+    }
 
-    pub async fn from_reader<R>(reader: &mut R) -> io::Result<Nbt>
+    pub async fn from_reader_with_limits<R>(reader: &mut R, limits: NbtLimits) -> Result<Nbt>
     where
         R: AsyncRead + NbtReader + Unpin + Send,
     {
-        let (tag, title) = reader.emit_next_header().await?;
+        Self::from_encoded_reader_with_limits(reader, Encoding::Java, limits).await
+    }
+
+    pub async fn from_encoded_reader<R>(reader: &mut R, encoding: Encoding) -> Result<Nbt>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        Self::from_encoded_reader_with_limits(reader, encoding, NbtLimits::default()).await
+    }
+
+    pub async fn from_encoded_reader_with_limits<R>(reader: &mut R, encoding: Encoding, limits: NbtLimits) -> Result<Nbt>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        let mut builder = TreeBuilder::default();
+        Self::visit_encoded_reader_with_limits(reader, encoding, limits, &mut builder).await?;
+
+        match builder.finish() {
+            (title, Some(Tag::Compound(content))) => Ok(Nbt { title, content }),
+            (_, other) => Err(Error::InvalidRoot(other.map_or(0x00, |tag| tag.id()))),
+        }
+    }
+
+    /// Counterpart to `to_networked_writer`, the root has to be a `TAG_Compound`. Use [`Tag::from_networked_reader`]
+    /// for values which may have a root of any type.
+    pub async fn from_networked_reader<R>(reader: &mut R) -> Result<Nbt>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        Self::from_networked_reader_with_limits(reader, NbtLimits::default()).await
+    }
+
+    pub async fn from_networked_reader_with_limits<R>(reader: &mut R, limits: NbtLimits) -> Result<Nbt>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        match Tag::from_networked_reader_with_limits(reader, limits).await? {
+            Some(Tag::Compound(content)) => Ok(Nbt {
+                title: String::new(),
+                content,
+            }),
+            Some(other) => Err(Error::InvalidRoot(other.id())),
+            None => Err(Error::InvalidRoot(0x00)),
+        }
+    }
+
+    pub async fn from_gzip_reader<R>(reader: &mut R) -> Result<Nbt>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Self::from_gzip_reader_with_limits(reader, NbtLimits::default()).await
+    }
+
+    /// The limits apply to the decompressed data, like for [`Nbt::from_any_reader_with_limits`]
+    pub async fn from_gzip_reader_with_limits<R>(reader: &mut R, limits: NbtLimits) -> Result<Nbt>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Self::from_reader_with_limits(&mut GzipDecoder::new(BufReader::new(reader)), limits).await
+    }
+
+    pub async fn from_zlib_reader<R>(reader: &mut R) -> Result<Nbt>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Self::from_zlib_reader_with_limits(reader, NbtLimits::default()).await
+    }
+
+    /// The limits apply to the decompressed data, like for [`Nbt::from_any_reader_with_limits`]
+    pub async fn from_zlib_reader_with_limits<R>(reader: &mut R, limits: NbtLimits) -> Result<Nbt>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Self::from_reader_with_limits(&mut ZlibDecoder::new(BufReader::new(reader)), limits).await
+    }
+
+    /// Reads an uncompressed, gzip or zlib compressed NBT, detecting the compression from its magic bytes
+    pub async fn from_any_reader<R>(reader: &mut R) -> Result<Nbt>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        Self::from_any_reader_with_limits(reader, NbtLimits::default()).await
+    }
+
+    /// The limits apply to the decompressed data, so they also guard against compression bombs
+    pub async fn from_any_reader_with_limits<R>(reader: &mut R, limits: NbtLimits) -> Result<Nbt>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let mut reader = BufReader::new(reader);
 
-        if tag != 0x0a {
-            panic!("TODO HANDLE ERROR! Must start with Root compound");
+        match Compression::detect(reader.fill_buf().await?) {
+            Compression::Uncompressed => Self::from_reader_with_limits(&mut reader, limits).await,
+            Compression::Gzip => Self::from_reader_with_limits(&mut GzipDecoder::new(reader), limits).await,
+            Compression::Zlib => Self::from_reader_with_limits(&mut ZlibDecoder::new(reader), limits).await,
         }
+    }
+}
 
-        let content = Tag::decode(tag, reader).await;
-        match content {
-            Ok(Tag::Compound(map)) => Ok(Nbt { title, content: map }),
-            _ => panic!("TODO HANDLE ERROR! NO ROOT COMPONENT"),
+/// The title is left empty, as for networked NBT's
+impl From<Compound> for Nbt {
+    fn from(content: Compound) -> Self {
+        Self {
+            title: String::new(),
+            content,
         }
     }
+}
+
+/// The title is dropped
+impl From<Nbt> for Tag {
+    fn from(value: Nbt) -> Self {
+        Tag::Compound(value.content)
+    }
+}
+
+impl<'a> IntoIterator for &'a Nbt {
+    type Item = (&'a String, &'a Tag);
+    type IntoIter = indexmap::map::Iter<'a, String, Tag>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.content.iter()
+    }
+}
 
-    // pub async fn from_zlib_reader<R: AsyncRead + NbtReader>(reader: &mut R) -> io::Result<Nbt> {
-    //     let mut zlib = ZlibDecoder::new(reader);
-    //     Self::from_reader(&mut zlib)
-    // }
+impl IntoIterator for Nbt {
+    type Item = (String, Tag);
+    type IntoIter = indexmap::map::IntoIter<String, Tag>;
 
-    // pub async fn from_gzip_reader<R: AsyncRead + NbtReader>(reader: &mut R) -> io::Result<Nbt> {
-    //     let mut zlib = GzDecoder::new(reader);
-    //     Self::from_reader(&mut zlib)
-    // }
+    fn into_iter(self) -> Self::IntoIter {
+        self.content.into_iter()
+    }
 }
 
 impl fmt::Display for Nbt {
@@ -127,6 +406,43 @@ impl Tag {
         }
     }
 
+    /// Since 1.20.2 networked NBT's have no root name and the root may be of any type (e.g. a bare `TAG_String` for
+    /// text components). A root of `TAG_End` is how the protocol sends an absent value and is returned as `None`.
+    pub async fn from_networked_reader<R>(reader: &mut R) -> Result<Option<Tag>>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        Self::from_networked_reader_with_limits(reader, NbtLimits::default()).await
+    }
+
+    pub async fn from_networked_reader_with_limits<R>(reader: &mut R, limits: NbtLimits) -> Result<Option<Tag>>
+    where
+        R: AsyncRead + NbtReader + Unpin + Send,
+    {
+        let mut reader = OffsetReader::new(reader);
+        let result = match reader.read_u8().await {
+            Ok(0x00) => Ok(None),
+            Ok(id) => {
+                let mut builder = TreeBuilder::default();
+                let result = EventReader::new(&mut reader, Encoding::Java, limits)
+                    .read_value(id, &mut builder)
+                    .await;
+                result.map(|()| builder.finish().1)
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        reader.locate(result)
+    }
+
+    pub async fn to_networked_writer<W>(&self, writer: &mut W) -> Result<()>
+    where
+        W: AsyncWrite + NbtWriter + Unpin + Send,
+    {
+        writer.write_u8(self.id()).await?;
+        self.encode(writer, Encoding::Java).await
+    }
+
     pub fn tag_name(&self) -> &str {
         match *self {
             Tag::Byte(_) => "TAG_Byte",
@@ -145,87 +461,41 @@ impl Tag {
     }
 
     #[async_recursion()]
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> io::Result<()> {
-        Ok(match self {
+    async fn encode<W>(&self, writer: &mut W, encoding: Encoding) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        match self {
             Tag::Byte(value) => writer.write_bare_byte(*value).await?,
-            Tag::Short(value) => writer.write_bare_short(*value).await?,
-            Tag::Int(value) => writer.write_bare_int(*value).await?,
-            Tag::Long(value) => writer.write_bare_long(*value).await?,
-            Tag::Float(value) => writer.write_bare_float(*value).await?,
-            Tag::Double(value) => writer.write_bare_double(*value).await?,
-            Tag::String(value) => writer.write_bare_string(value).await?,
+            Tag::Short(value) => writer.write_bare_short(*value, encoding).await?,
+            Tag::Int(value) => writer.write_bare_int(*value, encoding).await?,
+            Tag::Long(value) => writer.write_bare_long(*value, encoding).await?,
+            Tag::Float(value) => writer.write_bare_float(*value, encoding).await?,
+            Tag::Double(value) => writer.write_bare_double(*value, encoding).await?,
+            Tag::String(value) => writer.write_bare_string(value, encoding).await?,
             Tag::List(values) => {
-                if values.is_empty() {
-                    writer.write_u8(0).await?; // TAG_End
-                    writer.write_i32(0).await?; // Length of the list
-                } else {
-                    let first_id = values[0].id();
+                writer.write_u8(list_id(values)?).await?;
+                writer.write_len(values.len(), encoding).await?;
 
-                    writer.write_u8(first_id).await?;
-                    writer.write_i32(values.len() as i32).await?;
-
-                    for nbt in values {
-                        if nbt.id() != first_id {
-                            todo!("HANDLE PANIC!");
-                        }
-
-                        nbt.encode(writer).await?;
-                    }
+                for nbt in values {
+                    nbt.encode(writer, encoding).await?;
                 }
             }
             Tag::Compound(values) => {
                 for (name, nbt) in values {
                     writer.write_u8(nbt.id()).await?;
-                    writer.write_bare_string(name).await?;
-                    nbt.encode(writer).await?;
+                    writer.write_bare_string(name, encoding).await?;
+                    nbt.encode(writer, encoding).await?;
                 }
 
                 writer.close_nbt().await?;
             }
-            Tag::ByteArray(values) => writer.write_bare_byte_array(&values[..]).await?,
-            Tag::IntArray(values) => writer.write_bare_int_array(&values[..]).await?,
-            Tag::LongArray(values) => writer.write_bare_long_array(&values[..]).await?,
-        })
-    }
-
-    #[async_recursion()]
-    async fn decode<R: AsyncRead + Unpin + Send>(id: u8, reader: &mut R) -> Result<Tag> {
-        Ok(match id {
-            0x01 => Tag::Byte(reader.read_bare_byte().await?),
-            0x02 => Tag::Short(reader.read_bare_short().await?),
-            0x03 => Tag::Int(reader.read_bare_int().await?),
-            0x04 => Tag::Long(reader.read_bare_long().await?),
-            0x05 => Tag::Float(reader.read_bare_float().await?),
-            0x06 => Tag::Double(reader.read_bare_double().await?),
-            0x07 => Tag::ByteArray(reader.read_bare_byte_array().await?),
-            0x08 => Tag::String(reader.read_bare_string().await?),
-            0x09 => {
-                let id = reader.read_u8().await?;
-                let len = reader.read_i32().await? as usize;
-                let mut buf = Vec::with_capacity(len);
-                for _ in 0..len {
-                    buf.push(Tag::decode(id, reader).await?);
-                }
-                Tag::List(buf)
-            }
-            0x0a => {
-                let mut map = HashMap::new();
-                loop {
-                    let (id, name) = reader.emit_next_header().await?;
-
-                    if id.eq(&0x00) {
-                        break;
-                    }
+            Tag::ByteArray(values) => writer.write_bare_byte_array(&values[..], encoding).await?,
+            Tag::IntArray(values) => writer.write_bare_int_array(&values[..], encoding).await?,
+            Tag::LongArray(values) => writer.write_bare_long_array(&values[..], encoding).await?,
+        }
 
-                    let tag = Tag::decode(id, reader).await?;
-                    map.insert(name, tag);
-                }
-                Tag::Compound(map)
-            }
-            0x0b => Tag::IntArray(reader.read_bare_int_array().await?),
-            0x0c => Tag::LongArray(reader.read_bare_long_array().await?),
-            _ => panic!("TODO HANDLE ERROR! INVALID TYPE ID "),
-        })
+        Ok(())
     }
 
     pub fn print(&self, f: &mut fmt::Formatter, offset: usize) -> fmt::Result {
@@ -280,3 +550,97 @@ impl Tag {
         }
     }
 }
+
+/// Keeps track of how many bytes have been consumed so truncated input can be reported with its offset
+pub(crate) struct OffsetReader<'a, R> {
+    inner: &'a mut R,
+    offset: u64,
+}
+
+impl<'a, R> OffsetReader<'a, R> {
+    pub(crate) fn new(inner: &'a mut R) -> Self {
+        Self { inner, offset: 0 }
+    }
+
+    /// Attaches the current offset to errors caused by the input ending too early
+    pub(crate) fn locate<T>(&self, result: Result<T>) -> Result<T> {
+        match result {
+            Err(Error::IOError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => Err(Error::UnexpectedEof { offset: self.offset }),
+            other => other,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for OffsetReader<'_, R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        ready!(Pin::new(&mut *self.inner).poll_read(cx, buf))?;
+        self.offset += (buf.filled().len() - filled) as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player() -> Nbt {
+        Nbt::new(
+            "Player",
+            [
+                ("Health", Tag::Float(20.0)),
+                ("Score", Tag::Int(7)),
+                (
+                    "Abilities",
+                    Tag::from(Compound::from_iter([(String::from("flying"), Tag::from(false))])),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn typed_getters_return_none_for_other_types_and_missing_keys() {
+        let nbt = player();
+        assert_eq!(nbt.get_i32("Score"), Some(7));
+        assert_eq!(nbt.get_i32("Health"), None);
+        assert_eq!(nbt.get_i32("Missing"), None);
+        assert_eq!(nbt.get_f32("Health"), Some(20.0));
+
+        assert_eq!(
+            nbt.get_compound("Abilities").and_then(|abilities| abilities.get("flying")),
+            Some(&Tag::Byte(0))
+        );
+        assert_eq!(nbt.get_compound("Score"), None);
+        assert_eq!(nbt.get_compound("Missing"), None);
+        assert_eq!(nbt.get_bool("Score"), None);
+    }
+
+    #[test]
+    fn entries_keep_their_insertion_order() {
+        let mut nbt = player();
+        assert_eq!(nbt.insert("Level", 3), None);
+        // Replacing a value keeps its place, removing one keeps the order of the rest
+        assert_eq!(nbt.insert("Health", 10f32), Some(Tag::Float(20.0)));
+        assert_eq!(nbt.remove("Score"), Some(Tag::Int(7)));
+        assert_eq!(nbt.remove("Score"), None);
+
+        let keys: Vec<_> = nbt.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["Health", "Abilities", "Level"]);
+        assert_eq!(nbt.get_f32("Health"), Some(10.0));
+        assert_eq!(nbt.len(), 3);
+        assert!(nbt.contains_key("Level"));
+    }
+
+    #[test]
+    fn titles_are_kept_apart_from_the_content() {
+        let mut nbt = player();
+        assert_eq!(nbt.title(), "Player");
+        nbt.set_title("Renamed");
+        assert_eq!(nbt.title(), "Renamed");
+
+        let content = nbt.content().clone();
+        assert_eq!(Tag::from(nbt), Tag::Compound(content.clone()));
+        assert_eq!(Nbt::from(content).title(), "");
+    }
+}
@@ -0,0 +1,629 @@
+//! Conversion between JSON and NBT, e.g. for text components or registry data coming from data packs
+//!
+//! JSON has a single number type while NBT has six, so [`JsonOptions`] decides which tag types JSON values become.
+//! Without hints integers become ints (or longs when they do not fit), other numbers become doubles, booleans become
+//! bytes and arrays become lists.
+use crate::{
+    error::{Error, Result},
+    io::Nbt,
+    path::write_name,
+    types::{Compound, Tag},
+};
+use serde_json::{Map, Number, Value};
+use std::{collections::HashMap, fmt::Write, str::FromStr};
+
+/// Tag type a JSON value is converted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    Byte,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    ByteArray,
+    IntArray,
+    LongArray,
+}
+
+impl FromStr for Hint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "byte" => Hint::Byte,
+            "short" => Hint::Short,
+            "int" => Hint::Int,
+            "long" => Hint::Long,
+            "float" => Hint::Float,
+            "double" => Hint::Double,
+            "byte_array" => Hint::ByteArray,
+            "int_array" => Hint::IntArray,
+            "long_array" => Hint::LongArray,
+            _ => return Err(Error::Message(format!("Unknown tag type `{s}`"))),
+        })
+    }
+}
+
+/// How JSON values are typed when converting them to NBT
+#[derive(Clone, Debug, Default)]
+pub struct JsonOptions {
+    /// Tag types of the values stored under a key, for arrays the hint applies to their elements unless it names an
+    /// array type
+    pub hints: HashMap<String, Hint>,
+    /// Turns arrays of integers without a hint into int or long arrays instead of lists
+    pub typed_arrays: bool,
+    /// Fails instead of guessing on values without an obvious NBT counterpart: nulls, non finite floats, lists mixing
+    /// types, integers that do not fit in a long and hints that do not fit their value
+    pub strict: bool,
+}
+
+impl JsonOptions {
+    pub fn strict() -> Self {
+        JsonOptions {
+            strict: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn hint<S: Into<String>>(mut self, key: S, hint: Hint) -> Self {
+        self.hints.insert(key.into(), hint);
+        self
+    }
+}
+
+/// Converts a JSON value to a tag, leniently skipping nulls unless `options` is strict
+pub fn from_value(value: &Value, options: &JsonOptions) -> Result<Tag> {
+    let mut converter = Converter {
+        options,
+        path: String::new(),
+    };
+
+    match converter.convert(value, None)? {
+        Some(tag) => Ok(tag),
+        None => Err(converter.error("null has no NBT equivalent")),
+    }
+}
+
+/// Converts a tag to a JSON value, typed arrays become plain arrays and non finite floats become nulls unless
+/// `options` is strict
+pub fn to_value(tag: &Tag, options: &JsonOptions) -> Result<Value> {
+    let mut converter = Converter {
+        options,
+        path: String::new(),
+    };
+
+    converter.tag_to_value(tag)
+}
+
+impl Tag {
+    pub fn from_json(value: &Value, options: &JsonOptions) -> Result<Tag> {
+        from_value(value, options)
+    }
+
+    pub fn to_json(&self, options: &JsonOptions) -> Result<Value> {
+        to_value(self, options)
+    }
+}
+
+impl Nbt {
+    /// The root of the JSON must be an object, the title of the resulting `Nbt` is empty
+    pub fn from_json(value: &Value, options: &JsonOptions) -> Result<Nbt> {
+        match from_value(value, options)? {
+            Tag::Compound(content) => Ok(Nbt {
+                title: String::new(),
+                content,
+            }),
+            other => Err(Error::InvalidRoot(other.id())),
+        }
+    }
+
+    /// The title is not part of the JSON and gets dropped
+    pub fn to_json(&self, options: &JsonOptions) -> Result<Value> {
+        let mut converter = Converter {
+            options,
+            path: String::new(),
+        };
+
+        converter.compound_to_value(&self.content)
+    }
+}
+
+struct Converter<'a> {
+    options: &'a JsonOptions,
+    /// Location of the value being converted, in NBT path syntax
+    path: String,
+}
+
+impl Converter<'_> {
+    fn error<S: Into<String>>(&self, reason: S) -> Error {
+        Error::InvalidJson {
+            path: if self.path.is_empty() {
+                String::from("(root)")
+            } else {
+                self.path.clone()
+            },
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns `None` for nulls that are skipped
+    fn convert(&mut self, value: &Value, hint: Option<Hint>) -> Result<Option<Tag>> {
+        if let Some(hint) = hint {
+            if let Some(tag) = self.apply_hint(value, hint)? {
+                return Ok(Some(tag));
+            }
+        }
+
+        Ok(Some(match value {
+            Value::Null if self.options.strict => return Err(self.error("null has no NBT equivalent")),
+            Value::Null => return Ok(None),
+            Value::Bool(value) => Tag::Byte(*value as i8),
+            Value::Number(number) => self.number(number)?,
+            Value::String(value) => Tag::String(value.clone()),
+            Value::Array(values) => self.list(values, None)?,
+            Value::Object(map) => Tag::Compound(self.compound(map)?),
+        }))
+    }
+
+    /// Returns `None` when the hint does not fit the value and leniency allows falling back to the inferred type
+    fn apply_hint(&mut self, value: &Value, hint: Hint) -> Result<Option<Tag>> {
+        let tag = match (value, hint) {
+            (Value::Array(values), Hint::ByteArray | Hint::IntArray | Hint::LongArray) if values.iter().all(Value::is_array) => {
+                Some(self.list(values, Some(hint))?)
+            }
+            (Value::Array(values), Hint::ByteArray) => integers(values).map(Tag::ByteArray),
+            (Value::Array(values), Hint::IntArray) => integers(values).map(Tag::IntArray),
+            (Value::Array(values), Hint::LongArray) => integers(values).map(Tag::LongArray),
+            (Value::Array(values), _) => Some(self.list(values, Some(hint))?),
+            (_, Hint::Byte) => integer(value).map(Tag::Byte),
+            (_, Hint::Short) => integer(value).map(Tag::Short),
+            (_, Hint::Int) => integer(value).map(Tag::Int),
+            (_, Hint::Long) => integer(value).map(Tag::Long),
+            // Doubles past f32::MAX would silently become infinite floats
+            (Value::Number(number), Hint::Float) => number
+                .as_f64()
+                .filter(|value| value.is_finite() && (*value as f32).is_finite())
+                .map(|value| Tag::Float(value as f32)),
+            (Value::Number(number), Hint::Double) => number.as_f64().map(Tag::Double),
+            _ => None,
+        };
+
+        match tag {
+            None if self.options.strict => Err(self.error(format!("{} does not fit the {hint:?} hint", describe(value)))),
+            tag => Ok(tag),
+        }
+    }
+
+    fn number(&self, number: &Number) -> Result<Tag> {
+        if let Some(value) = number.as_i64() {
+            Ok(i32::try_from(value).map_or(Tag::Long(value), Tag::Int))
+        } else if number.is_u64() && self.options.strict {
+            Err(self.error(format!("{number} does not fit in a long")))
+        } else {
+            Ok(Tag::Double(number.as_f64().unwrap_or_default()))
+        }
+    }
+
+    fn list(&mut self, values: &[Value], hint: Option<Hint>) -> Result<Tag> {
+        let mut tags = Vec::with_capacity(values.len());
+        for (index, value) in values.iter().enumerate() {
+            let len = self.path.len();
+            let _ = write!(self.path, "[{index}]");
+            let tag = self.convert(value, hint)?;
+            self.path.truncate(len);
+            tags.extend(tag);
+        }
+
+        let tags = self.unify(tags)?;
+        if hint.is_none() && self.options.typed_arrays && !tags.is_empty() {
+            if let Some(values) = tags.iter().map(Tag::as_i32).collect::<Option<Vec<_>>>() {
+                return Ok(Tag::IntArray(values));
+            }
+            if let Some(values) = tags.iter().map(Tag::as_i64).collect::<Option<Vec<_>>>() {
+                return Ok(Tag::LongArray(values));
+            }
+        }
+
+        Ok(Tag::List(tags))
+    }
+
+    /// Gives the elements of a list a single type, widening integers to the largest type among them, integers mixed
+    /// with doubles to doubles and wrapping anything else in compounds with an empty key like vanilla does
+    fn unify(&self, tags: Vec<Tag>) -> Result<Vec<Tag>> {
+        let Some(first) = tags.first() else {
+            return Ok(tags);
+        };
+        let id = first.id();
+        if tags.iter().all(|tag| tag.id() == id) {
+            return Ok(tags);
+        }
+
+        if tags.iter().all(|tag| integer_rank(tag).is_some()) {
+            let widest = tags.iter().filter_map(integer_rank).max().unwrap_or_default();
+            return Ok(tags.into_iter().map(|tag| widen(&tag, widest)).collect());
+        }
+
+        let numeric = tags.iter().all(|tag| integer_value(tag).is_some() || matches!(tag, Tag::Double(_)));
+        if self.options.strict {
+            return Err(self.error(if numeric {
+                String::from("list mixes integers and decimals")
+            } else {
+                format!(
+                    "list mixes {} and {}",
+                    first.tag_name(),
+                    tags.iter().find(|tag| tag.id() != id).unwrap().tag_name()
+                )
+            }));
+        }
+
+        Ok(if numeric {
+            tags.iter()
+                .map(|tag| Tag::Double(integer_value(tag).map_or_else(|| tag.as_f64().unwrap_or_default(), |value| value as f64)))
+                .collect()
+        } else {
+            tags.into_iter()
+                .map(|tag| match tag {
+                    Tag::Compound(_) => tag,
+                    tag => Tag::Compound(Compound::from_iter([(String::new(), tag)])),
+                })
+                .collect()
+        })
+    }
+
+    fn compound(&mut self, map: &Map<String, Value>) -> Result<Compound> {
+        let mut compound = Compound::new();
+        for (key, value) in map {
+            let len = self.path.len();
+            self.push_key(key);
+            let tag = self.convert(value, self.options.hints.get(key).copied())?;
+            self.path.truncate(len);
+
+            if let Some(tag) = tag {
+                compound.insert(key.clone(), tag);
+            }
+        }
+
+        Ok(compound)
+    }
+
+    fn push_key(&mut self, key: &str) {
+        if !self.path.is_empty() {
+            self.path.push('.');
+        }
+        write_name(&mut self.path, key);
+    }
+
+    fn tag_to_value(&mut self, tag: &Tag) -> Result<Value> {
+        Ok(match tag {
+            Tag::Byte(value) => Value::from(*value),
+            Tag::Short(value) => Value::from(*value),
+            Tag::Int(value) => Value::from(*value),
+            Tag::Long(value) => Value::from(*value),
+            // Going through the shortest representation keeps 0.1f from turning into 0.10000000149011612
+            Tag::Float(value) => self.float(value.to_string().parse().unwrap_or(f64::NAN))?,
+            Tag::Double(value) => self.float(*value)?,
+            Tag::String(value) => Value::String(value.clone()),
+            Tag::List(values) => {
+                let mut out = Vec::with_capacity(values.len());
+                for (index, value) in values.iter().enumerate() {
+                    let len = self.path.len();
+                    let _ = write!(self.path, "[{index}]");
+                    out.push(self.tag_to_value(value)?);
+                    self.path.truncate(len);
+                }
+                Value::Array(out)
+            }
+            Tag::Compound(compound) => self.compound_to_value(compound)?,
+            Tag::ByteArray(values) => Value::from_iter(values.iter().copied()),
+            Tag::IntArray(values) => Value::from_iter(values.iter().copied()),
+            Tag::LongArray(values) => Value::from_iter(values.iter().copied()),
+        })
+    }
+
+    fn compound_to_value(&mut self, compound: &Compound) -> Result<Value> {
+        let mut map = Map::with_capacity(compound.len());
+        for (key, value) in compound {
+            let len = self.path.len();
+            self.push_key(key);
+            map.insert(key.clone(), self.tag_to_value(value)?);
+            self.path.truncate(len);
+        }
+
+        Ok(Value::Object(map))
+    }
+
+    fn float(&self, value: f64) -> Result<Value> {
+        match Number::from_f64(value) {
+            Some(number) => Ok(Value::Number(number)),
+            None if self.options.strict => Err(self.error(format!("{value} cannot be represented in JSON"))),
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+/// Integers that fit in `T`, booleans counting as 0 and 1
+fn integer<T: TryFrom<i64>>(value: &Value) -> Option<T> {
+    match value {
+        Value::Bool(value) => T::try_from(*value as i64).ok(),
+        Value::Number(number) => number.as_i64().and_then(|value| T::try_from(value).ok()),
+        _ => None,
+    }
+}
+
+fn integers<T: TryFrom<i64>>(values: &[Value]) -> Option<Vec<T>> {
+    values.iter().map(integer).collect()
+}
+
+fn integer_rank(tag: &Tag) -> Option<u8> {
+    match tag {
+        Tag::Byte(_) => Some(0),
+        Tag::Short(_) => Some(1),
+        Tag::Int(_) => Some(2),
+        Tag::Long(_) => Some(3),
+        _ => None,
+    }
+}
+
+fn integer_value(tag: &Tag) -> Option<i64> {
+    match *tag {
+        Tag::Byte(value) => Some(value as i64),
+        Tag::Short(value) => Some(value as i64),
+        Tag::Int(value) => Some(value as i64),
+        Tag::Long(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn widen(tag: &Tag, rank: u8) -> Tag {
+    let value = integer_value(tag).unwrap_or_default();
+    match rank {
+        0 => Tag::Byte(value as i8),
+        1 => Tag::Short(value as i16),
+        2 => Tag::Int(value as i32),
+        _ => Tag::Long(value),
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => String::from("null"),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::String(_) => String::from("a string"),
+        Value::Array(_) => String::from("an array"),
+        Value::Object(_) => String::from("an object"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn compound(value: Value, options: &JsonOptions) -> Result<Compound> {
+        Nbt::from_json(&value, options).map(|nbt| nbt.content)
+    }
+
+    fn error_path(result: Result<impl std::fmt::Debug>) -> String {
+        match result {
+            Err(Error::InvalidJson { path, .. }) => path,
+            other => panic!("expected a JSON error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn values_without_hints_get_inferred_types() {
+        let content = compound(
+            json!({
+                "int": 1,
+                "long": 1i64 << 40,
+                "double": 0.5,
+                "bool": true,
+                "text": "hi",
+                "list": [1, 2],
+                "nested": { "skipped": null },
+            }),
+            &JsonOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            Nbt::from(content),
+            crate::nbt! {
+                "int": 1,
+                "long": 1i64 << 40,
+                "double": 0.5,
+                "bool": 1i8,
+                "text": "hi",
+                "list": [1, 2],
+                "nested": {},
+            }
+        );
+    }
+
+    #[test]
+    fn hints_pick_the_tag_type() {
+        let options = JsonOptions::default()
+            .hint("byte", Hint::Byte)
+            .hint("short", Hint::Short)
+            .hint("long", Hint::Long)
+            .hint("float", Hint::Float)
+            .hint("double", Hint::Double)
+            .hint("bytes", Hint::ByteArray)
+            .hint("ints", Hint::IntArray)
+            .hint("longs", Hint::LongArray)
+            .hint("shorts", Hint::Short)
+            .hint("rows", Hint::IntArray);
+        let content = compound(
+            json!({
+                "byte": true,
+                "short": 300,
+                "long": 1,
+                "float": 1,
+                "double": 2,
+                "bytes": [1, -1],
+                "ints": [1, 2],
+                "longs": [3],
+                "shorts": [1, 2],
+                "rows": [[1], [2, 3]],
+            }),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Nbt::from(content),
+            crate::nbt! {
+                "byte": 1i8,
+                "short": 300i16,
+                "long": 1i64,
+                "float": 1f32,
+                "double": 2f64,
+                "bytes": [B; 1, -1],
+                "ints": [I; 1, 2],
+                "longs": [L; 3],
+                "shorts": [1i16, 2i16],
+                "rows": [[I; 1], [I; 2, 3]],
+            }
+        );
+    }
+
+    #[test]
+    fn hints_are_parsed_case_insensitively() {
+        assert_eq!("BYTE_ARRAY".parse::<Hint>().unwrap(), Hint::ByteArray);
+        assert_eq!("float".parse::<Hint>().unwrap(), Hint::Float);
+        assert!("string".parse::<Hint>().is_err());
+    }
+
+    #[test]
+    fn hints_that_do_not_fit_fall_back_unless_strict() {
+        let value = json!({ "small": 300, "text": "hi" });
+        let options = JsonOptions::default().hint("small", Hint::Byte).hint("text", Hint::Int);
+        assert_eq!(
+            Nbt::from(compound(value.clone(), &options).unwrap()),
+            crate::nbt! { "small": 300, "text": "hi" }
+        );
+
+        let options = JsonOptions { strict: true, ..options };
+        assert_eq!(error_path(compound(value, &options)), "small");
+    }
+
+    #[test]
+    fn typed_arrays_replace_integer_lists() {
+        let options = JsonOptions {
+            typed_arrays: true,
+            ..Default::default()
+        };
+        let content = compound(
+            json!({ "ints": [1, 2], "longs": [1, 1i64 << 40], "empty": [], "mixed": [1, 0.5] }),
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Nbt::from(content),
+            crate::nbt! { "ints": [I; 1, 2], "longs": [L; 1, 1i64 << 40], "empty": [], "mixed": [1f64, 0.5] }
+        );
+    }
+
+    #[test]
+    fn mixed_lists_are_unified_unless_strict() {
+        let value = json!({ "numbers": [1, 1i64 << 40, 0.5], "things": ["a", 1, { "b": 2 }] });
+        let content = compound(value, &JsonOptions::default()).unwrap();
+        assert_eq!(
+            Nbt::from(content),
+            crate::nbt! {
+                "numbers": [1f64, 1099511627776f64, 0.5],
+                "things": [{ "": "a" }, { "": 1 }, { "b": 2 }],
+            }
+        );
+
+        // Integers alone are widened in strict mode too
+        let content = compound(json!({ "numbers": [1, 1i64 << 40] }), &JsonOptions::strict()).unwrap();
+        assert_eq!(Nbt::from(content), crate::nbt! { "numbers": [1i64, 1i64 << 40] });
+
+        assert_eq!(
+            error_path(compound(json!({ "a": { "b": [1, 0.5] } }), &JsonOptions::strict())),
+            "a.b"
+        );
+        assert_eq!(error_path(compound(json!({ "a": [["x", 1]] }), &JsonOptions::strict())), "a[0]");
+    }
+
+    #[test]
+    fn strict_mode_rejects_values_without_nbt_counterparts() {
+        let strict = JsonOptions::strict();
+        assert_eq!(error_path(compound(json!({ "a": [null] }), &strict)), "a[0]");
+        assert_eq!(error_path(compound(json!({ "big": u64::MAX }), &strict)), "big");
+        assert_eq!(error_path(compound(json!({ "a b": null }), &strict)), "\"a b\"");
+        assert_eq!(error_path(Tag::from_json(&Value::Null, &JsonOptions::default())), "(root)");
+
+        let nbt = crate::nbt! { "nan": f64::NAN, "list": [f32::INFINITY] };
+        assert_eq!(error_path(nbt.to_json(&strict)), "nan");
+        assert_eq!(error_path(nbt.content["list"].to_json(&strict)), "[0]");
+        assert_eq!(
+            nbt.to_json(&JsonOptions::default()).unwrap(),
+            json!({ "nan": null, "list": [null] })
+        );
+    }
+
+    #[test]
+    fn the_root_has_to_be_an_object() {
+        assert!(matches!(
+            Nbt::from_json(&json!([1]), &JsonOptions::default()),
+            Err(Error::InvalidRoot(_))
+        ));
+    }
+
+    #[test]
+    fn nbt_survives_a_round_trip_through_json_with_hints() {
+        let nbt = crate::nbt! {
+            "name": "Steve",
+            "health": 20f32,
+            "speed": 0.1f32,
+            "xp": 1.5,
+            "level": 30,
+            "seed": 1i64 << 40,
+            "flags": 3i8,
+            "slot": 4i16,
+            "inventory": [{ "id": "stone", "count": 64i8 }],
+            "heights": [L; 1, 2],
+            "uuid": [I; 1, 2, 3, 4],
+            "bytes": [B; 1, -1],
+            "nested": { "positions": [[1.0, 2.0], [3.0, 4.0]] },
+        };
+        let options = JsonOptions::strict()
+            .hint("health", Hint::Float)
+            .hint("speed", Hint::Float)
+            .hint("seed", Hint::Long)
+            .hint("flags", Hint::Byte)
+            .hint("count", Hint::Byte)
+            .hint("slot", Hint::Short)
+            .hint("heights", Hint::LongArray)
+            .hint("uuid", Hint::IntArray)
+            .hint("bytes", Hint::ByteArray);
+
+        let value = nbt.to_json(&options).unwrap();
+        // Floats are printed by their shortest representation rather than widened to doubles
+        assert_eq!(value["speed"], json!(0.1));
+        assert_eq!(Nbt::from_json(&value, &options).unwrap(), nbt);
+    }
+
+    #[test]
+    fn float_hints_reject_values_out_of_range() {
+        let options = JsonOptions::strict().hint("speed", Hint::Float);
+        assert_eq!(
+            Tag::from_json(&json!({ "speed": 0.5 }), &options).unwrap(),
+            Tag::Compound(Compound::from_iter([(String::from("speed"), Tag::Float(0.5))]))
+        );
+
+        let error = Tag::from_json(&json!({ "speed": 1e300 }), &options).unwrap_err();
+        assert!(matches!(&error, Error::InvalidJson { path, .. } if path == "speed"), "{error}");
+
+        // Leniently the value keeps its inferred type rather than becoming infinite
+        let options = JsonOptions::default().hint("speed", Hint::Float);
+        assert_eq!(
+            Tag::from_json(&json!({ "speed": 1e300 }), &options).unwrap(),
+            Tag::Compound(Compound::from_iter([(String::from("speed"), Tag::Double(1e300))]))
+        );
+    }
+}
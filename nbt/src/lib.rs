@@ -1,2 +1,23 @@
+mod macros;
+
+pub mod array;
+pub mod borrowed;
+pub mod de;
+pub mod error;
 pub mod io;
+pub mod json;
+pub mod limits;
+pub mod path;
+pub mod ser;
+mod slice;
+pub mod snbt;
 pub mod types;
+pub mod visit;
+mod write;
+
+pub use borrowed::NbtRef;
+pub use de::{from_nbt, from_tag};
+pub use error::{Error, Result};
+pub use limits::NbtLimits;
+pub use path::NbtPath;
+pub use ser::{to_nbt, to_tag};
@@ -0,0 +1,144 @@
+//! Bounds on what decoding untrusted input may cost, modelled after vanilla's `NbtAccounter`
+use crate::error::{Error, Result};
+use std::{fmt, mem};
+
+/// Sizes are estimates of the memory taken up by the decoded tags rather than the length of the input, since a single
+/// byte of input (e.g. an empty compound inside a list) can expand into a much larger `Tag`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NbtLimits {
+    /// How many lists and compounds may be nested inside each other
+    pub max_depth: usize,
+    /// Estimated size of all decoded tags in bytes
+    pub max_bytes: u64,
+    /// Number of elements in a single list or array
+    pub max_list_len: usize,
+}
+
+impl NbtLimits {
+    /// Only trusted input should be decoded without limits, the depth is still bounded by the stack
+    pub(crate) const UNLIMITED: NbtLimits = NbtLimits {
+        max_depth: usize::MAX,
+        max_bytes: u64::MAX,
+        max_list_len: usize::MAX,
+    };
+
+    /// The 2 MiB quota vanilla applies to NBT received over the network
+    pub fn network() -> Self {
+        Self {
+            max_bytes: 0x200000,
+            ..Self::default()
+        }
+    }
+}
+
+/// Vanilla's nesting depth limit, with no bound on the size. Lengths are never trusted for preallocation, so truncated
+/// input claiming huge lengths fails with an error instead of allocating.
+impl Default for NbtLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: 512,
+            max_bytes: u64::MAX,
+            max_list_len: i32::MAX as usize,
+        }
+    }
+}
+
+/// The limit that was exceeded, see [`NbtLimits`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Depth,
+    Bytes,
+    ListLength,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Depth => "Nesting depth",
+            Limit::Bytes => "Decoded size in bytes",
+            Limit::ListLength => "List length",
+        })
+    }
+}
+
+/// Estimated size of a tag itself, payloads stored on the heap are accounted separately
+const TAG_SIZE: u64 = mem::size_of::<crate::types::Tag>() as u64;
+
+/// Upper bound on the capacity reserved for a list before its elements have actually been read
+pub(crate) const MAX_PREALLOCATION: usize = 1024;
+
+/// Tracks the resources used while decoding a single value
+pub(crate) struct NbtAccounter {
+    limits: NbtLimits,
+    depth: usize,
+    bytes: u64,
+}
+
+impl NbtAccounter {
+    pub(crate) fn new(limits: NbtLimits) -> Self {
+        Self {
+            limits,
+            depth: 0,
+            bytes: 0,
+        }
+    }
+
+    fn exceeded(limit: Limit, max: impl TryInto<u64>) -> Error {
+        Error::LimitExceeded {
+            limit,
+            max: max.try_into().unwrap_or(u64::MAX),
+        }
+    }
+
+    pub(crate) fn charge(&mut self, bytes: u64) -> Result<()> {
+        self.bytes = self.bytes.saturating_add(bytes);
+
+        if self.bytes > self.limits.max_bytes {
+            return Err(Self::exceeded(Limit::Bytes, self.limits.max_bytes));
+        }
+
+        Ok(())
+    }
+
+    /// Accounts for a single tag, excluding its payload
+    pub(crate) fn charge_tag(&mut self) -> Result<()> {
+        self.charge(TAG_SIZE)
+    }
+
+    pub(crate) fn charge_tags(&mut self, count: usize) -> Result<()> {
+        self.charge(TAG_SIZE.saturating_mul(count as u64))
+    }
+
+    /// Validates the length prefix of a list, negative lengths are read as empty
+    pub(crate) fn list_len(&self, len: i32) -> Result<usize> {
+        let len = len.max(0) as usize;
+
+        if len > self.limits.max_list_len {
+            return Err(Self::exceeded(Limit::ListLength, self.limits.max_list_len));
+        }
+
+        Ok(len)
+    }
+
+    /// Validates the length prefix of an array with elements of `width` bytes and charges for its payload up front
+    pub(crate) fn array_len(&mut self, len: i32, width: usize) -> Result<usize> {
+        let len = self.list_len(len)?;
+        self.charge((len as u64).saturating_mul(width as u64))?;
+
+        Ok(len)
+    }
+
+    /// Entered a list or compound
+    pub(crate) fn push(&mut self) -> Result<()> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Self::exceeded(Limit::Depth, self.limits.max_depth));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.depth -= 1;
+    }
+}
@@ -0,0 +1,76 @@
+/// Builds an [`Nbt`](crate::io::Nbt) with an empty title, with syntax close to SNBT:
+///
+/// ```
+/// let nbt = nbt::nbt! {
+///     "text": "hi",
+///     "list": [1, 2],
+///     "nested": { "flag": true },
+///     "heights": [L; 1, 2, 3],
+/// };
+/// ```
+///
+/// Values are converted with `Into<Tag>`, lists and compounds may be nested and typed arrays are written as `[B; ..]`,
+/// `[I; ..]` and `[L; ..]`.
+#[macro_export]
+macro_rules! nbt {
+    ($($tt:tt)*) => {
+        $crate::io::Nbt::from($crate::__nbt_compound!(@[] $($tt)*))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nbt_tag {
+    ({ $($tt:tt)* }) => {
+        $crate::types::Tag::Compound($crate::__nbt_compound!(@[] $($tt)*))
+    };
+    ([B; $($value:expr),* $(,)?]) => {
+        $crate::types::Tag::ByteArray(::std::vec![$($value),*])
+    };
+    ([I; $($value:expr),* $(,)?]) => {
+        $crate::types::Tag::IntArray(::std::vec![$($value),*])
+    };
+    ([L; $($value:expr),* $(,)?]) => {
+        $crate::types::Tag::LongArray(::std::vec![$($value),*])
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::types::Tag::List($crate::__nbt_list!(@[] $($tt)*))
+    };
+    ($value:expr) => {
+        $crate::types::Tag::from($value)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nbt_list {
+    (@[$($out:expr,)*]) => {
+        ::std::vec![$($out),*]
+    };
+    (@[$($out:expr,)*] { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__nbt_list!(@[$($out,)* $crate::__nbt_tag!({ $($inner)* }),] $($($rest)*)?)
+    };
+    (@[$($out:expr,)*] [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::__nbt_list!(@[$($out,)* $crate::__nbt_tag!([ $($inner)* ]),] $($($rest)*)?)
+    };
+    (@[$($out:expr,)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__nbt_list!(@[$($out,)* $crate::__nbt_tag!($value),] $($($rest)*)?)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nbt_compound {
+    (@[$($out:expr,)*]) => {
+        <$crate::types::Compound as ::std::iter::FromIterator<(::std::string::String, $crate::types::Tag)>>::from_iter([$($out),*])
+    };
+    (@[$($out:expr,)*] $key:literal : { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $crate::__nbt_compound!(@[$($out,)* (::std::string::String::from($key), $crate::__nbt_tag!({ $($inner)* })),] $($($rest)*)?)
+    };
+    (@[$($out:expr,)*] $key:literal : [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::__nbt_compound!(@[$($out,)* (::std::string::String::from($key), $crate::__nbt_tag!([ $($inner)* ])),] $($($rest)*)?)
+    };
+    (@[$($out:expr,)*] $key:literal : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__nbt_compound!(@[$($out,)* (::std::string::String::from($key), $crate::__nbt_tag!($value)),] $($($rest)*)?)
+    };
+}
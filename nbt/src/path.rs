@@ -0,0 +1,551 @@
+//! NBT paths as used by vanilla's `/data` command, e.g. `Inventory[{Slot:0b}].tag.display.Name`
+use crate::{
+    error::{Error, Result},
+    io::Nbt,
+    snbt::{compound_prefix, quoted_string_prefix, write_compound, write_quoted},
+    types::{Compound, Tag},
+};
+use std::{fmt, mem, str::FromStr};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// `{foo:1b}`, only valid at the start of a path
+    MatchRoot(Compound),
+    /// `foo`
+    Child(String),
+    /// `foo{bar:1b}`
+    MatchChild(String, Compound),
+    /// `[]`
+    AllElements,
+    /// `[0]`, negative indices count from the end
+    Index(i32),
+    /// `[{foo:1b}]`
+    MatchElement(Compound),
+}
+
+/// A path may select any number of tags, e.g. `[]` selects every element of a list
+#[derive(Clone, Debug, PartialEq)]
+pub struct NbtPath {
+    nodes: Vec<Node>,
+}
+
+impl FromStr for NbtPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        PathParser { input: s, position: 0 }.parse()
+    }
+}
+
+impl fmt::Display for NbtPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            match node {
+                Node::MatchRoot(pattern) => write_compound(&mut out, pattern, None),
+                Node::Child(name) | Node::MatchChild(name, _) => {
+                    if index > 0 {
+                        out.push('.');
+                    }
+
+                    write_name(&mut out, name);
+
+                    if let Node::MatchChild(_, pattern) = node {
+                        write_compound(&mut out, pattern, None);
+                    }
+                }
+                Node::AllElements => out.push_str("[]"),
+                Node::Index(index) => out.push_str(&format!("[{index}]")),
+                Node::MatchElement(pattern) => {
+                    out.push('[');
+                    write_compound(&mut out, pattern, None);
+                    out.push(']');
+                }
+            }
+        }
+
+        f.write_str(&out)
+    }
+}
+
+struct PathParser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl PathParser<'_> {
+    fn error(&self, reason: impl Into<String>) -> Error {
+        Error::InvalidPath {
+            column: self.input[..self.position].chars().count() + 1,
+            reason: reason.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("Expected '{expected}' but found '{c}'"))),
+            None => Err(self.error(format!("Expected '{expected}' but reached the end of input"))),
+        }
+    }
+
+    /// Errors in embedded SNBT are reported relative to the whole path
+    fn snbt<T>(&mut self, parse: fn(&str) -> Result<(T, usize)>) -> Result<T> {
+        match parse(&self.input[self.position..]) {
+            Ok((value, len)) => {
+                self.position += len;
+                Ok(value)
+            }
+            Err(Error::InvalidSnbt { column, reason, .. }) => Err(Error::InvalidPath {
+                column: self.input[..self.position].chars().count() + column,
+                reason,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse(mut self) -> Result<NbtPath> {
+        let mut nodes = vec![];
+
+        loop {
+            nodes.push(self.read_node(nodes.is_empty())?);
+
+            match self.peek() {
+                None => return Ok(NbtPath { nodes }),
+                Some('[' | '{') => {}
+                Some('.') => self.position += 1,
+                Some(c) => return Err(self.error(format!("Expected '.' or '[' but found '{c}'"))),
+            }
+        }
+    }
+
+    fn read_node(&mut self, first: bool) -> Result<Node> {
+        match self.peek() {
+            Some('{') if first => Ok(Node::MatchRoot(self.snbt(compound_prefix)?)),
+            Some('{') => Err(self.error("Compound filters must follow a name or start the path")),
+            Some('[') => {
+                self.position += 1;
+                let node = match self.peek() {
+                    Some(']') => Node::AllElements,
+                    Some('{') => Node::MatchElement(self.snbt(compound_prefix)?),
+                    _ => {
+                        let start = self.position;
+                        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '-') {
+                            self.position += 1;
+                        }
+
+                        match self.input[start..self.position].parse() {
+                            Ok(index) => Node::Index(index),
+                            Err(_) => {
+                                self.position = start;
+                                return Err(self.error("Expected an index, a compound filter or ']'"));
+                            }
+                        }
+                    }
+                };
+
+                self.expect(']')?;
+                Ok(node)
+            }
+            Some('"' | '\'') => {
+                let name = self.snbt(quoted_string_prefix)?;
+                self.read_child(name)
+            }
+            _ => {
+                let start = self.position;
+                while let Some(c) = self.peek().filter(|&c| is_unquoted_char(c)) {
+                    self.position += c.len_utf8();
+                }
+
+                if start == self.position {
+                    return Err(self.error("Expected a name"));
+                }
+
+                self.read_child(self.input[start..self.position].to_string())
+            }
+        }
+    }
+
+    fn read_child(&mut self, name: String) -> Result<Node> {
+        match self.peek() {
+            Some('{') => Ok(Node::MatchChild(name, self.snbt(compound_prefix)?)),
+            _ => Ok(Node::Child(name)),
+        }
+    }
+}
+
+/// Writes a child name the way it appears in a path, quoting it only when needed so it parses back to `name`
+pub fn write_name(out: &mut String, name: &str) {
+    if !name.is_empty() && name.chars().all(is_unquoted_char) {
+        out.push_str(name);
+    } else {
+        write_quoted(out, name);
+    }
+}
+
+/// Names in paths may contain anything but the characters that delimit nodes
+fn is_unquoted_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '"' | '\'' | '[' | ']' | '.' | '{' | '}')
+}
+
+/// Filters match partially: compounds only need to contain the entries of the pattern and lists an element matching
+/// each element of the pattern
+fn matches(pattern: &Tag, tag: &Tag) -> bool {
+    match (pattern, tag) {
+        (Tag::Compound(pattern), Tag::Compound(map)) => compound_matches(pattern, map),
+        (Tag::List(pattern), Tag::List(values)) if pattern.is_empty() => values.is_empty(),
+        (Tag::List(pattern), Tag::List(values)) => pattern.iter().all(|p| values.iter().any(|v| matches(p, v))),
+        _ => pattern == tag,
+    }
+}
+
+fn compound_matches(pattern: &Compound, map: &Compound) -> bool {
+    pattern.iter().all(|(name, p)| map.get(name).is_some_and(|v| matches(p, v)))
+}
+
+fn is_match(pattern: &Compound, tag: &Tag) -> bool {
+    matches!(tag, Tag::Compound(map) if compound_matches(pattern, map))
+}
+
+fn resolve(index: i32, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index as i64 } else { index as i64 };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+/// Lists only hold elements of a single type
+fn accepts(values: &[Tag], id: u8) -> bool {
+    values.first().is_none_or(|first| first.id() == id)
+}
+
+fn child<'a>(node: &Node, map: &'a Compound) -> Option<&'a Tag> {
+    match node {
+        Node::Child(name) => map.get(name),
+        Node::MatchChild(name, pattern) => map.get(name).filter(|tag| is_match(pattern, tag)),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(node: &Node, map: &'a mut Compound) -> Option<&'a mut Tag> {
+    match node {
+        Node::Child(name) => map.get_mut(name),
+        Node::MatchChild(name, pattern) => map.get_mut(name).filter(|tag| is_match(pattern, tag)),
+        _ => None,
+    }
+}
+
+fn select<'a>(nodes: &[Node], tag: &'a Tag, out: &mut Vec<&'a Tag>) {
+    let Some((node, rest)) = nodes.split_first() else {
+        return out.push(tag);
+    };
+
+    match (node, tag) {
+        (Node::MatchRoot(pattern), tag) if is_match(pattern, tag) => select(rest, tag, out),
+        (Node::Child(_) | Node::MatchChild(..), Tag::Compound(map)) => {
+            if let Some(child) = child(node, map) {
+                select(rest, child, out);
+            }
+        }
+        (Node::AllElements, Tag::List(values)) => values.iter().for_each(|value| select(rest, value, out)),
+        (Node::Index(index), Tag::List(values)) => {
+            if let Some(index) = resolve(*index, values.len()) {
+                select(rest, &values[index], out);
+            }
+        }
+        (Node::MatchElement(pattern), Tag::List(values)) => values
+            .iter()
+            .filter(|value| is_match(pattern, value))
+            .for_each(|value| select(rest, value, out)),
+        _ => {}
+    }
+}
+
+fn select_mut<'a>(nodes: &[Node], tag: &'a mut Tag, out: &mut Vec<&'a mut Tag>) {
+    let Some((node, rest)) = nodes.split_first() else {
+        return out.push(tag);
+    };
+
+    match (node, tag) {
+        (Node::MatchRoot(pattern), tag) if is_match(pattern, tag) => select_mut(rest, tag, out),
+        (Node::Child(_) | Node::MatchChild(..), Tag::Compound(map)) => {
+            if let Some(child) = child_mut(node, map) {
+                select_mut(rest, child, out);
+            }
+        }
+        (Node::AllElements, Tag::List(values)) => values.iter_mut().for_each(|value| select_mut(rest, value, out)),
+        (Node::Index(index), Tag::List(values)) => {
+            if let Some(index) = resolve(*index, values.len()) {
+                select_mut(rest, &mut values[index], out);
+            }
+        }
+        (Node::MatchElement(pattern), Tag::List(values)) => values
+            .iter_mut()
+            .filter(|value| is_match(pattern, value))
+            .for_each(|value| select_mut(rest, value, out)),
+        _ => {}
+    }
+}
+
+/// Tag to create for a missing child so that `next` can be applied to it, `None` creates the compound a merge targets
+fn empty_for(next: Option<&Node>) -> Option<Tag> {
+    match next {
+        None | Some(Node::Child(_) | Node::MatchChild(..)) => Some(Tag::Compound(Compound::new())),
+        Some(Node::AllElements | Node::Index(_) | Node::MatchElement(_)) => Some(Tag::List(vec![])),
+        Some(Node::MatchRoot(_)) => None,
+    }
+}
+
+/// Like `select_mut`, but creates missing compounds and lists on the way like vanilla's `/data modify` does. `after` is
+/// the node that will be applied to the selected tags.
+fn select_or_create<'a>(nodes: &[Node], after: Option<&Node>, tag: &'a mut Tag, out: &mut Vec<&'a mut Tag>) {
+    let Some((node, rest)) = nodes.split_first() else {
+        return out.push(tag);
+    };
+    let next = rest.first().or(after);
+
+    match (node, tag) {
+        (Node::MatchRoot(pattern), tag) if is_match(pattern, tag) => select_or_create(rest, after, tag, out),
+        (Node::Child(name), Tag::Compound(map)) => {
+            if !map.contains_key(name) {
+                if let Some(empty) = empty_for(next) {
+                    map.insert(name.clone(), empty);
+                }
+            }
+
+            if let Some(child) = map.get_mut(name) {
+                select_or_create(rest, after, child, out);
+            }
+        }
+        (Node::MatchChild(name, pattern), Tag::Compound(map)) => {
+            let child = map.entry(name.clone()).or_insert_with(|| Tag::Compound(pattern.clone()));
+
+            if is_match(pattern, child) {
+                select_or_create(rest, after, child, out);
+            }
+        }
+        (Node::AllElements, Tag::List(values)) => {
+            if values.is_empty() {
+                values.extend(empty_for(next));
+            }
+
+            values.iter_mut().for_each(|value| select_or_create(rest, after, value, out));
+        }
+        (Node::Index(index), Tag::List(values)) => {
+            if let Some(index) = resolve(*index, values.len()) {
+                select_or_create(rest, after, &mut values[index], out);
+            }
+        }
+        (Node::MatchElement(pattern), Tag::List(values)) => {
+            if !values.iter().any(|value| is_match(pattern, value)) && accepts(values, 0x0a) {
+                values.push(Tag::Compound(pattern.clone()));
+            }
+
+            values
+                .iter_mut()
+                .filter(|value| is_match(pattern, value))
+                .for_each(|value| select_or_create(rest, after, value, out));
+        }
+        _ => {}
+    }
+}
+
+fn replace(slot: &mut Tag, value: &Tag) -> usize {
+    if slot == value {
+        return 0;
+    }
+
+    *slot = value.clone();
+    1
+}
+
+fn set(node: &Node, parent: &mut Tag, value: &Tag) -> usize {
+    match (node, parent) {
+        (Node::Child(name), Tag::Compound(map)) => match map.get_mut(name) {
+            Some(slot) => replace(slot, value),
+            None => {
+                map.insert(name.clone(), value.clone());
+                1
+            }
+        },
+        (Node::MatchChild(name, pattern), Tag::Compound(map)) => match map.get_mut(name) {
+            Some(slot) if is_match(pattern, slot) => replace(slot, value),
+            _ => 0,
+        },
+        (Node::AllElements, Tag::List(values)) if values.is_empty() => {
+            values.push(value.clone());
+            1
+        }
+        (Node::AllElements, Tag::List(values)) if accepts(values, value.id()) => values.iter_mut().map(|slot| replace(slot, value)).sum(),
+        (Node::Index(index), Tag::List(values)) if accepts(values, value.id()) => match resolve(*index, values.len()) {
+            Some(index) => replace(&mut values[index], value),
+            None => 0,
+        },
+        (Node::MatchElement(pattern), Tag::List(values)) if accepts(values, value.id()) => values
+            .iter_mut()
+            .filter(|slot| is_match(pattern, slot))
+            .map(|slot| replace(slot, value))
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn remove(node: &Node, parent: &mut Tag) -> usize {
+    match (node, parent) {
+        (Node::Child(name), Tag::Compound(map)) => usize::from(map.shift_remove(name).is_some()),
+        (Node::MatchChild(name, pattern), Tag::Compound(map)) => {
+            if !map.get(name).is_some_and(|tag| is_match(pattern, tag)) {
+                return 0;
+            }
+
+            map.shift_remove(name);
+            1
+        }
+        (Node::AllElements, Tag::List(values)) => mem::take(values).len(),
+        (Node::Index(index), Tag::List(values)) => match resolve(*index, values.len()) {
+            Some(index) => {
+                values.remove(index);
+                1
+            }
+            None => 0,
+        },
+        (Node::MatchElement(pattern), Tag::List(values)) => {
+            let len = values.len();
+            values.retain(|value| !is_match(pattern, value));
+            len - values.len()
+        }
+        _ => 0,
+    }
+}
+
+/// Nested compounds are merged recursively, everything else is replaced
+fn merge(map: &mut Compound, value: &Compound) {
+    for (name, tag) in value {
+        match (map.get_mut(name), tag) {
+            (Some(Tag::Compound(existing)), Tag::Compound(tag)) => merge(existing, tag),
+            _ => {
+                map.insert(name.clone(), tag.clone());
+            }
+        }
+    }
+}
+
+impl Tag {
+    pub fn get_path(&self, path: &NbtPath) -> Vec<&Tag> {
+        let mut out = vec![];
+        select(&path.nodes, self, &mut out);
+        out
+    }
+
+    pub fn get_path_mut(&mut self, path: &NbtPath) -> Vec<&mut Tag> {
+        let mut out = vec![];
+        select_mut(&path.nodes, self, &mut out);
+        out
+    }
+
+    /// Replaces every selected tag with `value`, creating missing parents on the way. Returns how many tags changed.
+    pub fn set_path(&mut self, path: &NbtPath, value: Tag) -> usize {
+        let Some((last, parents)) = path.nodes.split_last() else {
+            return 0;
+        };
+
+        let mut out = vec![];
+        select_or_create(parents, Some(last), self, &mut out);
+        out.into_iter().map(|parent| set(last, parent, &value)).sum()
+    }
+
+    /// Returns how many tags were removed
+    pub fn remove_path(&mut self, path: &NbtPath) -> usize {
+        let Some((last, parents)) = path.nodes.split_last() else {
+            return 0;
+        };
+
+        let mut out = vec![];
+        select_mut(parents, self, &mut out);
+        out.into_iter().map(|parent| remove(last, parent)).sum()
+    }
+
+    /// Merges `value` into every selected compound, creating it if it's missing. Returns how many compounds were
+    /// merged into.
+    pub fn merge_path(&mut self, path: &NbtPath, value: &Compound) -> usize {
+        let mut out = vec![];
+        select_or_create(&path.nodes, None, self, &mut out);
+
+        let mut merged = 0;
+        for tag in out {
+            if let Tag::Compound(map) = tag {
+                merge(map, value);
+                merged += 1;
+            }
+        }
+
+        merged
+    }
+}
+
+/// The root compound itself is never selected, since it isn't stored as a `Tag`
+impl Nbt {
+    pub fn get_path(&self, path: &NbtPath) -> Vec<&Tag> {
+        let mut out = vec![];
+        let nodes = match path.nodes.split_first() {
+            Some((Node::MatchRoot(pattern), rest)) if compound_matches(pattern, &self.content) => rest,
+            Some((Node::MatchRoot(_), _)) => return out,
+            _ => &path.nodes[..],
+        };
+
+        if let Some((node, rest)) = nodes.split_first() {
+            if let Some(child) = child(node, &self.content) {
+                select(rest, child, &mut out);
+            }
+        }
+
+        out
+    }
+
+    pub fn get_path_mut(&mut self, path: &NbtPath) -> Vec<&mut Tag> {
+        let mut out = vec![];
+        let nodes = match path.nodes.split_first() {
+            Some((Node::MatchRoot(pattern), rest)) if compound_matches(pattern, &self.content) => rest,
+            Some((Node::MatchRoot(_), _)) => return out,
+            _ => &path.nodes[..],
+        };
+
+        if let Some((node, rest)) = nodes.split_first() {
+            if let Some(child) = child_mut(node, &mut self.content) {
+                select_mut(rest, child, &mut out);
+            }
+        }
+
+        out
+    }
+
+    pub fn set_path(&mut self, path: &NbtPath, value: Tag) -> usize {
+        self.with_root(|root| root.set_path(path, value))
+    }
+
+    pub fn remove_path(&mut self, path: &NbtPath) -> usize {
+        self.with_root(|root| root.remove_path(path))
+    }
+
+    pub fn merge_path(&mut self, path: &NbtPath, value: &Compound) -> usize {
+        self.with_root(|root| root.merge_path(path, value))
+    }
+
+    /// None of the operations replace the root itself, so it is still a compound afterwards
+    fn with_root<T>(&mut self, f: impl FnOnce(&mut Tag) -> T) -> T {
+        let mut root = Tag::Compound(mem::take(&mut self.content));
+        let result = f(&mut root);
+
+        if let Tag::Compound(content) = root {
+            self.content = content;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,458 @@
+//! Serde `Serializer` turning any `Serialize` type into a `Tag`
+//!
+//! * `bool` is stored as a `TAG_Byte` of 0 or 1, unsigned integers are stored bit for bit in the signed tag of the same width
+//! * `None` fields are left out of their compound, `None` anywhere else is an error
+//! * Unit enum variants become a `TAG_String` of their name, all other variants a compound with a single entry
+use crate::{
+    array::{BYTE_ARRAY_TOKEN, INT_ARRAY_TOKEN, LONG_ARRAY_TOKEN},
+    error::{Error, Result},
+    io::Nbt,
+    types::{Compound, Tag},
+};
+use serde::{ser, Serialize};
+use std::fmt::Display;
+
+pub fn to_tag<T>(value: &T) -> Result<Tag>
+where
+    T: Serialize + ?Sized,
+{
+    value
+        .serialize(Serializer)?
+        .ok_or_else(|| Error::Message("None can only be serialized as a compound field".into()))
+}
+
+/// The value has to serialize to a compound, e.g. a struct or a map
+pub fn to_nbt<S, T>(title: S, value: &T) -> Result<Nbt>
+where
+    S: Into<String>,
+    T: Serialize + ?Sized,
+{
+    match to_tag(value)? {
+        Tag::Compound(content) => Ok(Nbt {
+            title: title.into(),
+            content,
+        }),
+        other => Err(Error::InvalidRoot(other.id())),
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+/// Produces `None` for values that have no NBT representation on their own (`None` and `()`)
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeCompound;
+    type SerializeStruct = SerializeCompound;
+    type SerializeStructVariant = SerializeVariant<SerializeCompound>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Some(Tag::Byte(v as i8)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        Ok(Some(Tag::Byte(v)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        Ok(Some(Tag::Short(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        Ok(Some(Tag::Int(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(Some(Tag::Long(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        Ok(Some(Tag::Byte(v as i8)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        Ok(Some(Tag::Short(v as i16)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        Ok(Some(Tag::Int(v as i32)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        Ok(Some(Tag::Long(v as i64)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        Ok(Some(Tag::Float(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Some(Tag::Double(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        Ok(Some(Tag::String(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(Some(Tag::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(Tag::ByteArray(v.iter().map(|&b| b as i8).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(Some(Tag::Compound(Compound::new())))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Self::Ok> {
+        Ok(Some(Tag::String(variant.to_string())))
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: Serialize + ?Sized,
+    {
+        let tag = value.serialize(self)?;
+
+        match name {
+            BYTE_ARRAY_TOKEN => into_array(tag, Tag::ByteArray, |tag| match tag {
+                Tag::Byte(v) => Some(v),
+                _ => None,
+            }),
+            INT_ARRAY_TOKEN => into_array(tag, Tag::IntArray, |tag| match tag {
+                Tag::Int(v) => Some(v),
+                _ => None,
+            }),
+            LONG_ARRAY_TOKEN => into_array(tag, Tag::LongArray, |tag| match tag {
+                Tag::Long(v) => Some(v),
+                _ => None,
+            }),
+            _ => Ok(tag),
+        }
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: Serialize + ?Sized,
+    {
+        let mut map = Compound::new();
+        if let Some(tag) = value.serialize(self)? {
+            map.insert(variant.to_string(), tag);
+        }
+
+        Ok(Some(Tag::Compound(map)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeList {
+            values: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeCompound {
+            map: Compound::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+/// Converts the `TAG_List` produced for a sequence into one of the typed arrays
+fn into_array<T>(tag: Option<Tag>, wrap: fn(Vec<T>) -> Tag, extract: fn(Tag) -> Option<T>) -> Result<Option<Tag>> {
+    match tag {
+        Some(Tag::List(values)) => {
+            let mut array = Vec::with_capacity(values.len());
+
+            for value in values {
+                let id = value.id();
+                match extract(value) {
+                    Some(v) => array.push(v),
+                    None => return Err(Error::Message(format!("Typed arrays can't contain elements with tag id {id:#04x}"))),
+                }
+            }
+
+            Ok(Some(wrap(array)))
+        }
+        Some(Tag::ByteArray(_) | Tag::IntArray(_) | Tag::LongArray(_)) => Ok(tag),
+        _ => Err(Error::Message("Typed arrays must be serialized from a sequence".into())),
+    }
+}
+
+pub struct SerializeList {
+    values: Vec<Tag>,
+}
+
+impl SerializeList {
+    fn push<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let tag = value
+            .serialize(Serializer)?
+            .ok_or_else(|| Error::Message("Lists can't contain None".into()))?;
+
+        if let Some(first) = self.values.first() {
+            if first.id() != tag.id() {
+                return Err(Error::MixedListTypes {
+                    expected: first.id(),
+                    found: tag.id(),
+                });
+            }
+        }
+
+        self.values.push(tag);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Tag::List(self.values)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Tag::List(self.values)))
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Tag::List(self.values)))
+    }
+}
+
+pub struct SerializeCompound {
+    map: Compound,
+    next_key: Option<String>,
+}
+
+impl SerializeCompound {
+    fn insert<T>(&mut self, key: String, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        if let Some(tag) = value.serialize(Serializer)? {
+            self.map.insert(key, tag);
+        }
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for SerializeCompound {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.next_key = Some(match key.serialize(Serializer)? {
+            Some(Tag::String(key)) => key,
+            Some(Tag::Byte(v)) => v.to_string(),
+            Some(Tag::Short(v)) => v.to_string(),
+            Some(Tag::Int(v)) => v.to_string(),
+            Some(Tag::Long(v)) => v.to_string(),
+            _ => return Err(Error::Message("Compound keys must be strings or integers".into())),
+        });
+
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| Error::Message("serialize_value called before serialize_key".into()))?;
+
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Tag::Compound(self.map)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeCompound {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(Tag::Compound(self.map)))
+    }
+}
+
+/// Wraps the serialized variant content into a compound keyed by the variant name
+pub struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> SerializeVariant<S> {
+    fn wrap(variant: &'static str, tag: Option<Tag>) -> Option<Tag> {
+        let mut map = Compound::new();
+        if let Some(tag) = tag {
+            map.insert(variant.to_string(), tag);
+        }
+
+        Some(Tag::Compound(map))
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Self::wrap(self.variant, ser::SerializeSeq::end(self.inner)?))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeCompound> {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.inner.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Self::wrap(self.variant, ser::SerializeStruct::end(self.inner)?))
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Tag::Byte(v) => serializer.serialize_i8(*v),
+            Tag::Short(v) => serializer.serialize_i16(*v),
+            Tag::Int(v) => serializer.serialize_i32(*v),
+            Tag::Long(v) => serializer.serialize_i64(*v),
+            Tag::Float(v) => serializer.serialize_f32(*v),
+            Tag::Double(v) => serializer.serialize_f64(*v),
+            Tag::String(v) => serializer.serialize_str(v),
+            Tag::List(v) => v.serialize(serializer),
+            Tag::Compound(v) => v.serialize(serializer),
+            Tag::ByteArray(v) => serializer.serialize_newtype_struct(BYTE_ARRAY_TOKEN, v),
+            Tag::IntArray(v) => serializer.serialize_newtype_struct(INT_ARRAY_TOKEN, v),
+            Tag::LongArray(v) => serializer.serialize_newtype_struct(LONG_ARRAY_TOKEN, v),
+        }
+    }
+}
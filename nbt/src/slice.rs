@@ -0,0 +1,352 @@
+//! Synchronous NBT parsing straight out of a byte slice, for when the whole input is already in memory
+//! (region files, `level.dat` after decompression, ...) and the async `NbtReader` would cost an await per primitive
+use crate::{
+    borrowed::decode_array,
+    error::{Error, Result},
+    io::Nbt,
+    limits::{NbtAccounter, NbtLimits, MAX_PREALLOCATION},
+    types::{Compound, Tag},
+};
+use std::borrow::Cow;
+
+pub(crate) struct SliceReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    accounter: NbtAccounter,
+}
+
+macro_rules! read_be (
+    ($type: ty, $name: ident) => (
+        #[inline]
+        pub(crate) fn $name(&mut self) -> Result<$type> {
+            let bytes = self.take(std::mem::size_of::<$type>())?;
+            Ok(<$type>::from_be_bytes(bytes.try_into().expect("take returns exactly the requested length")))
+        }
+    )
+);
+
+impl<'a> SliceReader<'a> {
+    pub(crate) fn new(data: &'a [u8], limits: NbtLimits) -> Self {
+        Self {
+            data,
+            position: 0,
+            accounter: NbtAccounter::new(limits),
+        }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Bytes consumed since `start`
+    pub(crate) fn since(&self, start: usize) -> &'a [u8] {
+        &self.data[start..self.position]
+    }
+
+    #[inline]
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        match self.data.get(self.position..self.position.saturating_add(len)) {
+            Some(bytes) => {
+                self.position += len;
+                Ok(bytes)
+            }
+            None => Err(Error::UnexpectedEof {
+                offset: self.data.len() as u64,
+            }),
+        }
+    }
+
+    read_be!(u8, read_u8);
+    read_be!(i8, read_i8);
+    read_be!(u16, read_u16);
+    read_be!(i16, read_i16);
+    read_be!(i32, read_i32);
+    read_be!(i64, read_i64);
+    read_be!(f32, read_f32);
+    read_be!(f64, read_f64);
+
+    /// Length prefix of lists, negative lengths are read as empty
+    #[inline]
+    pub(crate) fn read_len(&mut self) -> Result<usize> {
+        let len = self.read_i32()?;
+        self.accounter.list_len(len)
+    }
+
+    /// Raw modified UTF-8 bytes of a string
+    #[inline]
+    pub(crate) fn read_string_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.read_u16()? as usize;
+        self.accounter.charge(len as u64)?;
+        self.take(len)
+    }
+
+    /// Raw big-endian bytes of an array with elements of `width` bytes
+    #[inline]
+    pub(crate) fn read_array_bytes(&mut self, width: usize) -> Result<&'a [u8]> {
+        let len = self.read_i32()?;
+        let len = self.accounter.array_len(len, width)?;
+        self.take(len.saturating_mul(width))
+    }
+
+    /// Skips over the payload of a tag without allocating. The limits are enforced just like when decoding, so input
+    /// that passes here can be decoded with the same limits.
+    pub(crate) fn skip(&mut self, id: u8) -> Result<()> {
+        let mut open = vec![];
+        self.skip_start(id, &mut open)?;
+
+        loop {
+            let id = match open.last_mut() {
+                None => return Ok(()),
+                Some(Skipping::List { id, remaining }) => remaining.checked_sub(1).map(|left| {
+                    *remaining = left;
+                    *id
+                }),
+                Some(Skipping::Compound) => match self.read_u8()? {
+                    0x00 => None,
+                    id => {
+                        self.read_string_bytes()?;
+                        Some(id)
+                    }
+                },
+            };
+
+            match id {
+                Some(id) => self.skip_start(id, &mut open)?,
+                None => {
+                    open.pop();
+                    self.accounter.pop();
+                }
+            }
+        }
+    }
+
+    /// Skips a tag, or only the header of a list or compound whose elements are left to [`SliceReader::skip`]
+    fn skip_start(&mut self, id: u8, open: &mut Vec<Skipping>) -> Result<()> {
+        self.accounter.charge_tag()?;
+
+        match id {
+            0x01 => self.take(1).map(drop),
+            0x02 => self.take(2).map(drop),
+            0x03 | 0x05 => self.take(4).map(drop),
+            0x04 | 0x06 => self.take(8).map(drop),
+            0x07 => self.read_array_bytes(1).map(drop),
+            0x08 => self.read_string_bytes().map(drop),
+            0x09 => {
+                let id = self.read_u8()?;
+                let len = self.read_len()?;
+
+                self.accounter.push()?;
+                match fixed_width(id) {
+                    // Lists of primitives can be skipped in one go
+                    Some(width) => {
+                        self.accounter.charge_tags(len)?;
+                        self.take(len.saturating_mul(width))?;
+                        self.accounter.pop();
+                    }
+                    None if len > 0 && id == 0x00 => return Err(Error::UnknownTagId(id)),
+                    None => open.push(Skipping::List { id, remaining: len }),
+                }
+
+                Ok(())
+            }
+            0x0a => {
+                self.accounter.push()?;
+                open.push(Skipping::Compound);
+
+                Ok(())
+            }
+            0x0b => self.read_array_bytes(4).map(drop),
+            0x0c => self.read_array_bytes(8).map(drop),
+            _ => Err(Error::UnknownTagId(id)),
+        }
+    }
+
+    pub(crate) fn decode(&mut self, id: u8) -> Result<Tag> {
+        let mut open = vec![];
+        match self.decode_start(id, &mut open)? {
+            Some(tag) => Ok(tag),
+            None => self.decode_open(open),
+        }
+    }
+
+    /// The elements of a list whose header has already been read
+    pub(crate) fn decode_list(&mut self, id: u8, len: usize) -> Result<Tag> {
+        self.accounter.push()?;
+        self.decode_open(vec![Decoding::list(id, len)])
+    }
+
+    /// Reads the elements of the lists and compounds in `open` until the outermost one is complete. The nesting is
+    /// kept on the heap rather than the call stack, so the depth is only bounded by the limits.
+    fn decode_open(&mut self, mut open: Vec<Decoding>) -> Result<Tag> {
+        let mut value = None;
+
+        loop {
+            let id = match open.last_mut() {
+                None => return Ok(value.expect("the outermost list or compound is complete")),
+                Some(Decoding::List { id, remaining, values }) => {
+                    values.extend(value.take());
+                    remaining.checked_sub(1).map(|left| {
+                        *remaining = left;
+                        *id
+                    })
+                }
+                Some(Decoding::Compound { map, name }) => {
+                    if let Some(tag) = value.take() {
+                        map.insert(std::mem::take(name), tag);
+                    }
+
+                    match self.read_u8()? {
+                        0x00 => None,
+                        id => {
+                            *name = decode_string(self.read_string_bytes()?)?.into_owned();
+                            Some(id)
+                        }
+                    }
+                }
+            };
+
+            value = match id {
+                Some(id) => self.decode_start(id, &mut open)?,
+                None => {
+                    self.accounter.pop();
+                    open.pop().map(Decoding::finish)
+                }
+            };
+        }
+    }
+
+    /// Decodes a tag, or only the header of a list or compound, which is pushed to `open` and `None` returned
+    fn decode_start(&mut self, id: u8, open: &mut Vec<Decoding>) -> Result<Option<Tag>> {
+        self.accounter.charge_tag()?;
+
+        Ok(Some(match id {
+            0x01 => Tag::Byte(self.read_i8()?),
+            0x02 => Tag::Short(self.read_i16()?),
+            0x03 => Tag::Int(self.read_i32()?),
+            0x04 => Tag::Long(self.read_i64()?),
+            0x05 => Tag::Float(self.read_f32()?),
+            0x06 => Tag::Double(self.read_f64()?),
+            0x07 => Tag::ByteArray(decode_array(self.read_array_bytes(1)?)),
+            0x08 => Tag::String(decode_string(self.read_string_bytes()?)?.into_owned()),
+            0x09 => {
+                let id = self.read_u8()?;
+                let len = self.read_len()?;
+
+                if len > 0 && id == 0x00 {
+                    return Err(Error::UnknownTagId(id));
+                }
+
+                self.accounter.push()?;
+                open.push(Decoding::list(id, len));
+                return Ok(None);
+            }
+            0x0a => {
+                self.accounter.push()?;
+                open.push(Decoding::Compound {
+                    map: Compound::new(),
+                    name: String::new(),
+                });
+                return Ok(None);
+            }
+            0x0b => Tag::IntArray(decode_array(self.read_array_bytes(4)?)),
+            0x0c => Tag::LongArray(decode_array(self.read_array_bytes(8)?)),
+            _ => return Err(Error::UnknownTagId(id)),
+        }))
+    }
+}
+
+/// A list or compound [`SliceReader::skip`] is inside of
+enum Skipping {
+    List { id: u8, remaining: usize },
+    Compound,
+}
+
+/// A list or compound [`SliceReader::decode`] is inside of, with the elements read so far
+enum Decoding {
+    List {
+        id: u8,
+        remaining: usize,
+        values: Vec<Tag>,
+    },
+    /// `name` is the name of the entry being read
+    Compound {
+        map: Compound,
+        name: String,
+    },
+}
+
+impl Decoding {
+    fn list(id: u8, len: usize) -> Self {
+        Decoding::List {
+            id,
+            remaining: len,
+            values: Vec::with_capacity(len.min(MAX_PREALLOCATION)),
+        }
+    }
+
+    fn finish(self) -> Tag {
+        match self {
+            Decoding::List { values, .. } => Tag::List(values),
+            Decoding::Compound { map, .. } => Tag::Compound(map),
+        }
+    }
+}
+
+/// Size of the payload of tags that don't have a length prefix
+pub(crate) fn fixed_width(id: u8) -> Option<usize> {
+    match id {
+        0x01 => Some(1),
+        0x02 => Some(2),
+        0x03 | 0x05 => Some(4),
+        0x04 | 0x06 => Some(8),
+        _ => None,
+    }
+}
+
+pub(crate) fn decode_string(bytes: &[u8]) -> Result<Cow<'_, str>> {
+    cesu8::from_java_cesu8(bytes).map_err(|_| Error::InvalidMutf8)
+}
+
+impl Nbt {
+    /// Synchronous counterpart to `from_reader` for input that is already in memory
+    pub fn from_slice(bytes: &[u8]) -> Result<Nbt> {
+        Self::from_slice_with_limits(bytes, NbtLimits::default())
+    }
+
+    pub fn from_slice_with_limits(bytes: &[u8], limits: NbtLimits) -> Result<Nbt> {
+        let mut reader = SliceReader::new(bytes, limits);
+        let id = reader.read_u8()?;
+
+        if id != 0x0a {
+            return Err(Error::InvalidRoot(id));
+        }
+
+        let title = decode_string(reader.read_string_bytes()?)?.into_owned();
+        match reader.decode(id)? {
+            Tag::Compound(content) => Ok(Nbt { title, content }),
+            other => Err(Error::InvalidRoot(other.id())),
+        }
+    }
+}
+
+impl Tag {
+    /// Synchronous counterpart to `from_networked_reader` for input that is already in memory
+    pub fn from_networked_slice(bytes: &[u8]) -> Result<Option<Tag>> {
+        Self::from_networked_slice_with_limits(bytes, NbtLimits::default())
+    }
+
+    pub fn from_networked_slice_with_limits(bytes: &[u8], limits: NbtLimits) -> Result<Option<Tag>> {
+        Ok(Self::from_networked_prefix_with_limits(bytes, limits)?.0)
+    }
+
+    /// For input that goes on after the tag, e.g. a packet with more fields. Also returns how many bytes the tag took up.
+    pub fn from_networked_prefix_with_limits(bytes: &[u8], limits: NbtLimits) -> Result<(Option<Tag>, usize)> {
+        let mut reader = SliceReader::new(bytes, limits);
+
+        let tag = match reader.read_u8()? {
+            0x00 => None,
+            id => Some(reader.decode(id)?),
+        };
+        Ok((tag, reader.position()))
+    }
+}
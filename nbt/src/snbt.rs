@@ -0,0 +1,596 @@
+//! Stringified NBT as used by vanilla commands, e.g. `{foo:1b,bar:[I;1,2,3]}`
+use crate::{
+    error::{Error, Result},
+    io::Nbt,
+    limits::{NbtAccounter, NbtLimits},
+    types::{Compound, Tag},
+};
+use std::str::FromStr;
+
+const INDENT: &str = "    ";
+
+/// Parses a single SNBT value, the input may be surrounded by whitespace but must not contain anything else
+pub fn from_str(input: &str) -> Result<Tag> {
+    let mut parser = Parser::new(input);
+    let tag = parser.read_value()?;
+    parser.skip_whitespace();
+
+    if parser.peek().is_some() {
+        return Err(parser.error("Trailing data after value"));
+    }
+
+    Ok(tag)
+}
+
+/// Parses a compound at the start of `input`, returning it along with the number of bytes it took up
+pub(crate) fn compound_prefix(input: &str) -> Result<(Compound, usize)> {
+    let mut parser = Parser::new(input);
+    let compound = parser.read_compound()?;
+
+    Ok((compound, parser.position))
+}
+
+/// Parses a quoted string at the start of `input`, returning it along with the number of bytes it took up
+pub(crate) fn quoted_string_prefix(input: &str) -> Result<(String, usize)> {
+    let mut parser = Parser::new(input);
+    let value = parser.read_quoted_string()?;
+
+    Ok((value, parser.position))
+}
+
+/// Formats a tag on a single line without any whitespace
+pub fn to_string(tag: &Tag) -> String {
+    let mut out = String::new();
+    write_tag(&mut out, tag, None);
+    out
+}
+
+/// Formats a tag spread over multiple lines, indented by four spaces per level
+pub fn to_string_pretty(tag: &Tag) -> String {
+    let mut out = String::new();
+    write_tag(&mut out, tag, Some(0));
+    out
+}
+
+impl Tag {
+    pub fn from_snbt(input: &str) -> Result<Tag> {
+        from_str(input)
+    }
+
+    pub fn to_snbt(&self) -> String {
+        to_string(self)
+    }
+
+    pub fn to_snbt_pretty(&self) -> String {
+        to_string_pretty(self)
+    }
+}
+
+impl FromStr for Tag {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        from_str(s)
+    }
+}
+
+impl Nbt {
+    /// The root of the SNBT must be a compound, the title of the resulting `Nbt` is empty
+    pub fn from_snbt(input: &str) -> Result<Nbt> {
+        match from_str(input)? {
+            Tag::Compound(content) => Ok(Nbt {
+                title: String::new(),
+                content,
+            }),
+            other => Err(Error::InvalidRoot(other.id())),
+        }
+    }
+
+    /// The title is not part of SNBT and gets dropped
+    pub fn to_snbt(&self) -> String {
+        let mut out = String::new();
+        write_compound(&mut out, &self.content, None);
+        out
+    }
+
+    /// The title is not part of SNBT and gets dropped
+    pub fn to_snbt_pretty(&self) -> String {
+        let mut out = String::new();
+        write_compound(&mut out, &self.content, Some(0));
+        out
+    }
+}
+
+impl FromStr for Nbt {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Nbt::from_snbt(s)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// Only bounds the nesting depth, the size is already bounded by the input
+    accounter: NbtAccounter,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            position: 0,
+            accounter: NbtAccounter::new(NbtLimits::default()),
+        }
+    }
+
+    fn error(&self, reason: impl Into<String>) -> Error {
+        let consumed = &self.input[..self.position];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+
+        Error::InvalidSnbt {
+            line,
+            column,
+            reason: reason.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.next();
+                Ok(())
+            }
+            Some(c) => Err(self.error(format!("Expected '{expected}' but found '{c}'"))),
+            None => Err(self.error(format!("Expected '{expected}' but reached the end of input"))),
+        }
+    }
+
+    /// Consumes a `,` if present and returns whether another element follows it
+    fn has_element_separator(&mut self, close: char) -> Result<bool> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(',') => {
+                self.next();
+                Ok(true)
+            }
+            Some(c) if c == close => Ok(false),
+            Some(c) => Err(self.error(format!("Expected ',' or '{close}' but found '{c}'"))),
+            None => Err(self.error(format!("Expected ',' or '{close}' but reached the end of input"))),
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Tag> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => {
+                self.accounter.push()?;
+                let compound = self.read_compound()?;
+                self.accounter.pop();
+
+                Ok(Tag::Compound(compound))
+            }
+            Some('[') => {
+                self.accounter.push()?;
+                let tag = self.read_list_or_array()?;
+                self.accounter.pop();
+
+                Ok(tag)
+            }
+            Some('"' | '\'') => Ok(Tag::String(self.read_quoted_string()?)),
+            Some(_) => {
+                let start = self.position;
+                let token = self.read_unquoted_string();
+
+                if token.is_empty() {
+                    self.position = start;
+                    return Err(self.error("Expected a value"));
+                }
+
+                Ok(parse_unquoted(token))
+            }
+            None => Err(self.error("Expected a value but reached the end of input")),
+        }
+    }
+
+    fn read_key(&mut self) -> Result<String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('"' | '\'') => self.read_quoted_string(),
+            _ => {
+                let key = self.read_unquoted_string();
+
+                if key.is_empty() {
+                    return Err(self.error("Expected a key"));
+                }
+
+                Ok(key.to_string())
+            }
+        }
+    }
+
+    fn read_compound(&mut self) -> Result<Compound> {
+        self.expect('{')?;
+        let mut map = Compound::new();
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.next();
+            return Ok(map);
+        }
+
+        loop {
+            let key = self.read_key()?;
+            self.expect(':')?;
+            let value = self.read_value()?;
+            map.insert(key, value);
+
+            if !self.has_element_separator('}')? {
+                break;
+            }
+        }
+
+        self.expect('}')?;
+        Ok(map)
+    }
+
+    fn read_list_or_array(&mut self) -> Result<Tag> {
+        self.expect('[')?;
+
+        let rest = &self.input[self.position..];
+        let mut chars = rest.chars();
+        if let (Some(kind), Some(';')) = (chars.next(), chars.next()) {
+            if !matches!(kind, '"' | '\'') {
+                let start = self.position;
+                self.next();
+                self.next();
+
+                return match kind {
+                    'B' => Ok(Tag::ByteArray(self.read_array_elements(start, |tag| match tag {
+                        Tag::Byte(v) => Some(v),
+                        _ => None,
+                    })?)),
+                    'I' => Ok(Tag::IntArray(self.read_array_elements(start, |tag| match tag {
+                        Tag::Int(v) => Some(v),
+                        _ => None,
+                    })?)),
+                    'L' => Ok(Tag::LongArray(self.read_array_elements(start, |tag| match tag {
+                        Tag::Long(v) => Some(v),
+                        _ => None,
+                    })?)),
+                    _ => {
+                        self.position = start;
+                        Err(self.error(format!("Unknown array type '{kind}', expected 'B', 'I' or 'L'")))
+                    }
+                };
+            }
+        }
+
+        let mut values: Vec<Tag> = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(Tag::List(values));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let start = self.position;
+            let value = self.read_value()?;
+
+            if let Some(first) = values.first() {
+                if first.id() != value.id() {
+                    self.position = start;
+                    return Err(self.error(format!("Can't insert {} into a list of {}", value.tag_name(), first.tag_name())));
+                }
+            }
+
+            values.push(value);
+
+            if !self.has_element_separator(']')? {
+                break;
+            }
+        }
+
+        self.expect(']')?;
+        Ok(Tag::List(values))
+    }
+
+    fn read_array_elements<T>(&mut self, start: usize, extract: impl Fn(Tag) -> Option<T>) -> Result<Vec<T>> {
+        let mut values = vec![];
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.next();
+            return Ok(values);
+        }
+
+        loop {
+            self.skip_whitespace();
+            let element_start = self.position;
+            let value = self.read_value()?;
+            let name = value.tag_name().to_string();
+
+            match extract(value) {
+                Some(v) => values.push(v),
+                None => {
+                    self.position = element_start;
+                    let kind = self.input[start..].chars().next().unwrap_or_default();
+                    return Err(self.error(format!("Can't insert {name} into an array of type '{kind}'")));
+                }
+            }
+
+            if !self.has_element_separator(']')? {
+                break;
+            }
+        }
+
+        self.expect(']')?;
+        Ok(values)
+    }
+
+    fn read_unquoted_string(&mut self) -> &'a str {
+        let start = self.position;
+
+        while self.peek().is_some_and(is_unquoted_char) {
+            self.next();
+        }
+
+        &self.input[start..self.position]
+    }
+
+    fn read_quoted_string(&mut self) -> Result<String> {
+        let quote = match self.next() {
+            Some(c @ ('"' | '\'')) => c,
+            _ => return Err(self.error("Expected a quoted string")),
+        };
+        let mut value = String::new();
+
+        loop {
+            match self.next() {
+                Some('\\') => match self.next() {
+                    Some(c) if c == '\\' || c == quote => value.push(c),
+                    Some(c) => {
+                        self.position -= c.len_utf8();
+                        return Err(self.error(format!("Invalid escape sequence '\\{c}' in quoted string")));
+                    }
+                    None => return Err(self.error("Unterminated quoted string")),
+                },
+                Some(c) if c == quote => return Ok(value),
+                Some(c) => value.push(c),
+                None => return Err(self.error("Unterminated quoted string")),
+            }
+        }
+    }
+}
+
+fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+/// `[-+]?(0|[1-9][0-9]*)`
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+
+    match digits.as_bytes() {
+        [] => false,
+        [b'0'] => true,
+        [b'0', ..] => false,
+        bytes => bytes.iter().all(u8::is_ascii_digit),
+    }
+}
+
+/// `[-+]?([0-9]+[.]?|[0-9]*[.][0-9]+)(e[-+]?[0-9]+)?`, the dot is mandatory unless `dot_optional` is set
+fn is_decimal(s: &str, dot_optional: bool) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(index) => (&s[..index], Some(&s[index + 1..])),
+        None => (s, None),
+    };
+
+    let mantissa_valid = match mantissa.split_once('.') {
+        Some((int, frac)) => {
+            (!int.is_empty() || !frac.is_empty()) && int.bytes().all(|b| b.is_ascii_digit()) && frac.bytes().all(|b| b.is_ascii_digit())
+        }
+        None => dot_optional && !mantissa.is_empty() && mantissa.bytes().all(|b| b.is_ascii_digit()),
+    };
+
+    let exponent_valid = exponent.is_none_or(|e| {
+        let e = e.strip_prefix(['-', '+']).unwrap_or(e);
+        !e.is_empty() && e.bytes().all(|b| b.is_ascii_digit())
+    });
+
+    mantissa_valid && exponent_valid
+}
+
+/// Unquoted tokens are numbers if they match one of vanilla's number patterns and fit the type, booleans
+/// become bytes and everything else is a string
+fn parse_unquoted(token: &str) -> Tag {
+    let (body, suffix) = match token.char_indices().last() {
+        Some((index, c)) if c.is_ascii_alphabetic() => (&token[..index], Some(c.to_ascii_lowercase())),
+        _ => (token, None),
+    };
+
+    let number = match suffix {
+        Some('b') if is_integer(body) => body.parse().ok().map(Tag::Byte),
+        Some('s') if is_integer(body) => body.parse().ok().map(Tag::Short),
+        Some('l') if is_integer(body) => body.parse().ok().map(Tag::Long),
+        Some('f') if is_decimal(body, true) => body.parse().ok().map(Tag::Float),
+        Some('d') if is_decimal(body, true) => body.parse().ok().map(Tag::Double),
+        Some('f') if body == "NaN" => Some(Tag::Float(f32::NAN)),
+        Some('d') if body == "NaN" => Some(Tag::Double(f64::NAN)),
+        None if is_integer(token) => token.parse().ok().map(Tag::Int),
+        None if is_decimal(token, false) => token.parse().ok().map(Tag::Double),
+        _ => None,
+    };
+
+    number.unwrap_or_else(|| match token {
+        "true" => Tag::Byte(1),
+        "false" => Tag::Byte(0),
+        _ => Tag::String(token.to_string()),
+    })
+}
+
+pub(crate) fn write_quoted(out: &mut String, value: &str) {
+    let quote = if value.contains('"') && !value.contains('\'') { '\'' } else { '"' };
+
+    out.push(quote);
+    for c in value.chars() {
+        if c == '\\' || c == quote {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push(quote);
+}
+
+fn write_key(out: &mut String, key: &str) {
+    if !key.is_empty() && key.chars().all(is_unquoted_char) {
+        out.push_str(key);
+    } else {
+        write_quoted(out, key);
+    }
+}
+
+fn write_newline(out: &mut String, depth: usize) {
+    out.push('\n');
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+}
+
+/// `depth` is `None` for compact output and the current indentation level otherwise
+fn write_tag(out: &mut String, tag: &Tag, depth: Option<usize>) {
+    match tag {
+        Tag::Byte(v) => out.push_str(&format!("{v}b")),
+        Tag::Short(v) => out.push_str(&format!("{v}s")),
+        Tag::Int(v) => out.push_str(&v.to_string()),
+        Tag::Long(v) => out.push_str(&format!("{v}L")),
+        Tag::Float(v) if v.is_finite() => out.push_str(&format!("{v:?}f")),
+        Tag::Double(v) if v.is_finite() => out.push_str(&format!("{v:?}d")),
+        Tag::Float(v) => write_non_finite(out, *v as f64, 'f'),
+        Tag::Double(v) => write_non_finite(out, *v, 'd'),
+        Tag::String(v) => write_quoted(out, v),
+        Tag::ByteArray(v) => write_array(out, 'B', v.iter().map(|v| format!("{v}B")), depth),
+        Tag::IntArray(v) => write_array(out, 'I', v.iter().map(|v| v.to_string()), depth),
+        Tag::LongArray(v) => write_array(out, 'L', v.iter().map(|v| format!("{v}L")), depth),
+        Tag::List(v) => write_list(out, v, depth),
+        Tag::Compound(v) => write_compound(out, v, depth),
+    }
+}
+
+/// Infinities are written as a literal too large for either type, which vanilla reads back as infinite too. NaN has no
+/// literal at all, `NaNf` and `NaNd` are only understood by this parser.
+fn write_non_finite(out: &mut String, value: f64, suffix: char) {
+    if value.is_nan() {
+        out.push_str("NaN");
+    } else {
+        if value.is_sign_negative() {
+            out.push('-');
+        }
+        out.push_str("1e999");
+    }
+    out.push(suffix);
+}
+
+fn write_array(out: &mut String, kind: char, values: impl Iterator<Item = String>, depth: Option<usize>) {
+    out.push('[');
+    out.push(kind);
+    out.push(';');
+
+    for (i, value) in values.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        if depth.is_some() {
+            out.push(' ');
+        }
+        out.push_str(&value);
+    }
+
+    out.push(']');
+}
+
+fn write_list(out: &mut String, values: &[Tag], depth: Option<usize>) {
+    out.push('[');
+
+    match depth {
+        // Nested structures get one line per element, primitives stay on a single line
+        Some(depth) if values.iter().any(|v| matches!(v, Tag::Compound(_) | Tag::List(_))) => {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_newline(out, depth + 1);
+                write_tag(out, value, Some(depth + 1));
+            }
+            write_newline(out, depth);
+        }
+        _ => {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(if depth.is_some() { ", " } else { "," });
+                }
+                write_tag(out, value, depth);
+            }
+        }
+    }
+
+    out.push(']');
+}
+
+pub(crate) fn write_compound(out: &mut String, map: &Compound, depth: Option<usize>) {
+    out.push('{');
+
+    for (i, (key, value)) in map.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+
+        match depth {
+            Some(depth) => {
+                write_newline(out, depth + 1);
+                write_key(out, key);
+                out.push_str(": ");
+                write_tag(out, value, Some(depth + 1));
+            }
+            None => {
+                write_key(out, key);
+                out.push(':');
+                write_tag(out, value, None);
+            }
+        }
+    }
+
+    if let Some(depth) = depth {
+        if !map.is_empty() {
+            write_newline(out, depth);
+        }
+    }
+
+    out.push('}');
+}
@@ -1,6 +1,16 @@
-use std::{collections::HashMap, io::Result};
+use crate::{
+    borrowed::decode_array,
+    error::{Error, Result},
+    io::Encoding,
+    limits::MAX_PREALLOCATION,
+};
+use indexmap::IndexMap;
+use std::io;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+/// Compounds keep their entries in insertion order, so decoding and re-encoding a NBT yields the exact same bytes
+pub type Compound = IndexMap<String, Tag>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Tag {
     Byte(i8),
@@ -11,24 +21,173 @@ pub enum Tag {
     Double(f64),
     String(String),
     List(Vec<Tag>),
-    Compound(HashMap<String, Tag>),
+    Compound(Compound),
     ByteArray(Vec<i8>),
     IntArray(Vec<i32>),
     LongArray(Vec<i64>),
 }
 
+macro_rules! copy_accessor (
+    ($name: ident, $variant: ident, $type: ty) => (
+        pub fn $name(&self) -> Option<$type> {
+            match self {
+                Tag::$variant(value) => Some(*value),
+                _ => None,
+            }
+        }
+    )
+);
+
+macro_rules! ref_accessor (
+    ($name: ident, $name_mut: ident, $variant: ident, $type: ty) => (
+        pub fn $name(&self) -> Option<&$type> {
+            match self {
+                Tag::$variant(value) => Some(value),
+                _ => None,
+            }
+        }
+
+        pub fn $name_mut(&mut self) -> Option<&mut $type> {
+            match self {
+                Tag::$variant(value) => Some(value),
+                _ => None,
+            }
+        }
+    )
+);
+
+/// Accessors return `None` if the tag is of a different type, numbers are never converted
+impl Tag {
+    copy_accessor!(as_i8, Byte, i8);
+    copy_accessor!(as_i16, Short, i16);
+    copy_accessor!(as_i32, Int, i32);
+    copy_accessor!(as_i64, Long, i64);
+    copy_accessor!(as_f32, Float, f32);
+    copy_accessor!(as_f64, Double, f64);
+    ref_accessor!(as_list, as_list_mut, List, Vec<Tag>);
+    ref_accessor!(as_compound, as_compound_mut, Compound, Compound);
+    ref_accessor!(as_byte_array, as_byte_array_mut, ByteArray, Vec<i8>);
+    ref_accessor!(as_int_array, as_int_array_mut, IntArray, Vec<i32>);
+    ref_accessor!(as_long_array, as_long_array_mut, LongArray, Vec<i64>);
+
+    /// Booleans are stored as bytes
+    pub fn as_bool(&self) -> Option<bool> {
+        self.as_i8().map(|value| value != 0)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Entry of a compound, `None` for any other type
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        self.as_compound()?.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Tag> {
+        self.as_compound_mut()?.get_mut(name)
+    }
+}
+
+macro_rules! from_primitive (
+    ($type: ty, $variant: ident) => (
+        impl From<$type> for Tag {
+            fn from(value: $type) -> Self {
+                Tag::$variant(value)
+            }
+        }
+    )
+);
+
+from_primitive!(i8, Byte);
+from_primitive!(i16, Short);
+from_primitive!(i32, Int);
+from_primitive!(i64, Long);
+from_primitive!(f32, Float);
+from_primitive!(f64, Double);
+from_primitive!(String, String);
+from_primitive!(Compound, Compound);
+from_primitive!(Vec<Tag>, List);
+from_primitive!(Vec<i8>, ByteArray);
+from_primitive!(Vec<i32>, IntArray);
+from_primitive!(Vec<i64>, LongArray);
+
+impl From<bool> for Tag {
+    fn from(value: bool) -> Self {
+        Tag::Byte(value as i8)
+    }
+}
+
+impl From<&str> for Tag {
+    fn from(value: &str) -> Self {
+        Tag::String(value.to_string())
+    }
+}
+
+/// Vectors of bytes, ints and longs become typed arrays, everything else becomes a `TAG_List`
+macro_rules! from_list (
+    ($type: ty) => (
+        impl From<Vec<$type>> for Tag {
+            fn from(value: Vec<$type>) -> Self {
+                Tag::List(value.into_iter().map(Tag::from).collect())
+            }
+        }
+    )
+);
+
+from_list!(i16);
+from_list!(f32);
+from_list!(f64);
+from_list!(bool);
+from_list!(String);
+from_list!(&str);
+from_list!(Compound);
+
+/// Java and Bedrock little endian strings have a 16 bit length prefix
+pub(crate) fn string_len(len: usize) -> Result<u16> {
+    u16::try_from(len).map_err(|_| Error::StringTooLong(len))
+}
+
+/// Lists and arrays have a signed 32 bit length prefix in every encoding
+pub(crate) fn list_len(len: usize) -> Result<i32> {
+    i32::try_from(len).map_err(|_| Error::ListTooLong(len))
+}
+
+/// Bytes taken up by `len` elements of `width` bytes, which may not fit a `usize` on 32 bit targets
+pub(crate) fn array_bytes(len: usize, width: usize) -> Result<usize> {
+    len.checked_mul(width).ok_or(Error::ListTooLong(len))
+}
+
+/// Id of the elements of a list, `TAG_End` for empty ones. Checked before the list header is written, so a list of
+/// mixed types never leaves a header behind.
+pub(crate) fn list_id(values: &[Tag]) -> Result<u8> {
+    let id = values.first().map_or(0x00, Tag::id);
+    match values.iter().find(|value| value.id() != id) {
+        Some(value) => Err(Error::MixedListTypes {
+            expected: id,
+            found: value.id(),
+        }),
+        None => Ok(id),
+    }
+}
+
 pub(super) trait NbtWriter {
     async fn close_nbt(&mut self) -> Result<()>;
-    async fn write_bare_string(&mut self, value: &str) -> Result<()>;
+    async fn write_varint(&mut self, value: u64) -> Result<()>;
+    async fn write_len(&mut self, len: usize, encoding: Encoding) -> Result<()>;
+    async fn write_bare_string(&mut self, value: &str, encoding: Encoding) -> Result<()>;
     async fn write_bare_byte(&mut self, value: i8) -> Result<()>;
-    async fn write_bare_short(&mut self, value: i16) -> Result<()>;
-    async fn write_bare_int(&mut self, value: i32) -> Result<()>;
-    async fn write_bare_long(&mut self, value: i64) -> Result<()>;
-    async fn write_bare_float(&mut self, value: f32) -> Result<()>;
-    async fn write_bare_double(&mut self, value: f64) -> Result<()>;
-    async fn write_bare_int_array(&mut self, value: &[i32]) -> Result<()>;
-    async fn write_bare_long_array(&mut self, value: &[i64]) -> Result<()>;
-    async fn write_bare_byte_array(&mut self, value: &[i8]) -> Result<()>;
+    async fn write_bare_short(&mut self, value: i16, encoding: Encoding) -> Result<()>;
+    async fn write_bare_int(&mut self, value: i32, encoding: Encoding) -> Result<()>;
+    async fn write_bare_long(&mut self, value: i64, encoding: Encoding) -> Result<()>;
+    async fn write_bare_float(&mut self, value: f32, encoding: Encoding) -> Result<()>;
+    async fn write_bare_double(&mut self, value: f64, encoding: Encoding) -> Result<()>;
+    async fn write_bare_int_array(&mut self, value: &[i32], encoding: Encoding) -> Result<()>;
+    async fn write_bare_long_array(&mut self, value: &[i64], encoding: Encoding) -> Result<()>;
+    async fn write_bare_byte_array(&mut self, value: &[i8], encoding: Encoding) -> Result<()>;
 }
 
 impl<W: AsyncWrite + Unpin> NbtWriter for W {
@@ -37,11 +196,39 @@ impl<W: AsyncWrite + Unpin> NbtWriter for W {
         Ok(self.write_u8(0x00).await?)
     }
 
+    async fn write_varint(&mut self, mut value: u64) -> Result<()> {
+        while value >= 0x80 {
+            self.write_u8(value as u8 | 0x80).await?;
+            value >>= 7;
+        }
+
+        Ok(self.write_u8(value as u8).await?)
+    }
+
+    /// Length prefix of lists and arrays
     #[inline]
-    async fn write_bare_string(&mut self, value: &str) -> Result<()> {
-        let mod_utf8 = cesu8::to_java_cesu8(value);
-        self.write_u16(mod_utf8.len() as u16).await?;
-        Ok(self.write_all(&mod_utf8).await?)
+    async fn write_len(&mut self, len: usize, encoding: Encoding) -> Result<()> {
+        self.write_bare_int(list_len(len)?, encoding).await
+    }
+
+    #[inline]
+    async fn write_bare_string(&mut self, value: &str, encoding: Encoding) -> Result<()> {
+        match encoding {
+            Encoding::Java => {
+                let mod_utf8 = cesu8::to_java_cesu8(value);
+                self.write_u16(string_len(mod_utf8.len())?).await?;
+                Ok(self.write_all(&mod_utf8).await?)
+            }
+            Encoding::BedrockLittleEndian => {
+                self.write_u16_le(string_len(value.len())?).await?;
+                Ok(self.write_all(value.as_bytes()).await?)
+            }
+            Encoding::BedrockNetwork => {
+                let len = u32::try_from(value.len()).map_err(|_| Error::StringTooLong(value.len()))?;
+                self.write_varint(len as u64).await?;
+                Ok(self.write_all(value.as_bytes()).await?)
+            }
+        }
     }
 
     #[inline]
@@ -50,51 +237,68 @@ impl<W: AsyncWrite + Unpin> NbtWriter for W {
     }
 
     #[inline]
-    async fn write_bare_short(&mut self, value: i16) -> Result<()> {
-        Ok(self.write_i16(value).await?)
+    async fn write_bare_short(&mut self, value: i16, encoding: Encoding) -> Result<()> {
+        Ok(match encoding {
+            Encoding::Java => self.write_i16(value).await,
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => self.write_i16_le(value).await,
+        }?)
     }
 
     #[inline]
-    async fn write_bare_int(&mut self, value: i32) -> Result<()> {
-        Ok(self.write_i32(value).await?)
+    async fn write_bare_int(&mut self, value: i32, encoding: Encoding) -> Result<()> {
+        match encoding {
+            Encoding::Java => Ok(self.write_i32(value).await?),
+            Encoding::BedrockLittleEndian => Ok(self.write_i32_le(value).await?),
+            Encoding::BedrockNetwork => self.write_varint(((value << 1) ^ (value >> 31)) as u32 as u64).await,
+        }
     }
 
     #[inline]
-    async fn write_bare_long(&mut self, value: i64) -> Result<()> {
-        Ok(self.write_i64(value).await?)
+    async fn write_bare_long(&mut self, value: i64, encoding: Encoding) -> Result<()> {
+        match encoding {
+            Encoding::Java => Ok(self.write_i64(value).await?),
+            Encoding::BedrockLittleEndian => Ok(self.write_i64_le(value).await?),
+            Encoding::BedrockNetwork => self.write_varint(((value << 1) ^ (value >> 63)) as u64).await,
+        }
     }
 
     #[inline]
-    async fn write_bare_float(&mut self, value: f32) -> Result<()> {
-        Ok(self.write_f32(value).await?)
+    async fn write_bare_float(&mut self, value: f32, encoding: Encoding) -> Result<()> {
+        Ok(match encoding {
+            Encoding::Java => self.write_f32(value).await,
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => self.write_f32_le(value).await,
+        }?)
     }
 
     #[inline]
-    async fn write_bare_double(&mut self, value: f64) -> Result<()> {
-        Ok(self.write_f64(value).await?)
+    async fn write_bare_double(&mut self, value: f64, encoding: Encoding) -> Result<()> {
+        Ok(match encoding {
+            Encoding::Java => self.write_f64(value).await,
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => self.write_f64_le(value).await,
+        }?)
     }
 
     #[inline]
-    async fn write_bare_int_array(&mut self, value: &[i32]) -> Result<()> {
-        self.write_i32(value.len() as i32).await?;
+    async fn write_bare_int_array(&mut self, value: &[i32], encoding: Encoding) -> Result<()> {
+        self.write_len(value.len(), encoding).await?;
         for &v in value {
-            self.write_i32(v).await?;
+            self.write_bare_int(v, encoding).await?;
         }
         Ok(())
     }
 
     #[inline]
-    async fn write_bare_long_array(&mut self, value: &[i64]) -> Result<()> {
-        self.write_i32(value.len() as i32).await?;
+    async fn write_bare_long_array(&mut self, value: &[i64], encoding: Encoding) -> Result<()> {
+        self.write_len(value.len(), encoding).await?;
         for &v in value {
-            self.write_i64(v).await?;
+            self.write_bare_long(v, encoding).await?;
         }
         Ok(())
     }
 
     #[inline]
-    async fn write_bare_byte_array(&mut self, value: &[i8]) -> Result<()> {
-        self.write_i32(value.len() as i32).await?;
+    async fn write_bare_byte_array(&mut self, value: &[i8], encoding: Encoding) -> Result<()> {
+        self.write_len(value.len(), encoding).await?;
         for &v in value {
             self.write_i8(v).await?;
         }
@@ -103,30 +307,43 @@ impl<W: AsyncWrite + Unpin> NbtWriter for W {
 }
 
 pub(super) trait NbtReader {
-    async fn emit_next_header(&mut self) -> Result<(u8, String)>;
-    async fn read_bare_string(&mut self) -> Result<String>;
+    async fn read_varint(&mut self, max_bytes: u32) -> Result<u64>;
+    async fn read_len(&mut self, encoding: Encoding) -> Result<i32>;
+    async fn read_string_len(&mut self, encoding: Encoding) -> Result<usize>;
+    async fn read_bare_string(&mut self, encoding: Encoding) -> Result<String>;
     async fn read_bare_byte(&mut self) -> Result<i8>;
-    async fn read_bare_short(&mut self) -> Result<i16>;
-    async fn read_bare_int(&mut self) -> Result<i32>;
-    async fn read_bare_long(&mut self) -> Result<i64>;
-    async fn read_bare_float(&mut self) -> Result<f32>;
-    async fn read_bare_double(&mut self) -> Result<f64>;
-    async fn read_bare_int_array(&mut self) -> Result<Vec<i32>>;
-    async fn read_bare_long_array(&mut self) -> Result<Vec<i64>>;
-    async fn read_bare_byte_array(&mut self) -> Result<Vec<i8>>;
+    async fn read_bare_short(&mut self, encoding: Encoding) -> Result<i16>;
+    async fn read_bare_int(&mut self, encoding: Encoding) -> Result<i32>;
+    async fn read_bare_long(&mut self, encoding: Encoding) -> Result<i64>;
+    async fn read_bare_float(&mut self, encoding: Encoding) -> Result<f32>;
+    async fn read_bare_double(&mut self, encoding: Encoding) -> Result<f64>;
+    async fn read_bare_bytes(&mut self, len: usize) -> Result<Vec<u8>>;
+    async fn skip_bytes(&mut self, len: u64) -> Result<()>;
+    async fn read_bare_int_array(&mut self, len: usize, encoding: Encoding) -> Result<Vec<i32>>;
+    async fn read_bare_long_array(&mut self, len: usize, encoding: Encoding) -> Result<Vec<i64>>;
+    async fn read_bare_byte_array(&mut self, len: usize) -> Result<Vec<i8>>;
 }
 
 impl<R: AsyncRead + Unpin> NbtReader for R {
-    async fn emit_next_header(&mut self) -> Result<(u8, String)> {
-        let tag = self.read_u8().await?;
-
-        match tag {
-            0x00 => Ok((tag, "".to_string())),
-            _ => {
-                let name = self.read_bare_string().await?;
-                Ok((tag, name))
+    async fn read_varint(&mut self, max_bytes: u32) -> Result<u64> {
+        let mut value = 0;
+
+        for i in 0..max_bytes {
+            let byte = self.read_u8().await?;
+            value |= ((byte & 0x7f) as u64) << (7 * i);
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
             }
         }
+
+        Err(Error::InvalidVarInt)
+    }
+
+    /// Length prefix of lists and arrays
+    #[inline]
+    async fn read_len(&mut self, encoding: Encoding) -> Result<i32> {
+        self.read_bare_int(encoding).await
     }
 
     #[inline]
@@ -135,89 +352,153 @@ impl<R: AsyncRead + Unpin> NbtReader for R {
     }
 
     #[inline]
-    async fn read_bare_short(&mut self) -> Result<i16> {
-        Ok(self.read_i16().await?)
+    async fn read_bare_short(&mut self, encoding: Encoding) -> Result<i16> {
+        Ok(match encoding {
+            Encoding::Java => self.read_i16().await?,
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => self.read_i16_le().await?,
+        })
     }
 
     #[inline]
-    async fn read_bare_int(&mut self) -> Result<i32> {
-        Ok(self.read_i32().await?)
+    async fn read_bare_int(&mut self, encoding: Encoding) -> Result<i32> {
+        Ok(match encoding {
+            Encoding::Java => self.read_i32().await?,
+            Encoding::BedrockLittleEndian => self.read_i32_le().await?,
+            Encoding::BedrockNetwork => {
+                let value = self.read_varint(5).await? as u32;
+                (value >> 1) as i32 ^ -((value & 1) as i32)
+            }
+        })
     }
 
     #[inline]
-    async fn read_bare_long(&mut self) -> Result<i64> {
-        Ok(self.read_i64().await?)
+    async fn read_bare_long(&mut self, encoding: Encoding) -> Result<i64> {
+        Ok(match encoding {
+            Encoding::Java => self.read_i64().await?,
+            Encoding::BedrockLittleEndian => self.read_i64_le().await?,
+            Encoding::BedrockNetwork => {
+                let value = self.read_varint(10).await?;
+                (value >> 1) as i64 ^ -((value & 1) as i64)
+            }
+        })
     }
 
     #[inline]
-    async fn read_bare_float(&mut self) -> Result<f32> {
-        Ok(self.read_f32().await?)
+    async fn read_bare_float(&mut self, encoding: Encoding) -> Result<f32> {
+        Ok(match encoding {
+            Encoding::Java => self.read_f32().await?,
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => self.read_f32_le().await?,
+        })
     }
 
     #[inline]
-    async fn read_bare_double(&mut self) -> Result<f64> {
-        Ok(self.read_f64().await?)
+    async fn read_bare_double(&mut self, encoding: Encoding) -> Result<f64> {
+        Ok(match encoding {
+            Encoding::Java => self.read_f64().await?,
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => self.read_f64_le().await?,
+        })
     }
 
-    #[inline]
-    async fn read_bare_int_array(&mut self) -> Result<Vec<i32>> {
-        let len = self.read_i32().await? as usize;
-        let mut buf = Vec::with_capacity(len);
+    /// Never allocates more than has actually been read, whatever `len` claims
+    async fn read_bare_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(len.min(MAX_PREALLOCATION));
+        (&mut *self).take(len as u64).read_to_end(&mut bytes).await?;
 
-        for _ in 0..len {
-            buf.push(self.read_i32().await?);
+        if bytes.len() < len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
         }
 
-        Ok(buf)
+        Ok(bytes)
     }
 
-    #[inline]
-    async fn read_bare_long_array(&mut self) -> Result<Vec<i64>> {
-        let len = self.read_i32().await? as usize;
+    /// Discards `len` bytes through a small stack buffer rather than allocating
+    async fn skip_bytes(&mut self, len: u64) -> Result<()> {
+        let mut buf = [0; 512];
+        let mut remaining = len;
 
-        let mut buf = Vec::with_capacity(len);
-        for _ in 0..len {
-            buf.push(self.read_i64().await?);
+        while remaining > 0 {
+            let chunk = remaining.min(buf.len() as u64) as usize;
+            let read = self.read(&mut buf[..chunk]).await?;
+            if read == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            remaining -= read as u64;
         }
 
-        Ok(buf)
+        Ok(())
     }
 
     #[inline]
-    async fn read_bare_byte_array(&mut self) -> Result<Vec<i8>> {
-        let len = self.read_i32().await? as usize;
-        let mut buf = Vec::with_capacity(len);
+    async fn read_bare_int_array(&mut self, len: usize, encoding: Encoding) -> Result<Vec<i32>> {
+        match encoding {
+            Encoding::Java => Ok(decode_array(&self.read_bare_bytes(array_bytes(len, 4)?).await?)),
+            Encoding::BedrockLittleEndian => Ok(self
+                .read_bare_bytes(array_bytes(len, 4)?)
+                .await?
+                .chunks_exact(4)
+                .map(|chunk| i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect()),
+            Encoding::BedrockNetwork => {
+                let mut buf = Vec::with_capacity(len.min(MAX_PREALLOCATION));
+                for _ in 0..len {
+                    buf.push(self.read_bare_int(encoding).await?);
+                }
+                Ok(buf)
+            }
+        }
+    }
 
-        for _ in 0..len {
-            buf.push(self.read_i8().await?);
+    #[inline]
+    async fn read_bare_long_array(&mut self, len: usize, encoding: Encoding) -> Result<Vec<i64>> {
+        match encoding {
+            Encoding::Java => Ok(decode_array(&self.read_bare_bytes(array_bytes(len, 8)?).await?)),
+            Encoding::BedrockLittleEndian => Ok(self
+                .read_bare_bytes(array_bytes(len, 8)?)
+                .await?
+                .chunks_exact(8)
+                .map(|chunk| i64::from_le_bytes(chunk.try_into().expect("chunks_exact yields chunks of 8 bytes")))
+                .collect()),
+            Encoding::BedrockNetwork => {
+                let mut buf = Vec::with_capacity(len.min(MAX_PREALLOCATION));
+                for _ in 0..len {
+                    buf.push(self.read_bare_long(encoding).await?);
+                }
+                Ok(buf)
+            }
         }
+    }
+
+    #[inline]
+    async fn read_bare_byte_array(&mut self, len: usize) -> Result<Vec<i8>> {
+        Ok(decode_array(&self.read_bare_bytes(len).await?))
+    }
 
-        Ok(buf)
+    #[inline]
+    async fn read_string_len(&mut self, encoding: Encoding) -> Result<usize> {
+        Ok(match encoding {
+            Encoding::Java => self.read_u16().await? as usize,
+            Encoding::BedrockLittleEndian => self.read_u16_le().await? as usize,
+            Encoding::BedrockNetwork => self.read_varint(5).await? as u32 as usize,
+        })
     }
 
     #[inline]
-    async fn read_bare_string(&mut self) -> Result<String> {
-        let len = self.read_u16().await? as usize;
+    async fn read_bare_string(&mut self, encoding: Encoding) -> Result<String> {
+        let len = self.read_string_len(encoding).await?;
 
         if len == 0 {
             return Ok("".to_string());
         }
 
-        let mut bytes = vec![0; len];
-        self.read_exact(&mut bytes).await?;
-
-        let java_decoded = match cesu8::from_java_cesu8(&bytes) {
-            Ok(string) => string,
-            Err(_) => panic!("InvalidCesu8String"),
-        };
+        let bytes = self.read_bare_bytes(len).await?;
 
-        Ok(if let Ok(string) = std::str::from_utf8(java_decoded.as_bytes()) {
-            string.into()
-        } else {
-            let lossy_string = String::from_utf8_lossy(java_decoded.as_bytes()).into_owned();
-            println!("Error decoding utf8 (bytes: {bytes:?}, lossy: \"{lossy_string})\"");
-            lossy_string
-        })
+        match encoding {
+            Encoding::Java => match cesu8::from_java_cesu8(&bytes) {
+                Ok(string) => Ok(string.into_owned()),
+                Err(_) => Err(Error::InvalidMutf8),
+            },
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => String::from_utf8(bytes).map_err(|_| Error::InvalidUtf8),
+        }
     }
 }
 
@@ -250,3 +531,77 @@ impl<R: AsyncRead + Unpin> NbtReader for R {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn array_lengths_that_overflow_are_errors() {
+        let len = usize::MAX / 4 + 1;
+        for encoding in [Encoding::Java, Encoding::BedrockLittleEndian] {
+            assert!(matches!(
+                block_on((&mut &[][..]).read_bare_int_array(len, encoding)),
+                Err(Error::ListTooLong(found)) if found == len
+            ));
+            assert!(matches!(
+                block_on((&mut &[][..]).read_bare_long_array(len, encoding)),
+                Err(Error::ListTooLong(found)) if found == len
+            ));
+        }
+    }
+
+    #[test]
+    fn primitives_become_the_tag_of_their_type() {
+        assert_eq!(Tag::from(1i8), Tag::Byte(1));
+        assert_eq!(Tag::from(2i16), Tag::Short(2));
+        assert_eq!(Tag::from(3i32), Tag::Int(3));
+        assert_eq!(Tag::from(4i64), Tag::Long(4));
+        assert_eq!(Tag::from(0.5f32), Tag::Float(0.5));
+        assert_eq!(Tag::from(0.25f64), Tag::Double(0.25));
+        assert_eq!(Tag::from(true), Tag::Byte(1));
+        assert_eq!(Tag::from(false), Tag::Byte(0));
+        assert_eq!(Tag::from("a"), Tag::String(String::from("a")));
+        assert_eq!(Tag::from(String::from("b")), Tag::String(String::from("b")));
+        assert_eq!(Tag::from(Compound::new()), Tag::Compound(Compound::new()));
+    }
+
+    #[test]
+    fn vectors_of_bytes_ints_and_longs_become_arrays() {
+        assert_eq!(Tag::from(vec![1i8, 2]), Tag::ByteArray(vec![1, 2]));
+        assert_eq!(Tag::from(vec![3i32]), Tag::IntArray(vec![3]));
+        assert_eq!(Tag::from(Vec::<i64>::new()), Tag::LongArray(vec![]));
+
+        assert_eq!(Tag::from(vec![1i16, 2]), Tag::List(vec![Tag::Short(1), Tag::Short(2)]));
+        assert_eq!(Tag::from(vec![0.5f32]), Tag::List(vec![Tag::Float(0.5)]));
+        assert_eq!(Tag::from(vec![0.5f64]), Tag::List(vec![Tag::Double(0.5)]));
+        assert_eq!(Tag::from(vec![true, false]), Tag::List(vec![Tag::Byte(1), Tag::Byte(0)]));
+        assert_eq!(Tag::from(vec!["a"]), Tag::from(vec![String::from("a")]));
+        assert_eq!(Tag::from(vec![Compound::new()]), Tag::List(vec![Tag::Compound(Compound::new())]));
+        assert_eq!(Tag::from(vec![Tag::Int(1)]), Tag::List(vec![Tag::Int(1)]));
+    }
+
+    #[test]
+    fn accessors_never_convert_between_types() {
+        assert_eq!(Tag::Int(1).as_i32(), Some(1));
+        assert_eq!(Tag::Int(1).as_i64(), None);
+        assert_eq!(Tag::Short(1).as_i32(), None);
+        assert_eq!(Tag::Float(1.0).as_f64(), None);
+        assert_eq!(Tag::Byte(2).as_bool(), Some(true));
+        assert_eq!(Tag::Int(1).as_bool(), None);
+        assert_eq!(Tag::from("a").as_str(), Some("a"));
+        assert_eq!(Tag::ByteArray(vec![1]).as_list(), None);
+        assert_eq!(Tag::List(vec![]).as_byte_array(), None);
+
+        let mut tag = Tag::from(Compound::from_iter([(String::from("x"), Tag::Int(1))]));
+        assert_eq!(tag.get("x"), Some(&Tag::Int(1)));
+        assert_eq!(tag.get("y"), None);
+        *tag.get_mut("x").unwrap() = Tag::Int(2);
+        assert_eq!(tag.get("x").and_then(Tag::as_i32), Some(2));
+        assert_eq!(Tag::List(vec![]).get("x"), None);
+    }
+}
@@ -0,0 +1,477 @@
+//! Event based reading of NBT, for pulling a few values out of large inputs without building the whole tree
+//!
+//! ```no_run
+//! use nbt::{io::Nbt, visit::{Flow, NbtVisitor}};
+//!
+//! #[derive(Default)]
+//! struct ChunkPosition {
+//!     next: Option<&'static str>,
+//!     x: Option<i32>,
+//!     z: Option<i32>,
+//! }
+//!
+//! impl NbtVisitor for ChunkPosition {
+//!     fn visit_entry(&mut self, _id: u8, name: &str) -> Flow {
+//!         self.next = match name {
+//!             "xPos" => Some("xPos"),
+//!             "zPos" => Some("zPos"),
+//!             _ => return Flow::Skip,
+//!         };
+//!         Flow::Visit
+//!     }
+//!
+//!     fn visit_int(&mut self, value: i32) {
+//!         match self.next {
+//!             Some("xPos") => self.x = Some(value),
+//!             _ => self.z = Some(value),
+//!         }
+//!     }
+//! }
+//!
+//! # async fn run(mut chunk: &[u8]) -> nbt::Result<()> {
+//! let mut position = ChunkPosition::default();
+//! Nbt::visit_reader(&mut chunk, &mut position).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    error::{Error, Result},
+    io::{Encoding, Nbt, OffsetReader},
+    limits::{NbtAccounter, NbtLimits, MAX_PREALLOCATION},
+    slice::fixed_width,
+    types::{Compound, NbtReader, Tag},
+};
+use std::{borrow::Cow, io};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// What the reader does with the value following a root or compound entry
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Flow {
+    /// Reads the value and reports it to the visitor
+    #[default]
+    Visit,
+    /// Consumes the value without reporting it or allocating anything for it
+    Skip,
+    /// Stops reading right away, leaving the rest of the input unread
+    Stop,
+}
+
+/// Receives the values of an NBT while it is being read
+///
+/// Every value is announced by `visit_root` or, inside compounds, by `visit_entry`, whose [`Flow`] decides whether
+/// it is read at all. List elements are reported in order between `start_list` and `end_list`, compound entries
+/// between `start_compound` and `end_compound`.
+pub trait NbtVisitor {
+    /// The name is empty for networked NBT's
+    fn visit_root(&mut self, _id: u8, _name: &str) -> Flow {
+        Flow::Visit
+    }
+
+    fn visit_entry(&mut self, _id: u8, _name: &str) -> Flow {
+        Flow::Visit
+    }
+
+    fn visit_byte(&mut self, _value: i8) {}
+    fn visit_short(&mut self, _value: i16) {}
+    fn visit_int(&mut self, _value: i32) {}
+    fn visit_long(&mut self, _value: i64) {}
+    fn visit_float(&mut self, _value: f32) {}
+    fn visit_double(&mut self, _value: f64) {}
+    fn visit_string(&mut self, _value: String) {}
+    fn visit_byte_array(&mut self, _value: Vec<i8>) {}
+    fn visit_int_array(&mut self, _value: Vec<i32>) {}
+    fn visit_long_array(&mut self, _value: Vec<i64>) {}
+    fn start_list(&mut self, _id: u8, _len: usize) {}
+    fn end_list(&mut self) {}
+    fn start_compound(&mut self) {}
+    fn end_compound(&mut self) {}
+}
+
+impl Nbt {
+    /// Streams an uncompressed NBT to `visitor` instead of building it in memory
+    pub async fn visit_reader<R, V>(reader: &mut R, visitor: &mut V) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send,
+        V: NbtVisitor,
+    {
+        Self::visit_encoded_reader_with_limits(reader, Encoding::Java, NbtLimits::default(), visitor).await
+    }
+
+    /// Skipped values count towards the limits just like visited ones
+    pub async fn visit_encoded_reader_with_limits<R, V>(
+        reader: &mut R,
+        encoding: Encoding,
+        limits: NbtLimits,
+        visitor: &mut V,
+    ) -> Result<()>
+    where
+        R: AsyncRead + Unpin + Send,
+        V: NbtVisitor,
+    {
+        let mut reader = OffsetReader::new(reader);
+        let result = EventReader::new(&mut reader, encoding, limits).read_root(visitor).await;
+
+        reader.locate(result)
+    }
+}
+
+enum Frame {
+    List { id: u8, remaining: usize },
+    Compound,
+}
+
+/// Drives an [`NbtVisitor`], keeping open lists and compounds on an explicit stack rather than recursing
+pub(crate) struct EventReader<'a, R> {
+    reader: &'a mut R,
+    encoding: Encoding,
+    accounter: NbtAccounter,
+    /// Reused for every entry name, so skipping entries does not allocate
+    name: Vec<u8>,
+}
+
+impl<'a, R> EventReader<'a, R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    pub(crate) fn new(reader: &'a mut R, encoding: Encoding, limits: NbtLimits) -> Self {
+        Self {
+            reader,
+            encoding,
+            accounter: NbtAccounter::new(limits),
+            name: Vec::new(),
+        }
+    }
+
+    /// Reads a named root, which has to be a `TAG_Compound`
+    async fn read_root<V: NbtVisitor>(&mut self, visitor: &mut V) -> Result<()> {
+        let id = self.reader.read_u8().await?;
+
+        if id != 0x0a {
+            return Err(Error::InvalidRoot(id));
+        }
+
+        let flow = {
+            let name = self.read_name().await?;
+            visitor.visit_root(id, &name)
+        };
+
+        match flow {
+            Flow::Visit => self.read_value(id, visitor).await,
+            Flow::Skip => self.skip(id).await,
+            Flow::Stop => Ok(()),
+        }
+    }
+
+    pub(crate) async fn read_value<V: NbtVisitor>(&mut self, id: u8, visitor: &mut V) -> Result<()> {
+        let encoding = self.encoding;
+        let mut stack = Vec::new();
+        let mut next = Some(id);
+
+        loop {
+            if let Some(id) = next.take() {
+                self.accounter.charge_tag()?;
+
+                match id {
+                    0x01 => visitor.visit_byte(self.reader.read_bare_byte().await?),
+                    0x02 => visitor.visit_short(self.reader.read_bare_short(encoding).await?),
+                    0x03 => visitor.visit_int(self.reader.read_bare_int(encoding).await?),
+                    0x04 => visitor.visit_long(self.reader.read_bare_long(encoding).await?),
+                    0x05 => visitor.visit_float(self.reader.read_bare_float(encoding).await?),
+                    0x06 => visitor.visit_double(self.reader.read_bare_double(encoding).await?),
+                    0x07 => {
+                        let len = self.accounter.array_len(self.reader.read_len(encoding).await?, 1)?;
+                        visitor.visit_byte_array(self.reader.read_bare_byte_array(len).await?);
+                    }
+                    0x08 => {
+                        let value = self.reader.read_bare_string(encoding).await?;
+                        self.accounter.charge(value.len() as u64)?;
+                        visitor.visit_string(value);
+                    }
+                    0x09 => {
+                        let id = self.reader.read_u8().await?;
+                        let len = self.accounter.list_len(self.reader.read_len(encoding).await?)?;
+
+                        self.accounter.push()?;
+                        visitor.start_list(id, len);
+                        stack.push(Frame::List { id, remaining: len });
+                    }
+                    0x0a => {
+                        self.accounter.push()?;
+                        visitor.start_compound();
+                        stack.push(Frame::Compound);
+                    }
+                    0x0b => {
+                        let len = self.accounter.array_len(self.reader.read_len(encoding).await?, 4)?;
+                        visitor.visit_int_array(self.reader.read_bare_int_array(len, encoding).await?);
+                    }
+                    0x0c => {
+                        let len = self.accounter.array_len(self.reader.read_len(encoding).await?, 8)?;
+                        visitor.visit_long_array(self.reader.read_bare_long_array(len, encoding).await?);
+                    }
+                    _ => return Err(Error::UnknownTagId(id)),
+                }
+            }
+
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::List { remaining: 0, .. }) => {
+                    stack.pop();
+                    self.accounter.pop();
+                    visitor.end_list();
+                }
+                Some(Frame::List { id, remaining }) => {
+                    *remaining -= 1;
+                    next = Some(*id);
+                }
+                Some(Frame::Compound) => {
+                    let id = self.reader.read_u8().await?;
+
+                    if id == 0x00 {
+                        stack.pop();
+                        self.accounter.pop();
+                        visitor.end_compound();
+                        continue;
+                    }
+
+                    let flow = {
+                        let name = self.read_name().await?;
+                        visitor.visit_entry(id, &name)
+                    };
+
+                    match flow {
+                        Flow::Visit => next = Some(id),
+                        Flow::Skip => self.skip(id).await?,
+                        Flow::Stop => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Consumes a value without decoding it, enforcing the same limits as reading it would
+    async fn skip(&mut self, id: u8) -> Result<()> {
+        let encoding = self.encoding;
+        let mut stack = Vec::new();
+        let mut next = Some(id);
+
+        loop {
+            if let Some(id) = next.take() {
+                self.accounter.charge_tag()?;
+
+                match id {
+                    0x01..=0x06 => self.skip_primitives(id, 1).await?,
+                    0x07 => {
+                        let len = self.accounter.array_len(self.reader.read_len(encoding).await?, 1)?;
+                        self.reader.skip_bytes(len as u64).await?;
+                    }
+                    0x08 => {
+                        let len = self.reader.read_string_len(encoding).await?;
+                        self.accounter.charge(len as u64)?;
+                        self.reader.skip_bytes(len as u64).await?;
+                    }
+                    0x09 => {
+                        let id = self.reader.read_u8().await?;
+                        let len = self.accounter.list_len(self.reader.read_len(encoding).await?)?;
+
+                        match id {
+                            // Lists of primitives are skipped in one go
+                            0x01..=0x06 => {
+                                self.accounter.push()?;
+                                self.accounter.charge_tags(len)?;
+                                self.skip_primitives(id, len).await?;
+                                self.accounter.pop();
+                            }
+                            0x00 if len > 0 => return Err(Error::UnknownTagId(id)),
+                            _ => {
+                                self.accounter.push()?;
+                                stack.push(Frame::List { id, remaining: len });
+                            }
+                        }
+                    }
+                    0x0a => {
+                        self.accounter.push()?;
+                        stack.push(Frame::Compound);
+                    }
+                    0x0b => {
+                        let len = self.accounter.array_len(self.reader.read_len(encoding).await?, 4)?;
+                        self.skip_primitives(0x03, len).await?;
+                    }
+                    0x0c => {
+                        let len = self.accounter.array_len(self.reader.read_len(encoding).await?, 8)?;
+                        self.skip_primitives(0x04, len).await?;
+                    }
+                    _ => return Err(Error::UnknownTagId(id)),
+                }
+            }
+
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::List { remaining: 0, .. }) => {
+                    stack.pop();
+                    self.accounter.pop();
+                }
+                Some(Frame::List { id, remaining }) => {
+                    *remaining -= 1;
+                    next = Some(*id);
+                }
+                Some(Frame::Compound) => match self.reader.read_u8().await? {
+                    0x00 => {
+                        stack.pop();
+                        self.accounter.pop();
+                    }
+                    id => {
+                        let len = self.reader.read_string_len(encoding).await?;
+                        self.accounter.charge(len as u64)?;
+                        self.reader.skip_bytes(len as u64).await?;
+                        next = Some(id);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Skips `count` payloads of a primitive type, only varints have to be looked at one by one
+    async fn skip_primitives(&mut self, id: u8, count: usize) -> Result<()> {
+        match (id, self.encoding) {
+            (0x03, Encoding::BedrockNetwork) => {
+                for _ in 0..count {
+                    self.reader.read_varint(5).await?;
+                }
+            }
+            (0x04, Encoding::BedrockNetwork) => {
+                for _ in 0..count {
+                    self.reader.read_varint(10).await?;
+                }
+            }
+            _ => {
+                let width = fixed_width(id).unwrap_or_default() as u64;
+                self.reader.skip_bytes(width.saturating_mul(count as u64)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn read_name(&mut self) -> Result<Cow<'_, str>> {
+        let len = self.reader.read_string_len(self.encoding).await?;
+        self.accounter.charge(len as u64)?;
+
+        self.name.clear();
+        (&mut *self.reader).take(len as u64).read_to_end(&mut self.name).await?;
+        if self.name.len() < len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        match self.encoding {
+            Encoding::Java => cesu8::from_java_cesu8(&self.name).map_err(|_| Error::InvalidMutf8),
+            Encoding::BedrockLittleEndian | Encoding::BedrockNetwork => {
+                std::str::from_utf8(&self.name).map(Cow::Borrowed).map_err(|_| Error::InvalidUtf8)
+            }
+        }
+    }
+}
+
+/// Builds the tree the `from_*reader` functions return
+#[derive(Default)]
+pub(crate) struct TreeBuilder {
+    title: String,
+    /// Name the next value is stored under in its compound
+    name: Option<String>,
+    /// Lists and compounds which have been started but not ended yet, along with the name they are stored under
+    open: Vec<(Option<String>, Tag)>,
+    root: Option<Tag>,
+}
+
+impl TreeBuilder {
+    /// The title and the root, which is missing if reading stopped early
+    pub(crate) fn finish(self) -> (String, Option<Tag>) {
+        (self.title, self.root)
+    }
+
+    fn add(&mut self, tag: Tag) {
+        match self.open.last_mut() {
+            Some((_, Tag::List(values))) => values.push(tag),
+            Some((_, Tag::Compound(map))) => {
+                map.insert(self.name.take().unwrap_or_default(), tag);
+            }
+            _ => self.root = Some(tag),
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some((name, tag)) = self.open.pop() {
+            self.name = name;
+            self.add(tag);
+        }
+    }
+}
+
+impl NbtVisitor for TreeBuilder {
+    fn visit_root(&mut self, _id: u8, name: &str) -> Flow {
+        self.title = name.to_string();
+        Flow::Visit
+    }
+
+    fn visit_entry(&mut self, _id: u8, name: &str) -> Flow {
+        self.name = Some(name.to_string());
+        Flow::Visit
+    }
+
+    fn visit_byte(&mut self, value: i8) {
+        self.add(Tag::Byte(value));
+    }
+
+    fn visit_short(&mut self, value: i16) {
+        self.add(Tag::Short(value));
+    }
+
+    fn visit_int(&mut self, value: i32) {
+        self.add(Tag::Int(value));
+    }
+
+    fn visit_long(&mut self, value: i64) {
+        self.add(Tag::Long(value));
+    }
+
+    fn visit_float(&mut self, value: f32) {
+        self.add(Tag::Float(value));
+    }
+
+    fn visit_double(&mut self, value: f64) {
+        self.add(Tag::Double(value));
+    }
+
+    fn visit_string(&mut self, value: String) {
+        self.add(Tag::String(value));
+    }
+
+    fn visit_byte_array(&mut self, value: Vec<i8>) {
+        self.add(Tag::ByteArray(value));
+    }
+
+    fn visit_int_array(&mut self, value: Vec<i32>) {
+        self.add(Tag::IntArray(value));
+    }
+
+    fn visit_long_array(&mut self, value: Vec<i64>) {
+        self.add(Tag::LongArray(value));
+    }
+
+    fn start_list(&mut self, _id: u8, len: usize) {
+        let name = self.name.take();
+        self.open.push((name, Tag::List(Vec::with_capacity(len.min(MAX_PREALLOCATION)))));
+    }
+
+    fn end_list(&mut self) {
+        self.close();
+    }
+
+    fn start_compound(&mut self) {
+        let name = self.name.take();
+        self.open.push((name, Tag::Compound(Compound::new())));
+    }
+
+    fn end_compound(&mut self) {
+        self.close();
+    }
+}
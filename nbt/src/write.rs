@@ -0,0 +1,91 @@
+//! Synchronous NBT writing into any `std::io::Write`, for when the output ends up in memory anyway (packet buffers,
+//! region files before compression, ...) and the async `NbtWriter` would cost an await per primitive. Like the slice
+//! reader, only the `Java` encoding is supported.
+use crate::{
+    error::Result,
+    io::Nbt,
+    types::{list_id, list_len, string_len, Tag},
+};
+use byteorder::{BigEndian, WriteBytesExt};
+use std::io::Write;
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    let mod_utf8 = cesu8::to_java_cesu8(value);
+    writer.write_u16::<BigEndian>(string_len(mod_utf8.len())?)?;
+    Ok(writer.write_all(&mod_utf8)?)
+}
+
+fn write_len<W: Write>(writer: &mut W, len: usize) -> Result<()> {
+    Ok(writer.write_i32::<BigEndian>(list_len(len)?)?)
+}
+
+impl Nbt {
+    /// Synchronous counterpart to `to_writer`
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u8(0x0a)?;
+        write_string(writer, self.title())?;
+        Tag::write_compound(writer, self)
+    }
+
+    /// Synchronous counterpart to `to_networked_writer`
+    pub fn write_networked<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u8(0x0a)?;
+        Tag::write_compound(writer, self)
+    }
+}
+
+impl Tag {
+    /// Synchronous counterpart to `to_networked_writer`
+    pub fn write_networked<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u8(self.id())?;
+        self.write_payload(writer)
+    }
+
+    fn write_compound<'a, W: Write>(writer: &mut W, entries: impl IntoIterator<Item = (&'a String, &'a Tag)>) -> Result<()> {
+        for (name, tag) in entries {
+            writer.write_u8(tag.id())?;
+            write_string(writer, name)?;
+            tag.write_payload(writer)?;
+        }
+
+        Ok(writer.write_u8(0x00)?)
+    }
+
+    fn write_payload<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            Tag::Byte(value) => writer.write_i8(*value)?,
+            Tag::Short(value) => writer.write_i16::<BigEndian>(*value)?,
+            Tag::Int(value) => writer.write_i32::<BigEndian>(*value)?,
+            Tag::Long(value) => writer.write_i64::<BigEndian>(*value)?,
+            Tag::Float(value) => writer.write_f32::<BigEndian>(*value)?,
+            Tag::Double(value) => writer.write_f64::<BigEndian>(*value)?,
+            Tag::String(value) => write_string(writer, value)?,
+            Tag::List(values) => {
+                writer.write_u8(list_id(values)?)?;
+                write_len(writer, values.len())?;
+
+                for value in values {
+                    value.write_payload(writer)?;
+                }
+            }
+            Tag::Compound(values) => Tag::write_compound(writer, values)?,
+            Tag::ByteArray(values) => {
+                write_len(writer, values.len())?;
+                let bytes: Vec<u8> = values.iter().map(|&value| value as u8).collect();
+                writer.write_all(&bytes)?;
+            }
+            Tag::IntArray(values) => {
+                write_len(writer, values.len())?;
+                let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_be_bytes()).collect();
+                writer.write_all(&bytes)?;
+            }
+            Tag::LongArray(values) => {
+                write_len(writer, values.len())?;
+                let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_be_bytes()).collect();
+                writer.write_all(&bytes)?;
+            }
+        }
+
+        Ok(())
+    }
+}
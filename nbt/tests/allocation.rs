@@ -0,0 +1,42 @@
+//! Checks on how much memory untrusted input can reserve. A test binary of its own, since it swaps out the allocator.
+use nbt::{types::Tag, NbtLimits};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Records the largest single allocation made
+struct Largest;
+
+static LARGEST: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Largest {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LARGEST.fetch_max(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Largest = Largest;
+
+#[test]
+fn list_lengths_are_not_trusted_for_preallocation() {
+    // Lists of lists, each claiming i32::MAX elements, padded to the size of a large packet
+    let mut bytes = vec![0x09];
+    for _ in 0..64 {
+        bytes.extend_from_slice(&[0x09, 0x7f, 0xff, 0xff, 0xff]);
+    }
+    bytes.resize(0x200000, 0x00);
+
+    assert!(Tag::from_networked_prefix_with_limits(&bytes, NbtLimits::network()).is_err());
+    assert!(
+        LARGEST.load(Ordering::Relaxed) < 0x400000,
+        "{} bytes reserved at once",
+        LARGEST.load(Ordering::Relaxed)
+    );
+}
@@ -0,0 +1,19 @@
+use nbt::borrowed::{NbtRef, TagRef};
+
+#[test]
+fn array_indices_past_the_end_are_none() {
+    // An unnamed root holding a long array `a` with a single element
+    let mut bytes = vec![0x0a, 0, 0, 0x0c, 0, 1, b'a', 0, 0, 0, 1];
+    bytes.extend(7i64.to_be_bytes());
+    bytes.push(0);
+
+    let nbt = NbtRef::new(&bytes).unwrap();
+    let Some(TagRef::LongArray(array)) = nbt.get("a") else {
+        panic!("expected a long array");
+    };
+    assert_eq!(array.get(0), Some(7));
+    assert_eq!(array.get(1), None);
+    // The start of the element still fits in a usize but its end does not
+    assert_eq!(array.get(usize::MAX / 8), None);
+    assert_eq!(array.get(usize::MAX), None);
+}
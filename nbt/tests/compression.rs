@@ -0,0 +1,83 @@
+use nbt::{
+    io::{Compression, Nbt},
+    limits::Limit,
+    types::Tag,
+    Error, NbtLimits,
+};
+use std::future::Future;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+fn level() -> Nbt {
+    let mut nbt = nbt::nbt! {
+        "name": "world",
+        "spawn": { "x": 1, "y": 64i16, "z": -3i64 },
+        "heights": [L; 7, 8, 9],
+    };
+    nbt.set_title("Level");
+    nbt
+}
+
+fn compress(nbt: &Nbt, compression: Compression) -> Vec<u8> {
+    let mut bytes = vec![];
+    block_on(nbt.to_compressed_writer(&mut bytes, compression)).unwrap();
+    bytes
+}
+
+#[test]
+fn compression_is_detected_from_the_magic_bytes() {
+    assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08]), Compression::Gzip);
+    // The usual levels of zlib, each header being a multiple of 31
+    for level in [0x01, 0x5e, 0x9c, 0xda] {
+        assert_eq!(Compression::detect(&[0x78, level]), Compression::Zlib);
+    }
+
+    assert_eq!(Compression::detect(&[0x0a, 0x00, 0x00]), Compression::Uncompressed);
+    // Deflate, but the check bits don't add up
+    assert_eq!(Compression::detect(&[0x78, 0x00]), Compression::Uncompressed);
+    assert_eq!(Compression::detect(&[0x1f]), Compression::Uncompressed);
+    assert_eq!(Compression::detect(&[]), Compression::Uncompressed);
+}
+
+#[test]
+fn written_compression_is_detected_and_read_back() {
+    for compression in [Compression::Uncompressed, Compression::Gzip, Compression::Zlib] {
+        let bytes = compress(&level(), compression);
+        assert_eq!(Compression::detect(&bytes), compression);
+        assert_eq!(block_on(Nbt::from_any_reader(&mut bytes.as_slice())).unwrap(), level());
+    }
+
+    let gzip = compress(&level(), Compression::Gzip);
+    assert_eq!(block_on(Nbt::from_gzip_reader(&mut gzip.as_slice())).unwrap(), level());
+    let zlib = compress(&level(), Compression::Zlib);
+    assert_eq!(block_on(Nbt::from_zlib_reader(&mut zlib.as_slice())).unwrap(), level());
+
+    // Reading with the wrong decoder fails instead of returning garbage
+    assert!(block_on(Nbt::from_zlib_reader(&mut gzip.as_slice())).is_err());
+    assert!(block_on(Nbt::from_gzip_reader(&mut zlib.as_slice())).is_err());
+}
+
+#[test]
+fn limits_apply_to_the_decompressed_data() {
+    // A megabyte of zeros compresses to a few kilobytes
+    let bomb = nbt::nbt! { "longs": Tag::LongArray(vec![0; 128 * 1024]) };
+    let limits = NbtLimits {
+        max_bytes: 64 * 1024,
+        ..NbtLimits::default()
+    };
+    let exceeded = |result: nbt::Result<Nbt>| matches!(result, Err(Error::LimitExceeded { limit: Limit::Bytes, .. }));
+
+    let gzip = compress(&bomb, Compression::Gzip);
+    assert!(gzip.len() < 64 * 1024);
+    assert!(exceeded(block_on(Nbt::from_gzip_reader_with_limits(&mut gzip.as_slice(), limits))));
+    assert!(exceeded(block_on(Nbt::from_any_reader_with_limits(&mut gzip.as_slice(), limits))));
+
+    let zlib = compress(&bomb, Compression::Zlib);
+    assert!(zlib.len() < 64 * 1024);
+    assert!(exceeded(block_on(Nbt::from_zlib_reader_with_limits(&mut zlib.as_slice(), limits))));
+    assert!(exceeded(block_on(Nbt::from_any_reader_with_limits(&mut zlib.as_slice(), limits))));
+
+    assert_eq!(block_on(Nbt::from_gzip_reader(&mut gzip.as_slice())).unwrap(), bomb);
+}
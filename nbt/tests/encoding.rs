@@ -0,0 +1,148 @@
+use nbt::{
+    io::{Encoding, Nbt},
+    types::Tag,
+    Error,
+};
+use std::future::Future;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+fn write(nbt: &Nbt, encoding: Encoding) -> nbt::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    block_on(nbt.to_encoded_writer(&mut bytes, encoding))?;
+    Ok(bytes)
+}
+
+#[test]
+fn strings_have_to_fit_their_length_prefix() {
+    let longest = nbt::nbt! { "value": "a".repeat(u16::MAX as usize) };
+    let too_long = nbt::nbt! { "value": "a".repeat(u16::MAX as usize + 1) };
+
+    for encoding in [Encoding::Java, Encoding::BedrockLittleEndian] {
+        assert!(write(&longest, encoding).is_ok());
+        assert!(matches!(write(&too_long, encoding), Err(Error::StringTooLong(65536))));
+    }
+    // Bedrock's network encoding has a VarInt length prefix instead
+    assert!(write(&too_long, Encoding::BedrockNetwork).is_ok());
+
+    // Names are strings too, and Java measures them after encoding to modified UTF-8, where NUL takes up 2 bytes
+    let name = Nbt::new("\0".repeat(32_768), [(String::from("value"), Tag::Byte(0))]);
+    assert!(matches!(write(&name, Encoding::Java), Err(Error::StringTooLong(65536))));
+
+    // The synchronous writer checks the same
+    assert!(longest.write(&mut vec![]).is_ok());
+    assert!(matches!(too_long.write(&mut vec![]), Err(Error::StringTooLong(65536))));
+    assert!(matches!(name.write(&mut vec![]), Err(Error::StringTooLong(65536))));
+    let tag = Tag::String("a".repeat(u16::MAX as usize + 1));
+    assert!(matches!(tag.write_networked(&mut vec![]), Err(Error::StringTooLong(65536))));
+}
+
+fn read(mut bytes: &[u8], encoding: Encoding) -> Nbt {
+    block_on(Nbt::from_encoded_reader(&mut bytes, encoding)).unwrap()
+}
+
+/// A root with an entry of every primitive type as well as a list and an array, as Bedrock writes it
+fn bedrock() -> Nbt {
+    nbt::nbt! {
+        "s": 0x1234i16,
+        "i": -1,
+        "m": i32::MIN,
+        "l": -2i64,
+        "f": 1f32,
+        "t": "hi",
+        "n": [1, -1],
+        "b": [B; 1, 2],
+    }
+}
+
+#[test]
+fn bedrock_little_endian_matches_known_bytes() {
+    #[rustfmt::skip]
+    let bytes = [
+        0x0a, 0x00, 0x00,
+        0x02, 0x01, 0x00, b's', 0x34, 0x12,
+        0x03, 0x01, 0x00, b'i', 0xff, 0xff, 0xff, 0xff,
+        0x03, 0x01, 0x00, b'm', 0x00, 0x00, 0x00, 0x80,
+        0x04, 0x01, 0x00, b'l', 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0x05, 0x01, 0x00, b'f', 0x00, 0x00, 0x80, 0x3f,
+        0x08, 0x01, 0x00, b't', 0x02, 0x00, b'h', b'i',
+        0x09, 0x01, 0x00, b'n', 0x03, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+        0x07, 0x01, 0x00, b'b', 0x02, 0x00, 0x00, 0x00, 0x01, 0x02,
+        0x00,
+    ];
+
+    assert_eq!(write(&bedrock(), Encoding::BedrockLittleEndian).unwrap(), bytes);
+    assert_eq!(read(&bytes, Encoding::BedrockLittleEndian), bedrock());
+}
+
+#[test]
+fn bedrock_network_matches_known_bytes() {
+    // Ints, longs and list lengths are zigzag VarInts, string lengths unsigned VarInts
+    #[rustfmt::skip]
+    let bytes = [
+        0x0a, 0x00,
+        0x02, 0x01, b's', 0x34, 0x12,
+        0x03, 0x01, b'i', 0x01,
+        0x03, 0x01, b'm', 0xff, 0xff, 0xff, 0xff, 0x0f,
+        0x04, 0x01, b'l', 0x03,
+        0x05, 0x01, b'f', 0x00, 0x00, 0x80, 0x3f,
+        0x08, 0x01, b't', 0x02, b'h', b'i',
+        0x09, 0x01, b'n', 0x03, 0x04, 0x02, 0x01,
+        0x07, 0x01, b'b', 0x04, 0x01, 0x02,
+        0x00,
+    ];
+
+    assert_eq!(write(&bedrock(), Encoding::BedrockNetwork).unwrap(), bytes);
+    assert_eq!(read(&bytes, Encoding::BedrockNetwork), bedrock());
+}
+
+#[test]
+fn bedrock_network_varints_span_several_bytes() {
+    for (tag, payload) in [
+        (Tag::Int(300), vec![0xd8, 0x04]),
+        (Tag::Int(-300), vec![0xd7, 0x04]),
+        (Tag::Int(i32::MAX), vec![0xfe, 0xff, 0xff, 0xff, 0x0f]),
+        (
+            Tag::Long(i64::MIN),
+            vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+        ),
+        (Tag::Long(64), vec![0x80, 0x01]),
+        (Tag::LongArray(vec![-1; 64]), [vec![0x80, 0x01], vec![0x01; 64]].concat()),
+        (Tag::String("a".repeat(200)), [vec![0xc8, 0x01], vec![b'a'; 200]].concat()),
+    ] {
+        let nbt = Nbt::new("", [("v", tag.clone())]);
+        let bytes = [&[0x0a, 0x00, tag.id(), 0x01, b'v'][..], &payload, &[0x00]].concat();
+
+        assert_eq!(write(&nbt, Encoding::BedrockNetwork).unwrap(), bytes, "{tag:?}");
+        assert_eq!(read(&bytes, Encoding::BedrockNetwork), nbt);
+    }
+}
+
+#[test]
+fn mixed_lists_fail_before_their_header_is_written() {
+    let nbt = nbt::nbt! { "list": Tag::List(vec![Tag::Int(1), Tag::Byte(2)]) };
+    // Everything up to the list's element id, the root and the entry's own id and name
+    let written_before = [0x0a, 0x00, 0x00, 0x09, 0x00, 0x04, b'l', b'i', b's', b't'];
+
+    let mut bytes = vec![];
+    assert!(matches!(
+        block_on(nbt.to_writer(&mut bytes)),
+        Err(Error::MixedListTypes {
+            expected: 0x03,
+            found: 0x01
+        })
+    ));
+    assert_eq!(bytes, written_before);
+
+    let mut bytes = vec![];
+    assert!(matches!(
+        nbt.write(&mut bytes),
+        Err(Error::MixedListTypes {
+            expected: 0x03,
+            found: 0x01
+        })
+    ));
+    assert_eq!(bytes, written_before);
+}
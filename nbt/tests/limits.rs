@@ -0,0 +1,188 @@
+use nbt::{
+    io::{Encoding, Nbt},
+    limits::Limit,
+    types::{Compound, Tag},
+    visit::{Flow, NbtVisitor},
+    Error, NbtLimits, NbtRef,
+};
+use std::future::Future;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+struct VisitAll;
+
+impl NbtVisitor for VisitAll {}
+
+/// Skipped values have to be read all the same
+struct SkipAll;
+
+impl NbtVisitor for SkipAll {
+    fn visit_entry(&mut self, _id: u8, _name: &str) -> Flow {
+        Flow::Skip
+    }
+}
+
+fn visit<V: NbtVisitor>(mut bytes: &[u8], limits: NbtLimits, visitor: &mut V) -> nbt::Result<()> {
+    block_on(Nbt::visit_encoded_reader_with_limits(&mut bytes, Encoding::Java, limits, visitor))
+}
+
+/// Runs every reader on `nbt` and returns what each of them made of it
+fn read_all(nbt: &Nbt, limits: NbtLimits) -> Vec<(&'static str, nbt::Result<()>)> {
+    let mut bytes = vec![];
+    nbt.write(&mut bytes).unwrap();
+
+    vec![
+        (
+            "async",
+            block_on(Nbt::from_reader_with_limits(&mut bytes.as_slice(), limits)).map(drop),
+        ),
+        ("slice", Nbt::from_slice_with_limits(&bytes, limits).map(drop)),
+        ("borrowed", NbtRef::with_limits(&bytes, limits).map(drop)),
+        ("visitor", visit(&bytes, limits, &mut VisitAll)),
+        ("skipping visitor", visit(&bytes, limits, &mut SkipAll)),
+    ]
+}
+
+fn assert_accepted(nbt: &Nbt, limits: NbtLimits) {
+    for (reader, result) in read_all(nbt, limits) {
+        assert!(result.is_ok(), "the {reader} reader failed with {result:?}");
+    }
+}
+
+fn assert_exceeds(nbt: &Nbt, limits: NbtLimits, expected: Limit) {
+    for (reader, result) in read_all(nbt, limits) {
+        assert!(
+            matches!(result, Err(Error::LimitExceeded { limit, .. }) if limit == expected),
+            "the {reader} reader returned {result:?} instead of exceeding the {expected:?} limit"
+        );
+    }
+}
+
+/// A root with `depth - 1` compounds nested inside it, ending in a list
+fn nested(depth: usize) -> Nbt {
+    let mut tag = Tag::List(vec![]);
+    for _ in 2..depth {
+        tag = Tag::Compound(Compound::from_iter([(String::from("inner"), tag)]));
+    }
+    nbt::nbt! { "inner": tag }
+}
+
+#[test]
+fn nesting_deeper_than_the_limit_fails() {
+    let limits = NbtLimits {
+        max_depth: 16,
+        ..NbtLimits::default()
+    };
+
+    assert_accepted(&nested(16), limits);
+    assert_exceeds(&nested(17), limits, Limit::Depth);
+}
+
+#[test]
+fn large_payloads_exceed_the_byte_limit() {
+    let limits = NbtLimits {
+        max_bytes: 4096,
+        ..NbtLimits::default()
+    };
+
+    assert_accepted(&nbt::nbt! { "longs": Tag::LongArray(vec![0; 64]) }, limits);
+    assert_exceeds(&nbt::nbt! { "longs": Tag::LongArray(vec![0; 1024]) }, limits, Limit::Bytes);
+    assert_exceeds(&nbt::nbt! { "text": "a".repeat(8192) }, limits, Limit::Bytes);
+    assert_accepted(&nbt::nbt! { "longs": Tag::LongArray(vec![0; 1024]) }, NbtLimits::network());
+}
+
+#[test]
+fn many_small_tags_exceed_the_byte_limit() {
+    // Each empty compound is a single byte of input but a whole tag in memory
+    let compounds = Tag::List(vec![Tag::Compound(Compound::new()); 4096]);
+    let limits = NbtLimits {
+        max_bytes: 8192,
+        ..NbtLimits::default()
+    };
+
+    assert_exceeds(&nbt::nbt! { "list": compounds.clone() }, limits, Limit::Bytes);
+    assert_accepted(&nbt::nbt! { "list": compounds }, NbtLimits::network());
+}
+
+#[test]
+fn long_lists_and_arrays_exceed_the_length_limit() {
+    let limits = NbtLimits {
+        max_list_len: 8,
+        ..NbtLimits::default()
+    };
+
+    assert_accepted(
+        &nbt::nbt! { "list": Tag::List(vec![Tag::Byte(0); 8]), "ints": [I; 0, 0, 0, 0, 0, 0, 0, 0] },
+        limits,
+    );
+    assert_exceeds(&nbt::nbt! { "list": Tag::List(vec![Tag::Byte(0); 9]) }, limits, Limit::ListLength);
+    assert_exceeds(&nbt::nbt! { "bytes": Tag::ByteArray(vec![0; 9]) }, limits, Limit::ListLength);
+    assert_exceeds(&nbt::nbt! { "ints": Tag::IntArray(vec![0; 9]) }, limits, Limit::ListLength);
+    assert_exceeds(&nbt::nbt! { "longs": Tag::LongArray(vec![0; 9]) }, limits, Limit::ListLength);
+}
+
+#[test]
+fn networked_readers_apply_the_limits_too() {
+    let tag = Tag::List(vec![Tag::Int(0); 9]);
+    let mut bytes = vec![];
+    tag.write_networked(&mut bytes).unwrap();
+    let limits = NbtLimits {
+        max_list_len: 8,
+        ..NbtLimits::default()
+    };
+
+    let exceeded = |result: nbt::Result<Option<Tag>>| {
+        matches!(
+            result,
+            Err(Error::LimitExceeded {
+                limit: Limit::ListLength,
+                ..
+            })
+        )
+    };
+    assert!(exceeded(Tag::from_networked_slice_with_limits(&bytes, limits)));
+    assert!(exceeded(block_on(Tag::from_networked_reader_with_limits(
+        &mut bytes.as_slice(),
+        limits
+    ))));
+    assert_eq!(
+        Tag::from_networked_slice_with_limits(&bytes, NbtLimits::default()).unwrap(),
+        Some(tag)
+    );
+}
+
+#[test]
+fn deep_nesting_does_not_overflow_the_stack() {
+    // Lists of lists, written by hand as building the tags would need as deep a recursion
+    const DEPTH: usize = 20_000;
+    let mut bytes = vec![0x0a, 0x00, 0x00, 0x09, 0x00, 0x00];
+    for _ in 1..DEPTH {
+        bytes.extend([0x09, 0x00, 0x00, 0x00, 0x01]);
+    }
+    bytes.extend([0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    let limits = NbtLimits {
+        max_depth: DEPTH + 1,
+        ..NbtLimits::default()
+    };
+    let nbt = NbtRef::with_limits(&bytes, limits).unwrap();
+    assert_eq!(nbt.get("").unwrap().id(), 0x09);
+    // Dropping the tags recurses as deep as they are nested
+    std::mem::forget(Nbt::from_slice_with_limits(&bytes, limits).unwrap());
+    std::mem::forget(nbt.to_nbt().unwrap());
+
+    let limits = NbtLimits {
+        max_depth: DEPTH,
+        ..limits
+    };
+    assert!(matches!(
+        NbtRef::with_limits(&bytes, limits),
+        Err(Error::LimitExceeded { limit: Limit::Depth, .. })
+    ));
+    assert!(matches!(
+        Nbt::from_slice_with_limits(&bytes, limits),
+        Err(Error::LimitExceeded { limit: Limit::Depth, .. })
+    ));
+}
@@ -0,0 +1,241 @@
+use nbt::{io::Nbt, path::write_name, types::Tag, Error, NbtPath};
+
+const PLAYER: &str = r#"{
+    Health: 20.0f,
+    "custom name": "Steve",
+    Inventory: [
+        {Slot: 0b, id: "stone", Count: 64b},
+        {Slot: 1b, id: "dirt", Count: 1b, tag: {display: {Name: "Dirt"}}},
+        {Slot: 2b, id: "stone", Count: 3b}
+    ],
+    Pos: [1.0d, 64.0d, -3.5d],
+    Abilities: {flying: 0b, mayfly: 1b}
+}"#;
+
+fn player() -> Nbt {
+    Nbt::from_snbt(PLAYER).unwrap()
+}
+
+fn path(path: &str) -> NbtPath {
+    path.parse().unwrap()
+}
+
+fn get(nbt: &Nbt, path: &str) -> Vec<String> {
+    nbt.get_path(&self::path(path)).into_iter().map(Tag::to_snbt).collect()
+}
+
+fn snbt(input: &str) -> Tag {
+    Tag::from_snbt(input).unwrap()
+}
+
+fn compound(input: &str) -> nbt::types::Compound {
+    match snbt(input) {
+        Tag::Compound(compound) => compound,
+        other => panic!("{other:?} is not a compound"),
+    }
+}
+
+/// Column of an invalid path
+fn error_column(path: &str) -> usize {
+    match path.parse::<NbtPath>() {
+        Err(Error::InvalidPath { column, .. }) => column,
+        other => panic!("`{path}` should be invalid, got {other:?}"),
+    }
+}
+
+#[test]
+fn paths_are_printed_like_they_are_parsed() {
+    for input in [
+        "Health",
+        "Inventory[]",
+        "Inventory[0].tag.display.Name",
+        "Inventory[-1]",
+        "Inventory[{id:\"stone\"}].Count",
+        "Abilities{mayfly:1b}.flying",
+        "{Health:20.0f}.Pos[0]",
+        "\"custom name\"",
+        "'say \"hi\"'.x",
+        "\"\"",
+        "a[0][1]",
+    ] {
+        let parsed = path(input);
+        assert_eq!(parsed.to_string(), input);
+        assert_eq!(path(&parsed.to_string()), parsed);
+    }
+
+    // Single quotes are only kept when the name holds double quotes
+    assert_eq!(path("'custom name'").to_string(), "\"custom name\"");
+}
+
+#[test]
+fn invalid_paths_report_the_column() {
+    assert_eq!(error_column(""), 1);
+    assert_eq!(error_column("a."), 3);
+    assert_eq!(error_column("a..b"), 3);
+    assert_eq!(error_column("a[x]"), 3);
+    assert_eq!(error_column("a[0"), 4);
+    assert_eq!(error_column("a[99999999999]"), 3);
+    assert_eq!(error_column("a.{b:1}"), 3);
+    assert_eq!(error_column("a\"b\""), 2);
+    assert_eq!(error_column("\"a"), 3);
+    // Errors in filters point into the filter
+    assert_eq!(error_column("a{b:}"), 5);
+}
+
+#[test]
+fn names_are_quoted_only_when_needed() {
+    for (name, written) in [
+        ("plain", "plain"),
+        ("with-dash_and+plus", "with-dash_and+plus"),
+        ("ünïcode", "ünïcode"),
+        ("", "\"\""),
+        ("a b", "\"a b\""),
+        ("a.b", "\"a.b\""),
+        ("a[0]", "\"a[0]\""),
+        ("{a}", "\"{a}\""),
+        ("say \"hi\"", "'say \"hi\"'"),
+        ("both ' and \"", "\"both ' and \\\"\""),
+        ("back\\slash", "back\\slash"),
+        ("quoted \\", "\"quoted \\\\\""),
+    ] {
+        let mut out = String::new();
+        write_name(&mut out, name);
+        assert_eq!(out, written);
+
+        // Whatever was written selects the name again
+        let mut nbt = Nbt::default();
+        nbt.set_path(&path(&out), Tag::Byte(1));
+        assert_eq!(nbt.content().keys().collect::<Vec<_>>(), [name]);
+    }
+}
+
+#[test]
+fn get_follows_children_and_indices() {
+    let nbt = player();
+    assert_eq!(get(&nbt, "Health"), ["20.0f"]);
+    assert_eq!(get(&nbt, "\"custom name\""), ["\"Steve\""]);
+    assert_eq!(get(&nbt, "'custom name'"), ["\"Steve\""]);
+    assert_eq!(get(&nbt, "Inventory[1].tag.display.Name"), ["\"Dirt\""]);
+    assert_eq!(get(&nbt, "Pos[0]"), ["1.0d"]);
+    assert_eq!(get(&nbt, "Pos[-1]"), ["-3.5d"]);
+    assert_eq!(get(&nbt, "Pos[-3]"), ["1.0d"]);
+    assert_eq!(get(&nbt, "Pos[]"), ["1.0d", "64.0d", "-3.5d"]);
+    assert_eq!(get(&nbt, "Inventory[].id"), ["\"stone\"", "\"dirt\"", "\"stone\""]);
+
+    assert!(get(&nbt, "Pos[3]").is_empty());
+    assert!(get(&nbt, "Pos[-4]").is_empty());
+    assert!(get(&nbt, "Missing").is_empty());
+    assert!(get(&nbt, "Health.value").is_empty());
+    assert!(get(&nbt, "Abilities[0]").is_empty());
+    assert!(get(&nbt, "Inventory[].tag.display.Name.x").is_empty());
+}
+
+#[test]
+fn get_applies_filters() {
+    let nbt = player();
+    assert_eq!(get(&nbt, "Inventory[{id:\"stone\"}].Count"), ["64b", "3b"]);
+    assert_eq!(get(&nbt, "Inventory[{tag:{display:{}}}].Slot"), ["1b"]);
+    assert!(get(&nbt, "Inventory[{id:\"stone\",Slot:1b}]").is_empty());
+    // Filters don't convert between number types
+    assert!(get(&nbt, "Inventory[{Slot:0}]").is_empty());
+
+    assert_eq!(get(&nbt, "Abilities{mayfly:1b}.flying"), ["0b"]);
+    assert!(get(&nbt, "Abilities{mayfly:0b}.flying").is_empty());
+
+    assert_eq!(get(&nbt, "{Health:20.0f}.Health"), ["20.0f"]);
+    assert!(get(&nbt, "{Health:1.0f}.Health").is_empty());
+
+    // List filters match when every element of the pattern is found
+    let nbt = Nbt::from_snbt("{a: {tags: [\"x\", \"y\", \"z\"]}, b: {tags: []}}").unwrap();
+    assert_eq!(get(&nbt, "a{tags:[\"z\",\"x\"]}.tags[0]"), ["\"x\""]);
+    assert!(get(&nbt, "a{tags:[\"w\"]}").is_empty());
+    assert_eq!(get(&nbt, "b{tags:[]}"), ["{tags:[]}"]);
+    assert!(get(&nbt, "a{tags:[]}").is_empty());
+}
+
+#[test]
+fn get_path_mut_edits_in_place() {
+    let mut nbt = player();
+    for count in nbt.get_path_mut(&path("Inventory[{id:\"stone\"}].Count")) {
+        *count = Tag::Byte(0);
+    }
+    assert_eq!(get(&nbt, "Inventory[].Count"), ["0b", "1b", "0b"]);
+}
+
+#[test]
+fn set_replaces_and_creates_tags() {
+    let mut nbt = player();
+    assert_eq!(nbt.set_path(&path("Health"), Tag::Float(10.0)), 1);
+    assert_eq!(get(&nbt, "Health"), ["10.0f"]);
+    // Setting the same value again changes nothing
+    assert_eq!(nbt.set_path(&path("Health"), Tag::Float(10.0)), 0);
+
+    assert_eq!(nbt.set_path(&path("Pos[-1]"), Tag::Double(0.0)), 1);
+    assert_eq!(get(&nbt, "Pos"), ["[1.0d,64.0d,0.0d]"]);
+    assert_eq!(nbt.set_path(&path("Pos[]"), Tag::Double(2.0)), 3);
+    assert_eq!(get(&nbt, "Pos"), ["[2.0d,2.0d,2.0d]"]);
+    assert_eq!(nbt.set_path(&path("Inventory[{id:\"stone\"}].Count"), Tag::Byte(5)), 2);
+    assert_eq!(get(&nbt, "Inventory[].Count"), ["5b", "1b", "5b"]);
+
+    // Missing parents are created on the way
+    assert_eq!(nbt.set_path(&path("Abilities.nested.deeper"), Tag::Int(1)), 1);
+    assert_eq!(get(&nbt, "Abilities.nested"), ["{deeper:1}"]);
+    assert_eq!(nbt.set_path(&path("Tags[]"), snbt("\"new\"")), 1);
+    assert_eq!(get(&nbt, "Tags"), ["[\"new\"]"]);
+    assert_eq!(nbt.set_path(&path("Effects[{id:1}].duration"), Tag::Int(20)), 1);
+    assert_eq!(get(&nbt, "Effects"), ["[{id:1,duration:20}]"]);
+}
+
+#[test]
+fn set_keeps_lists_of_a_single_type() {
+    let mut nbt = player();
+    assert_eq!(nbt.set_path(&path("Pos[0]"), Tag::Int(1)), 0);
+    assert_eq!(nbt.set_path(&path("Pos[]"), Tag::Int(1)), 0);
+    assert_eq!(nbt.set_path(&path("Pos[5]"), Tag::Double(1.0)), 0);
+    assert_eq!(nbt.set_path(&path("Abilities{mayfly:0b}.flying"), Tag::Byte(1)), 0);
+    assert_eq!(nbt.set_path(&path("Health.value"), Tag::Byte(1)), 0);
+    assert_eq!(nbt.to_snbt(), player().to_snbt());
+}
+
+#[test]
+fn remove_drops_selected_tags() {
+    let mut nbt = player();
+    assert_eq!(nbt.remove_path(&path("\"custom name\"")), 1);
+    assert_eq!(nbt.remove_path(&path("\"custom name\"")), 0);
+    assert_eq!(nbt.remove_path(&path("Pos[-1]")), 1);
+    assert_eq!(get(&nbt, "Pos"), ["[1.0d,64.0d]"]);
+    assert_eq!(nbt.remove_path(&path("Pos[2]")), 0);
+    assert_eq!(nbt.remove_path(&path("Inventory[{id:\"stone\"}]")), 2);
+    assert_eq!(get(&nbt, "Inventory[].id"), ["\"dirt\""]);
+    assert_eq!(nbt.remove_path(&path("Inventory[].tag.display")), 1);
+    assert_eq!(get(&nbt, "Inventory[0].tag"), ["{}"]);
+    assert_eq!(nbt.remove_path(&path("Abilities{mayfly:0b}")), 0);
+    assert_eq!(nbt.remove_path(&path("Abilities{mayfly:1b}")), 1);
+    assert_eq!(nbt.remove_path(&path("Pos[]")), 2);
+    assert_eq!(get(&nbt, "Pos"), ["[]"]);
+
+    // Nothing gets created on the way
+    assert_eq!(nbt.remove_path(&path("Missing.child")), 0);
+    assert!(get(&nbt, "Missing").is_empty());
+}
+
+#[test]
+fn merge_combines_compounds_recursively() {
+    let mut nbt = player();
+    let merged = nbt.merge_path(
+        &path("Inventory[{id:\"dirt\"}].tag"),
+        &compound("{display: {Lore: [\"old\"]}, Damage: 3}"),
+    );
+    assert_eq!(merged, 1);
+    assert_eq!(get(&nbt, "Inventory[1].tag"), ["{display:{Name:\"Dirt\",Lore:[\"old\"]},Damage:3}"]);
+
+    // Anything but compounds is replaced
+    assert_eq!(nbt.merge_path(&path("Inventory[1].tag"), &compound("{display: 1b}")), 1);
+    assert_eq!(get(&nbt, "Inventory[1].tag"), ["{display:1b,Damage:3}"]);
+
+    // Missing compounds are created, tags that aren't compounds are left alone
+    assert_eq!(nbt.merge_path(&path("Inventory[].tag"), &compound("{Unbreakable: 1b}")), 3);
+    assert_eq!(get(&nbt, "Inventory[0].tag"), ["{Unbreakable:1b}"]);
+    assert_eq!(nbt.merge_path(&path("Health"), &compound("{a: 1}")), 0);
+    assert_eq!(get(&nbt, "Health"), ["20.0f"]);
+}
@@ -0,0 +1,134 @@
+mod support;
+
+use nbt::{
+    io::{Encoding, Nbt},
+    types::Tag,
+    visit::{Flow, NbtVisitor},
+    NbtLimits, NbtRef,
+};
+use proptest::prelude::*;
+use std::future::Future;
+use support::{nbt, tag};
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+#[derive(Default)]
+struct SkipAll;
+
+impl NbtVisitor for SkipAll {
+    fn visit_root(&mut self, _id: u8, _name: &str) -> Flow {
+        Flow::Skip
+    }
+}
+
+#[test]
+fn non_finite_floats_keep_their_type_in_snbt() {
+    for value in [f32::INFINITY, f32::NEG_INFINITY] {
+        assert_eq!(Tag::from_snbt(&Tag::Float(value).to_snbt()).unwrap(), Tag::Float(value));
+    }
+    for value in [f64::INFINITY, f64::NEG_INFINITY] {
+        assert_eq!(Tag::from_snbt(&Tag::Double(value).to_snbt()).unwrap(), Tag::Double(value));
+    }
+
+    assert!(matches!(Tag::from_snbt(&Tag::Float(f32::NAN).to_snbt()).unwrap(), Tag::Float(v) if v.is_nan()));
+    assert!(matches!(Tag::from_snbt(&Tag::Double(f64::NAN).to_snbt()).unwrap(), Tag::Double(v) if v.is_nan()));
+}
+
+#[test]
+fn writing_back_keeps_the_bytes_of_the_input() {
+    // Compounds compare equal in any order, so only the bytes show whether the order of the keys survived
+    let input: &[u8] = include_bytes!("../../dimension_codec.nbt");
+    let nbt = Nbt::from_slice(input).unwrap();
+
+    let mut written = vec![];
+    nbt.write(&mut written).unwrap();
+    assert!(written == input, "the sync writer changed the bytes");
+
+    let mut written = vec![];
+    block_on(nbt.to_writer(&mut written)).unwrap();
+    assert!(written == input, "the async writer changed the bytes");
+
+    let read = block_on(Nbt::from_reader(&mut &input[..])).unwrap();
+    let mut written = vec![];
+    read.write(&mut written).unwrap();
+    assert!(written == input, "the async reader changed the order");
+}
+
+proptest! {
+    #[test]
+    fn reader_round_trip(nbt in nbt()) {
+        for encoding in [Encoding::Java, Encoding::BedrockLittleEndian, Encoding::BedrockNetwork] {
+            let mut bytes = vec![];
+            block_on(nbt.to_encoded_writer(&mut bytes, encoding)).unwrap();
+
+            prop_assert_eq!(&block_on(Nbt::from_encoded_reader(&mut bytes.as_slice(), encoding)).unwrap(), &nbt);
+        }
+    }
+
+    #[test]
+    fn slice_and_borrowed_agree_with_reader(nbt in nbt()) {
+        let mut bytes = vec![];
+        block_on(nbt.to_writer(&mut bytes)).unwrap();
+
+        prop_assert_eq!(&Nbt::from_slice(&bytes).unwrap(), &nbt);
+        prop_assert_eq!(&NbtRef::new(&bytes).unwrap().to_nbt().unwrap(), &nbt);
+
+        let mut written = vec![];
+        nbt.write(&mut written).unwrap();
+        prop_assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn skipping_consumes_everything(nbt in nbt()) {
+        for encoding in [Encoding::Java, Encoding::BedrockLittleEndian, Encoding::BedrockNetwork] {
+            let mut bytes = vec![];
+            block_on(nbt.to_encoded_writer(&mut bytes, encoding)).unwrap();
+            bytes.push(0xff);
+
+            let mut reader = bytes.as_slice();
+            block_on(Nbt::visit_encoded_reader_with_limits(&mut reader, encoding, Default::default(), &mut SkipAll)).unwrap();
+            prop_assert_eq!(reader, &[0xff][..]);
+        }
+    }
+
+    #[test]
+    fn networked_round_trip(tag in tag()) {
+        let mut bytes = vec![];
+        block_on(tag.to_networked_writer(&mut bytes)).unwrap();
+
+        prop_assert_eq!(block_on(Tag::from_networked_reader(&mut bytes.as_slice())).unwrap(), Some(tag.clone()));
+        prop_assert_eq!(Tag::from_networked_slice(&bytes).unwrap(), Some(tag.clone()));
+
+        let mut written = vec![];
+        tag.write_networked(&mut written).unwrap();
+        prop_assert_eq!(&written, &bytes);
+
+        // Whatever follows the tag is left alone
+        written.push(0xff);
+        prop_assert_eq!(Tag::from_networked_prefix_with_limits(&written, NbtLimits::default()).unwrap(), (Some(tag), bytes.len()));
+    }
+
+    #[test]
+    fn snbt_round_trip(tag in tag()) {
+        prop_assert_eq!(&Tag::from_snbt(&tag.to_snbt()).unwrap(), &tag);
+        prop_assert_eq!(&Tag::from_snbt(&tag.to_snbt_pretty()).unwrap(), &tag);
+    }
+
+    #[test]
+    fn serde_round_trip(tag in tag()) {
+        prop_assert_eq!(&nbt::to_tag(&tag).unwrap(), &tag);
+        prop_assert_eq!(&nbt::from_tag::<Tag>(tag.clone()).unwrap(), &tag);
+    }
+
+    #[test]
+    fn truncated_input_is_an_error(nbt in nbt(), cut in any::<prop::sample::Index>()) {
+        let mut bytes = vec![];
+        block_on(nbt.to_writer(&mut bytes)).unwrap();
+        let truncated = &bytes[..cut.index(bytes.len())];
+
+        prop_assert!(block_on(Nbt::from_reader(&mut &truncated[..])).is_err());
+        prop_assert!(Nbt::from_slice(truncated).is_err());
+    }
+}
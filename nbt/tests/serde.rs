@@ -0,0 +1,194 @@
+use nbt::{
+    array::{ByteArray, IntArray},
+    from_nbt, from_tag, to_nbt, to_tag,
+    types::Tag,
+    Error,
+};
+use serde::{
+    de::{self, DeserializeOwned, SeqAccess, Visitor},
+    Deserialize, Serialize,
+};
+use std::{collections::BTreeMap, fmt::Debug};
+
+/// Serializes `value`, checks the tag it became and deserializes it back
+fn round_trip<T>(value: &T, expected: Tag)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let tag = to_tag(value).unwrap();
+    assert_eq!(tag, expected);
+    assert_eq!(&from_tag::<T>(tag).unwrap(), value);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Item {
+    id: String,
+    count: u8,
+    damage: Option<i16>,
+    enchanted: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Player {
+    name: String,
+    health: f32,
+    xp: f64,
+    seed: u64,
+    pos: (f64, f64, f64),
+    inventory: Vec<Item>,
+    #[serde(with = "nbt::array::long_array")]
+    heights: Vec<i64>,
+    uuid: IntArray,
+    flags: ByteArray,
+    scores: Vec<i32>,
+    attributes: BTreeMap<String, f64>,
+}
+
+#[test]
+fn structs_become_compounds() {
+    let player = Player {
+        name: String::from("Steve"),
+        health: 20.0,
+        xp: 0.5,
+        seed: u64::MAX,
+        pos: (1.0, 64.0, -3.5),
+        inventory: vec![
+            Item {
+                id: String::from("stone"),
+                count: 200,
+                damage: None,
+                enchanted: false,
+            },
+            Item {
+                id: String::from("sword"),
+                count: 1,
+                damage: Some(3),
+                enchanted: true,
+            },
+        ],
+        heights: vec![1, 2],
+        uuid: IntArray(vec![1, 2, 3, 4]),
+        flags: ByteArray(vec![1, -1]),
+        scores: vec![5, 6],
+        attributes: BTreeMap::from([(String::from("speed"), 0.1)]),
+    };
+
+    let expected = nbt::nbt! {
+        "Name": "Steve",
+        "Health": 20f32,
+        "Xp": 0.5,
+        "Seed": -1i64,
+        "Pos": [1.0, 64.0, -3.5],
+        "Inventory": [
+            // Unsigned integers keep their bits, `None` fields are left out
+            { "id": "stone", "count": -56i8, "enchanted": 0i8 },
+            { "id": "sword", "count": 1i8, "damage": 3i16, "enchanted": 1i8 }
+        ],
+        "Heights": [L; 1, 2],
+        "Uuid": [I; 1, 2, 3, 4],
+        "Flags": [B; 1, -1],
+        "Scores": [5, 6],
+        "Attributes": { "speed": 0.1 },
+    };
+
+    let mut nbt = to_nbt("Player", &player).unwrap();
+    assert_eq!(nbt.title(), "Player");
+    nbt.set_title("");
+    assert_eq!(nbt, expected);
+    assert_eq!(from_nbt::<Player>(nbt).unwrap(), player);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(f32),
+    Point(i32, i32),
+    Box { width: i32, height: i32 },
+}
+
+#[test]
+fn enums_follow_the_externally_tagged_layout() {
+    round_trip(&Shape::Empty, Tag::from("Empty"));
+    round_trip(&Shape::Circle(1.5), Tag::from(nbt::nbt! { "Circle": 1.5f32 }));
+    round_trip(&Shape::Point(1, 2), Tag::from(nbt::nbt! { "Point": [1, 2] }));
+    round_trip(
+        &Shape::Box { width: 3, height: 4 },
+        Tag::from(nbt::nbt! { "Box": { "width": 3, "height": 4 } }),
+    );
+
+    assert!(from_tag::<Shape>(Tag::from(nbt::nbt! { "Circle": 1f32, "Box": {} })).is_err());
+    assert!(from_tag::<Shape>(Tag::Int(0)).is_err());
+}
+
+#[test]
+fn options_are_only_allowed_as_fields() {
+    round_trip(&Some(5i32), Tag::Int(5));
+    assert!(matches!(to_tag(&None::<i32>), Err(Error::Message(_))));
+    assert!(to_tag(&vec![Some(1), None]).is_err());
+
+    // A missing field deserializes as `None`, a present one as `Some`
+    let item: Item = from_tag(Tag::from(nbt::nbt! { "id": "a", "count": 1i8, "enchanted": 0i8 })).unwrap();
+    assert_eq!(item.damage, None);
+}
+
+#[test]
+fn sequences_map_to_lists_unless_marked_as_arrays() {
+    round_trip(&vec![1i8, 2], Tag::List(vec![Tag::Byte(1), Tag::Byte(2)]));
+    round_trip(
+        &vec![vec![1i64], vec![]],
+        Tag::List(vec![Tag::List(vec![Tag::Long(1)]), Tag::List(vec![])]),
+    );
+    round_trip(&IntArray(vec![]), Tag::IntArray(vec![]));
+
+    // Plain sequences can be read from typed arrays and the other way round
+    assert_eq!(from_tag::<Vec<i32>>(Tag::IntArray(vec![1, 2])).unwrap(), [1, 2]);
+    assert_eq!(from_tag::<Vec<i8>>(Tag::ByteArray(vec![-1])).unwrap(), [-1]);
+    assert_eq!(from_tag::<IntArray>(Tag::List(vec![Tag::Int(3)])).unwrap(), IntArray(vec![3]));
+}
+
+#[test]
+fn tags_survive_serde() {
+    let tag = Tag::from(nbt::nbt! {
+        "bytes": [B; 1],
+        "ints": [I; 2],
+        "longs": [L; 3],
+        "list": [[I; 4]],
+        "nested": { "x": 1i16 },
+    });
+
+    assert_eq!(from_tag::<Tag>(to_tag(&tag).unwrap()).unwrap(), tag);
+}
+
+/// An empty sequence claiming to hold `usize::MAX` elements
+struct Lying;
+
+impl<'de> SeqAccess<'de> for Lying {
+    type Error = de::value::Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, _seed: T) -> Result<Option<T::Value>, Self::Error> {
+        Ok(None)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(usize::MAX)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Lying {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+#[test]
+fn size_hints_are_not_trusted() {
+    assert_eq!(Tag::deserialize(Lying).unwrap(), Tag::List(vec![]));
+}
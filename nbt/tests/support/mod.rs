@@ -0,0 +1,53 @@
+//! Strategies for generating NBT, shared with the server's codec tests, which include this file by path
+#![allow(dead_code)]
+
+use nbt::{
+    io::Nbt,
+    types::{Compound, Tag},
+};
+use proptest::{collection::vec, prelude::*};
+
+/// NaN never compares equal, so it is the only value left out. Infinities are generated on purpose.
+pub fn float() -> impl Strategy<Value = f32> {
+    use prop::num::f32::*;
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+}
+
+pub fn double() -> impl Strategy<Value = f64> {
+    use prop::num::f64::*;
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO | INFINITE
+}
+
+pub fn tag() -> impl Strategy<Value = Tag> {
+    let leaf = prop_oneof![
+        any::<i8>().prop_map(Tag::Byte),
+        any::<i16>().prop_map(Tag::Short),
+        any::<i32>().prop_map(Tag::Int),
+        any::<i64>().prop_map(Tag::Long),
+        float().prop_map(Tag::Float),
+        double().prop_map(Tag::Double),
+        ".{0,16}".prop_map(Tag::String),
+        vec(any::<i8>(), 0..16).prop_map(Tag::ByteArray),
+        vec(any::<i32>(), 0..16).prop_map(Tag::IntArray),
+        vec(any::<i64>(), 0..16).prop_map(Tag::LongArray),
+    ];
+
+    leaf.prop_recursive(4, 64, 8, |inner| {
+        prop_oneof![
+            // Lists hold a single type, so elements not matching the first one are dropped
+            vec(inner.clone(), 0..8).prop_map(|tags| {
+                let id = tags.first().map(Tag::id);
+                Tag::List(tags.into_iter().filter(|tag| Some(tag.id()) == id).collect())
+            }),
+            compound_of(inner).prop_map(Tag::Compound),
+        ]
+    })
+}
+
+pub fn compound_of(inner: impl Strategy<Value = Tag>) -> impl Strategy<Value = Compound> {
+    vec((".{0,8}", inner), 0..8).prop_map(Compound::from_iter)
+}
+
+pub fn nbt() -> impl Strategy<Value = Nbt> {
+    (".{0,8}", compound_of(tag())).prop_map(|(title, content)| Nbt::new(title, content))
+}
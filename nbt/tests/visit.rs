@@ -0,0 +1,308 @@
+use nbt::{
+    io::{Encoding, Nbt},
+    visit::{Flow, NbtVisitor},
+    NbtLimits,
+};
+use std::{collections::HashMap, future::Future};
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+}
+
+/// Writes down every event, answering entries with the flow given for their name
+#[derive(Default)]
+struct Recorder {
+    flows: HashMap<&'static str, Flow>,
+    events: Vec<String>,
+}
+
+impl Recorder {
+    fn with(flows: &[(&'static str, Flow)]) -> Self {
+        Recorder {
+            flows: flows.iter().copied().collect(),
+            events: vec![],
+        }
+    }
+
+    fn flow(&self, name: &str) -> Flow {
+        self.flows.get(name).copied().unwrap_or_default()
+    }
+}
+
+impl NbtVisitor for Recorder {
+    fn visit_root(&mut self, id: u8, name: &str) -> Flow {
+        self.events.push(format!("root {id} {name:?}"));
+        self.flow(name)
+    }
+
+    fn visit_entry(&mut self, id: u8, name: &str) -> Flow {
+        self.events.push(format!("entry {id} {name:?}"));
+        self.flow(name)
+    }
+
+    fn visit_byte(&mut self, value: i8) {
+        self.events.push(format!("{value}b"));
+    }
+
+    fn visit_short(&mut self, value: i16) {
+        self.events.push(format!("{value}s"));
+    }
+
+    fn visit_int(&mut self, value: i32) {
+        self.events.push(format!("{value}"));
+    }
+
+    fn visit_long(&mut self, value: i64) {
+        self.events.push(format!("{value}L"));
+    }
+
+    fn visit_float(&mut self, value: f32) {
+        self.events.push(format!("{value}f"));
+    }
+
+    fn visit_double(&mut self, value: f64) {
+        self.events.push(format!("{value}d"));
+    }
+
+    fn visit_string(&mut self, value: String) {
+        self.events.push(format!("{value:?}"));
+    }
+
+    fn visit_byte_array(&mut self, value: Vec<i8>) {
+        self.events.push(format!("B{value:?}"));
+    }
+
+    fn visit_int_array(&mut self, value: Vec<i32>) {
+        self.events.push(format!("I{value:?}"));
+    }
+
+    fn visit_long_array(&mut self, value: Vec<i64>) {
+        self.events.push(format!("L{value:?}"));
+    }
+
+    fn start_list(&mut self, id: u8, len: usize) {
+        self.events.push(format!("start list {id} {len}"));
+    }
+
+    fn end_list(&mut self) {
+        self.events.push(String::from("end list"));
+    }
+
+    fn start_compound(&mut self) {
+        self.events.push(String::from("start compound"));
+    }
+
+    fn end_compound(&mut self) {
+        self.events.push(String::from("end compound"));
+    }
+}
+
+fn level() -> Nbt {
+    let mut nbt = nbt::nbt! {
+        "name": "world",
+        "spawn": { "x": 1, "y": 64i16, "z": -3i64 },
+        "players": [
+            { "name": "Steve", "pos": [0.5, 1.5f64], "flags": [B; 1, 0] },
+            { "name": "Alex", "pos": [2.5, 3.5f64], "flags": [B; 0] }
+        ],
+        "heights": [L; 7],
+        "sections": [[I; 1, 2], [I; 3]],
+        "time": 1.5f32,
+        "last": 9i8,
+    };
+    nbt.set_title("Level");
+    nbt
+}
+
+/// The events of visiting `level` in every encoding, which have to agree
+fn record(flows: &[(&'static str, Flow)]) -> Vec<String> {
+    let mut recorded: Option<Vec<String>> = None;
+    for encoding in [Encoding::Java, Encoding::BedrockLittleEndian, Encoding::BedrockNetwork] {
+        let mut bytes = vec![];
+        block_on(level().to_encoded_writer(&mut bytes, encoding)).unwrap();
+
+        let mut recorder = Recorder::with(flows);
+        block_on(Nbt::visit_encoded_reader_with_limits(
+            &mut bytes.as_slice(),
+            encoding,
+            NbtLimits::default(),
+            &mut recorder,
+        ))
+        .unwrap();
+
+        match &recorded {
+            Some(events) => assert_eq!(&recorder.events, events, "{encoding:?} disagrees"),
+            None => recorded = Some(recorder.events),
+        }
+    }
+    recorded.unwrap()
+}
+
+#[test]
+fn events_follow_the_order_of_the_input() {
+    assert_eq!(
+        record(&[]),
+        [
+            "root 10 \"Level\"",
+            "start compound",
+            "entry 8 \"name\"",
+            "\"world\"",
+            "entry 10 \"spawn\"",
+            "start compound",
+            "entry 3 \"x\"",
+            "1",
+            "entry 2 \"y\"",
+            "64s",
+            "entry 4 \"z\"",
+            "-3L",
+            "end compound",
+            "entry 9 \"players\"",
+            "start list 10 2",
+            "start compound",
+            "entry 8 \"name\"",
+            "\"Steve\"",
+            "entry 9 \"pos\"",
+            "start list 6 2",
+            "0.5d",
+            "1.5d",
+            "end list",
+            "entry 7 \"flags\"",
+            "B[1, 0]",
+            "end compound",
+            "start compound",
+            "entry 8 \"name\"",
+            "\"Alex\"",
+            "entry 9 \"pos\"",
+            "start list 6 2",
+            "2.5d",
+            "3.5d",
+            "end list",
+            "entry 7 \"flags\"",
+            "B[0]",
+            "end compound",
+            "end list",
+            "entry 12 \"heights\"",
+            "L[7]",
+            "entry 9 \"sections\"",
+            "start list 11 2",
+            "I[1, 2]",
+            "I[3]",
+            "end list",
+            "entry 5 \"time\"",
+            "1.5f",
+            "entry 1 \"last\"",
+            "9b",
+            "end compound",
+        ]
+    );
+}
+
+#[test]
+fn skipped_compounds_and_lists_are_not_reported() {
+    assert_eq!(
+        record(&[
+            ("spawn", Flow::Skip),
+            ("players", Flow::Skip),
+            ("sections", Flow::Skip),
+            ("time", Flow::Skip)
+        ]),
+        [
+            "root 10 \"Level\"",
+            "start compound",
+            "entry 8 \"name\"",
+            "\"world\"",
+            "entry 10 \"spawn\"",
+            "entry 9 \"players\"",
+            "entry 12 \"heights\"",
+            "L[7]",
+            "entry 9 \"sections\"",
+            "entry 5 \"time\"",
+            "entry 1 \"last\"",
+            "9b",
+            "end compound",
+        ]
+    );
+}
+
+#[test]
+fn entries_of_nested_compounds_can_be_skipped() {
+    // Inside the list the players' names and flags are skipped but their positions are still visited
+    let events = record(&[("name", Flow::Skip), ("flags", Flow::Skip), ("y", Flow::Skip)]);
+    let players = events.iter().position(|event| event == "entry 9 \"players\"").unwrap();
+
+    assert_eq!(
+        events[..players],
+        [
+            "root 10 \"Level\"",
+            "start compound",
+            "entry 8 \"name\"",
+            "entry 10 \"spawn\"",
+            "start compound",
+            "entry 3 \"x\"",
+            "1",
+            "entry 2 \"y\"",
+            "entry 4 \"z\"",
+            "-3L",
+            "end compound",
+        ]
+    );
+    assert_eq!(
+        events[players..players + 16],
+        [
+            "entry 9 \"players\"",
+            "start list 10 2",
+            "start compound",
+            "entry 8 \"name\"",
+            "entry 9 \"pos\"",
+            "start list 6 2",
+            "0.5d",
+            "1.5d",
+            "end list",
+            "entry 7 \"flags\"",
+            "end compound",
+            "start compound",
+            "entry 8 \"name\"",
+            "entry 9 \"pos\"",
+            "start list 6 2",
+            "2.5d",
+        ]
+    );
+    assert_eq!(events.last().unwrap(), "end compound");
+}
+
+#[test]
+fn skipping_the_root_reads_past_it() {
+    let mut bytes = vec![];
+    level().write(&mut bytes).unwrap();
+    bytes.push(0xAA);
+
+    let mut reader = bytes.as_slice();
+    let mut recorder = Recorder::with(&[("Level", Flow::Skip)]);
+    block_on(Nbt::visit_reader(&mut reader, &mut recorder)).unwrap();
+
+    assert_eq!(recorder.events, ["root 10 \"Level\""]);
+    assert_eq!(reader, [0xAA]);
+}
+
+#[test]
+fn stopping_leaves_the_rest_unread() {
+    let mut bytes = vec![];
+    level().write(&mut bytes).unwrap();
+
+    let mut reader = bytes.as_slice();
+    let mut recorder = Recorder::with(&[("pos", Flow::Stop)]);
+    block_on(Nbt::visit_reader(&mut reader, &mut recorder)).unwrap();
+
+    assert_eq!(
+        recorder.events[recorder.events.len() - 4..],
+        ["start compound", "entry 8 \"name\"", "\"Steve\"", "entry 9 \"pos\""]
+    );
+    assert!(!reader.is_empty());
+
+    // Stopping at the root reads nothing but its id and name
+    let mut recorder = Recorder::with(&[("Level", Flow::Stop)]);
+    let mut reader = bytes.as_slice();
+    block_on(Nbt::visit_reader(&mut reader, &mut recorder)).unwrap();
+    assert_eq!(recorder.events, ["root 10 \"Level\""]);
+    assert_eq!(reader.len(), bytes.len() - 8);
+}
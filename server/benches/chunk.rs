@@ -0,0 +1,225 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use futures::executor::block_on;
+use nbt::{io::Nbt, types::Tag};
+use server::{
+    packets::{
+        chunk::{BlockEntity, ChunkDataUpdateLight, Heightmaps, Light},
+        Packet,
+    },
+    tcp::codec::FrameCodec,
+    types::{BitSet, VarInt},
+};
+
+/// Roughly a full overworld chunk: 24 sections of block data, light for all of them and a few block entities
+fn chunk() -> ChunkDataUpdateLight {
+    let light = Light {
+        length: VarInt(2048),
+        sky_light_array: [0xFF; 2048],
+    };
+    let sign = Nbt::new("", [("id", Tag::String("minecraft:sign".to_string())), ("is_waxed", Tag::Byte(0))]);
+
+    ChunkDataUpdateLight {
+        chunk_x: 0,
+        chunk_z: 0,
+        heightmaps: Heightmaps {
+            motion_blocking: vec![0x0100_4020_1008_0402; 37],
+        },
+        data: (0..24 * 4096).map(|i| i as u8).collect(),
+        block_entities: vec![
+            BlockEntity {
+                packed_xz: 0,
+                y: 64,
+                typee: 7,
+                data: sign,
+            };
+            8
+        ],
+        sky_light_mask: BitSet(vec![0x03FF_FFFF]),
+        block_light_mask: BitSet(vec![0x03FF_FFFF]),
+        empty_sky_light_mask: BitSet::empty(),
+        empty_block_light_mask: BitSet::empty(),
+        sky_lights: vec![light.clone(); 26],
+        block_lights: vec![light; 26],
+    }
+}
+
+/// The async codec this crate used before, spelled out field by field: every primitive, and every element of the
+/// fixed length light arrays, went through its own await on an `AsyncWrite`/`AsyncRead`
+mod baseline {
+    use nbt::{io::Nbt, types::Tag, NbtLimits};
+    use server::{
+        packets::chunk::{BlockEntity, ChunkDataUpdateLight, Heightmaps, Light},
+        types::{BitSet, VarInt},
+    };
+    use std::io;
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    type Error = Box<dyn std::error::Error>;
+
+    async fn write_var_i32<W: AsyncWrite + Unpin + Send>(writer: &mut W, value: usize) -> io::Result<()> {
+        let mut value = value as u32;
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                return writer.write_u8(byte).await;
+            }
+            writer.write_u8(byte | 0x80).await?;
+        }
+    }
+
+    async fn read_var_i32<R: AsyncRead + Unpin + Send>(reader: &mut R) -> io::Result<usize> {
+        let mut value = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = reader.read_u8().await?;
+            value |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value as usize)
+    }
+
+    async fn write_bit_set<W: AsyncWrite + Unpin + Send>(writer: &mut W, bit_set: &BitSet) -> io::Result<()> {
+        write_var_i32(writer, bit_set.0.len()).await?;
+        for &long in &bit_set.0 {
+            writer.write_i64(long).await?;
+        }
+        Ok(())
+    }
+
+    async fn read_bit_set<R: AsyncRead + Unpin + Send>(reader: &mut R) -> io::Result<BitSet> {
+        let mut longs = vec![];
+        for _ in 0..read_var_i32(reader).await? {
+            longs.push(reader.read_i64().await?);
+        }
+        Ok(BitSet(longs))
+    }
+
+    async fn write_lights<W: AsyncWrite + Unpin + Send>(writer: &mut W, lights: &[Light]) -> io::Result<()> {
+        write_var_i32(writer, lights.len()).await?;
+        for light in lights {
+            write_var_i32(writer, light.length.0 as usize).await?;
+            for &byte in &light.sky_light_array {
+                writer.write_u8(byte).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_lights<R: AsyncRead + Unpin + Send>(reader: &mut R) -> io::Result<Vec<Light>> {
+        let mut lights = vec![];
+        for _ in 0..read_var_i32(reader).await? {
+            let length = VarInt(read_var_i32(reader).await? as i32);
+            let mut sky_light_array = [0; 2048];
+            for byte in &mut sky_light_array {
+                *byte = reader.read_u8().await?;
+            }
+            lights.push(Light { length, sky_light_array });
+        }
+        Ok(lights)
+    }
+
+    pub async fn encode_body<W: AsyncWrite + Unpin + Send>(packet: &ChunkDataUpdateLight, writer: &mut W) -> Result<(), Error> {
+        writer.write_i32(packet.chunk_x).await?;
+        writer.write_i32(packet.chunk_z).await?;
+        nbt::to_tag(&packet.heightmaps)?.to_networked_writer(writer).await?;
+        write_var_i32(writer, packet.data.len()).await?;
+        writer.write_all(&packet.data).await?;
+
+        write_var_i32(writer, packet.block_entities.len()).await?;
+        for block_entity in &packet.block_entities {
+            writer.write_u8(block_entity.packed_xz).await?;
+            writer.write_i16(block_entity.y).await?;
+            write_var_i32(writer, block_entity.typee as usize).await?;
+            block_entity.data.to_networked_writer(writer).await?;
+        }
+
+        for bit_set in [
+            &packet.sky_light_mask,
+            &packet.block_light_mask,
+            &packet.empty_sky_light_mask,
+            &packet.empty_block_light_mask,
+        ] {
+            write_bit_set(writer, bit_set).await?;
+        }
+        write_lights(writer, &packet.sky_lights).await?;
+        Ok(write_lights(writer, &packet.block_lights).await?)
+    }
+
+    pub async fn decode_body<R: AsyncRead + Unpin + Send>(reader: &mut R) -> Result<ChunkDataUpdateLight, Error> {
+        let chunk_x = reader.read_i32().await?;
+        let chunk_z = reader.read_i32().await?;
+        let heightmaps = match Tag::from_networked_reader_with_limits(reader, NbtLimits::network()).await? {
+            Some(tag) => nbt::from_tag::<Heightmaps>(tag)?,
+            None => return Err("missing heightmaps".into()),
+        };
+
+        let mut data = vec![];
+        for _ in 0..read_var_i32(reader).await? {
+            data.push(reader.read_u8().await?);
+        }
+
+        let mut block_entities = vec![];
+        for _ in 0..read_var_i32(reader).await? {
+            block_entities.push(BlockEntity {
+                packed_xz: reader.read_u8().await?,
+                y: reader.read_i16().await?,
+                typee: read_var_i32(reader).await? as i32,
+                data: Nbt::from_networked_reader_with_limits(reader, NbtLimits::network()).await?,
+            });
+        }
+
+        Ok(ChunkDataUpdateLight {
+            chunk_x,
+            chunk_z,
+            heightmaps,
+            data,
+            block_entities,
+            sky_light_mask: read_bit_set(reader).await?,
+            block_light_mask: read_bit_set(reader).await?,
+            empty_sky_light_mask: read_bit_set(reader).await?,
+            empty_block_light_mask: read_bit_set(reader).await?,
+            sky_lights: read_lights(reader).await?,
+            block_lights: read_lights(reader).await?,
+        })
+    }
+}
+
+fn chunk_packet(c: &mut Criterion) {
+    let packet = chunk();
+    let mut body = vec![];
+    packet.encode_body(&mut body).unwrap();
+
+    // Both codecs have to agree on the bytes for the comparison to mean anything
+    let mut async_body = vec![];
+    block_on(baseline::encode_body(&packet, &mut async_body)).unwrap();
+    assert_eq!(async_body, body);
+
+    let mut group = c.benchmark_group("chunk");
+    group.bench_function("encode_body_async", |b| {
+        b.iter(|| {
+            let mut buf = vec![];
+            block_on(baseline::encode_body(black_box(&packet), &mut buf)).unwrap();
+            buf
+        })
+    });
+    group.bench_function("decode_body_async", |b| {
+        b.iter(|| block_on(baseline::decode_body(&mut black_box(&body[..]))).unwrap())
+    });
+    group.bench_function("encode_body", |b| {
+        b.iter(|| {
+            let mut buf = Vec::with_capacity(packet.body_size_hint());
+            black_box(&packet).encode_body(&mut buf).unwrap();
+            buf
+        })
+    });
+    group.bench_function("decode_body", |b| {
+        b.iter(|| ChunkDataUpdateLight::decode_body(&mut black_box(&body[..])).unwrap())
+    });
+    group.bench_function("frame", |b| b.iter(|| FrameCodec::default().encode(black_box(&packet)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, chunk_packet);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+/// Settings the server is started with
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// Packets of at least this many bytes are zlib compressed from login on, `None` turns compression off
+    pub compression_threshold: Option<usize>,
+    /// Players are authenticated with the session server and the connection is encrypted. Off by default, like a
+    /// server that was started without setting it up.
+    pub online_mode: bool,
+    /// Message of the day shown in the server list
+    pub motd: String,
+    pub max_players: u32,
+    /// How long a new connection may stay silent before it is closed, vanilla's read timeout
+    pub handshake_timeout: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            compression_threshold: Some(256),
+            online_mode: false,
+            motd: String::from("https://www.youtube.com/watch?v=8gGQFRk5hJw"),
+            max_players: 123_456,
+            handshake_timeout: Duration::from_secs(30),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::errors::AuthError;
+use aes::Aes128;
+use cfb8::cipher::{generic_array::GenericArray, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use rsa::{pkcs8::EncodePublicKey, Pkcs1v15Encrypt, RsaPrivateKey};
+use sha1::{Digest, Sha1};
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+type Encryptor = cfb8::Encryptor<Aes128>;
+type Decryptor = cfb8::Decryptor<Aes128>;
+
+/// The RSA keypair the shared secret is exchanged with, generated once per server start like vanilla does
+pub struct ServerKeys {
+    private_key: RsaPrivateKey,
+    /// The public key as ASN.1 DER, which is what the client expects in Encryption Request
+    public_key: Vec<u8>,
+}
+
+impl ServerKeys {
+    pub fn generate() -> Result<Self, AuthError> {
+        let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024)?;
+        let public_key = private_key.to_public_key().to_public_key_der()?.into_vec();
+
+        Ok(Self { private_key, public_key })
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, AuthError> {
+        Ok(self.private_key.decrypt(Pkcs1v15Encrypt, data)?)
+    }
+}
+
+/// The hash sent to the session server, a SHA-1 digest printed as a signed hexadecimal number
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key: &[u8]) -> String {
+    let mut digest: [u8; 20] = Sha1::new()
+        .chain_update(server_id)
+        .chain_update(shared_secret)
+        .chain_update(public_key)
+        .finalize()
+        .into();
+
+    let negative = digest[0] & 0x80 != 0;
+    if negative {
+        // Two's complement, the digest is printed as its absolute value behind a minus
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            (*byte, carry) = (!*byte).overflowing_add(carry as u8);
+        }
+    }
+
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    let hex = hex.trim_start_matches('0');
+    if negative {
+        format!("-{hex}")
+    } else {
+        hex.to_string()
+    }
+}
+
+/// Reads through `inner`, decrypting everything with AES-128-CFB8 once [`CipherReader::enable`] was called
+pub struct CipherReader<R> {
+    inner: R,
+    cipher: Option<Decryptor>,
+}
+
+impl<R> CipherReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, cipher: None }
+    }
+
+    /// Decrypts everything read from now on, the shared secret is both key and IV
+    pub fn enable(&mut self, shared_secret: &[u8; 16]) {
+        self.cipher = Some(Decryptor::new(shared_secret.into(), shared_secret.into()));
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CipherReader<R> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let start = buf.filled().len();
+        ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+
+        if let Some(cipher) = &mut self.cipher {
+            for byte in buf.filled_mut()[start..].chunks_exact_mut(1) {
+                cipher.decrypt_block_mut(GenericArray::from_mut_slice(byte));
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Writes to `inner`, encrypting everything with AES-128-CFB8 once [`CipherWriter::enable`] was called
+///
+/// Encrypted bytes the inner writer didn't take yet are kept until the next write or flush.
+pub struct CipherWriter<W> {
+    inner: W,
+    cipher: Option<Encryptor>,
+    pending: Vec<u8>,
+}
+
+impl<W> CipherWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            cipher: None,
+            pending: vec![],
+        }
+    }
+
+    /// Encrypts everything written from now on, the shared secret is both key and IV
+    pub fn enable(&mut self, shared_secret: &[u8; 16]) {
+        self.cipher = Some(Encryptor::new(shared_secret.into(), shared_secret.into()));
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: AsyncWrite + Unpin> CipherWriter<W> {
+    fn poll_write_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let written = ready!(Pin::new(&mut self.inner).poll_write(cx, &self.pending))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..written);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for CipherWriter<W> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        if this.cipher.is_none() {
+            return Pin::new(&mut this.inner).poll_write(cx, buf);
+        }
+
+        // The cipher state moves with every byte, so a byte is only encrypted once it's taken for good
+        ready!(this.poll_write_pending(cx))?;
+
+        this.pending.extend_from_slice(buf);
+        if let Some(cipher) = &mut this.cipher {
+            for byte in this.pending.chunks_exact_mut(1) {
+                cipher.encrypt_block_mut(GenericArray::from_mut_slice(byte));
+            }
+        }
+
+        if let Poll::Ready(Err(err)) = this.poll_write_pending(cx) {
+            return Poll::Ready(Err(err));
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_pending(cx))?;
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.poll_write_pending(cx))?;
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -2,48 +2,79 @@ use crate::{
     errors::DecodeError,
     types::{BitSet, Position, VarInt, VarLong},
 };
+use bytes::Buf;
+use nbt::{io::Nbt, types::Tag, NbtLimits};
 use std::io::Cursor;
-use tokio::io::{AsyncRead, AsyncReadExt};
 use uuid::Uuid;
 
 static SEGMENT_BITS: u8 = 0x7F;
 static CONTINUE_BIT: u8 = 0x80;
+/// Upper bound for allocations made up front from untrusted length prefixes
+const MAX_PREALLOCATION: usize = 1024;
 
+/// Reads a value out of a buffer holding the whole packet
+///
+/// NBT is parsed straight out of `buf.chunk()`, so it has to be contiguous, which any slice, `Bytes` or `Cursor` is.
 pub trait Decoder {
     type Output;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError>;
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError>;
+
+    /// Reads `len` values back to back, types with a cheaper way than one value at a time override it
+    fn decode_vec<B: Buf>(len: usize, buf: &mut B) -> Result<Vec<Self::Output>, DecodeError> {
+        let mut values = Vec::with_capacity(len.min(MAX_PREALLOCATION));
+        for _ in 0..len {
+            values.push(Self::decode(buf)?);
+        }
+
+        Ok(values)
+    }
 }
 
+/// Trait adds additional helper methods for `Buf` to read protocol data, failing instead of panicking once it runs out
 pub trait DecoderReadExt {
-    async fn read_bool(&mut self) -> Result<bool, DecodeError>;
-    async fn read_string(&mut self, max_length: u16) -> Result<String, DecodeError>;
-    async fn read_byte_array(&mut self) -> Result<Vec<u8>, DecodeError>;
-    async fn read_var_i32(&mut self) -> Result<i32, DecodeError>;
-    async fn read_var_i64(&mut self) -> Result<i64, DecodeError>;
+    fn read_u8(&mut self) -> Result<u8, DecodeError>;
+    fn read_i8(&mut self) -> Result<i8, DecodeError>;
+    fn read_u16(&mut self) -> Result<u16, DecodeError>;
+    fn read_i16(&mut self) -> Result<i16, DecodeError>;
+    fn read_u32(&mut self) -> Result<u32, DecodeError>;
+    fn read_i32(&mut self) -> Result<i32, DecodeError>;
+    fn read_u64(&mut self) -> Result<u64, DecodeError>;
+    fn read_i64(&mut self) -> Result<i64, DecodeError>;
+    fn read_u128(&mut self) -> Result<u128, DecodeError>;
+    fn read_f32(&mut self) -> Result<f32, DecodeError>;
+    fn read_f64(&mut self) -> Result<f64, DecodeError>;
+    fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, DecodeError>;
+    fn read_bool(&mut self) -> Result<bool, DecodeError>;
+    fn read_string(&mut self, max_length: u16) -> Result<String, DecodeError>;
+    fn read_byte_array(&mut self) -> Result<Vec<u8>, DecodeError>;
+    fn read_length(&mut self) -> Result<usize, DecodeError>;
+    fn read_var_i32(&mut self) -> Result<i32, DecodeError>;
+    fn read_var_i64(&mut self) -> Result<i64, DecodeError>;
 }
 
+/// Generated by `#[derive(Receivable)]`, packets derive `Packet` and are read with [`Packet::decode_body`](crate::packets::Packet::decode_body)
 pub trait ReceiveFromStream: Sized {
-    async fn receive(buf: &mut Cursor<Vec<u8>>) -> Result<Self, DecodeError>;
+    fn receive(buf: &mut Cursor<Vec<u8>>) -> Result<Self, DecodeError>;
 }
 
 macro_rules! read_signed_var_int (
     ($type: ident, $name: ident, $max_bytes: expr) => (
-        async fn $name(&mut self) -> Result<$type, DecodeError> {
+        fn $name(&mut self) -> Result<$type, DecodeError> {
             let mut bytes = 0;
             let mut output = 0;
 
             loop {
-                let byte = self.read_u8().await?;
+                if bytes == $max_bytes {
+                    return Err(DecodeError::VarIntTooLong { max_bytes: $max_bytes })
+                }
+
+                let byte = self.read_u8()?;
                 let value = (byte & SEGMENT_BITS) as $type;
 
                 output |= value << 7 * bytes;
                 bytes += 1;
 
-                if bytes > $max_bytes {
-                    return Err(DecodeError::VarIntTooLong { max_bytes: $max_bytes })
-                }
-
                 if (byte & CONTINUE_BIT) == 0 {
                     break;
                 }
@@ -54,222 +85,223 @@ macro_rules! read_signed_var_int (
    );
 );
 
-impl Decoder for u8 {
-    type Output = Self;
-
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_u8().await?)
-    }
-}
-
-impl Decoder for i8 {
-    type Output = Self;
+macro_rules! read_number (
+    ($type: ty, $name: ident, $get: ident) => (
+        #[inline]
+        fn $name(&mut self) -> Result<$type, DecodeError> {
+            ensure_remaining(self, std::mem::size_of::<$type>())?;
+            Ok(self.$get())
+        }
+    )
+);
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_i8().await?)
-    }
-}
+macro_rules! decode_number (
+    ($type: ty, $read: ident) => (
+        impl Decoder for $type {
+            type Output = Self;
 
-impl Decoder for i16 {
-    type Output = Self;
+            #[inline]
+            fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+                buf.$read()
+            }
+        }
+    )
+);
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_i16().await?)
+fn ensure_remaining<B: Buf>(buf: &B, needed: usize) -> Result<(), DecodeError> {
+    match buf.remaining() {
+        remaining if remaining < needed => Err(DecodeError::UnexpectedEnd { needed, remaining }),
+        _ => Ok(()),
     }
 }
 
-impl Decoder for i32 {
-    type Output = Self;
-
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_i32().await?)
-    }
+/// Parses a networked NBT tag out of the rest of `buf`, a root of `TAG_End` is returned as `None`
+fn read_tag<B: Buf>(buf: &mut B) -> Result<Option<Tag>, DecodeError> {
+    let (tag, length) = Tag::from_networked_prefix_with_limits(buf.chunk(), NbtLimits::network())?;
+    buf.advance(length);
+    Ok(tag)
 }
 
-impl Decoder for String {
+impl Decoder for u8 {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        reader.read_string(32_768).await
+    #[inline]
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        buf.read_u8()
     }
-}
-
-impl Decoder for bool {
-    type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        reader.read_bool().await
+    fn decode_vec<B: Buf>(len: usize, buf: &mut B) -> Result<Vec<Self::Output>, DecodeError> {
+        buf.read_bytes(len)
     }
 }
 
-// impl Decoder for Vec<u8> {
-//     type Output = Self;
+decode_number!(i8, read_i8);
+decode_number!(i16, read_i16);
+decode_number!(i32, read_i32);
+decode_number!(u16, read_u16);
+decode_number!(u32, read_u32);
+decode_number!(i64, read_i64);
+decode_number!(u64, read_u64);
+decode_number!(f32, read_f32);
+decode_number!(f64, read_f64);
 
-//     fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-//         reader.read_byte_array()
-//     }
-// }
-
-// impl Decoder for Vec<String> {
-//     type Output = Self;
-
-//     fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-//         let len = reader.read_var_i32()?;
-//         let mut buffer: Vec<String> = vec![0; len as usize];
-
-//         for _ in 0..len {
-//             buffer.push(reader.read_string(MAX_STRING_LEN)?);
-//         }
-
-//         Ok(buffer)
-//     }
-// }
-
-impl Decoder for Uuid {
+impl Decoder for String {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(Uuid::from_u128(reader.read_u128().await?))
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        buf.read_string(32_768)
     }
 }
 
-impl Decoder for u16 {
+impl Decoder for bool {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_u16().await?)
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        buf.read_bool()
     }
 }
 
-impl Decoder for u32 {
+impl Decoder for Uuid {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_u32().await?)
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        Ok(Uuid::from_u128(buf.read_u128()?))
     }
 }
 
-impl Decoder for i64 {
+impl Decoder for VarInt {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_i64().await?)
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        Ok(VarInt(buf.read_var_i32()?))
     }
 }
 
-impl Decoder for u64 {
+impl Decoder for VarLong {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_u64().await?)
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        Ok(VarLong(buf.read_var_i64()?))
     }
 }
 
-impl Decoder for f32 {
+impl Decoder for Position {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_f32().await?)
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        Ok(Position::from(buf.read_u64()?))
     }
 }
 
-impl Decoder for f64 {
+/// The length prefix counts longs, not bytes
+impl Decoder for BitSet {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(reader.read_f64().await?)
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        Ok(Self(Vec::<i64>::decode(buf)?))
     }
 }
 
-impl Decoder for VarInt {
+/// NBT from clients is subject to the same quota vanilla applies
+impl Decoder for Nbt {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(VarInt(reader.read_var_i32().await?))
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        match read_tag(buf)? {
+            Some(Tag::Compound(content)) => Ok(Nbt::from(content)),
+            Some(other) => Err(nbt::Error::InvalidRoot(other.id()).into()),
+            None => Err(nbt::Error::InvalidRoot(0x00).into()),
+        }
     }
 }
 
-impl Decoder for VarLong {
+impl Decoder for Tag {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(VarLong(reader.read_var_i64().await?))
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        match read_tag(buf)? {
+            Some(tag) => Ok(tag),
+            None => Err(nbt::Error::InvalidRoot(0x00).into()),
+        }
     }
 }
 
-impl Decoder for Position {
+/// A root of `TAG_End` is an absent value
+impl Decoder for Option<Tag> {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        Ok(Position::from(reader.read_u64().await?))
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        read_tag(buf)
     }
 }
 
-// TODO: Test this like wtf is this shit???
-impl Decoder for BitSet {
+impl<T: Decoder<Output = T>> Decoder for Vec<T> {
     type Output = Self;
 
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        let bits = reader.read_byte_array().await?;
-        let chunks = bits
-            .chunks_exact(8)
-            .map(|chunk| {
-                let mut value = 0_i64;
-                for (i, &byte) in chunk.iter().enumerate() {
-                    value |= (byte as i64) << (i * 8);
-                }
-                value
-            })
-            .collect();
-
-        Ok(Self(chunks))
+    fn decode<B: Buf>(buf: &mut B) -> Result<Self::Output, DecodeError> {
+        let len = buf.read_length()?;
+        T::decode_vec(len, buf)
     }
 }
 
-impl<T: Decoder<Output = T>> Decoder for Vec<T> {
-    type Output = Self;
-
-    async fn decode<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Self::Output, DecodeError> {
-        let len = reader.read_var_i32().await?;
-        let mut x_vec: Vec<T> = Vec::with_capacity(len as usize);
-
-        for _ in 0..len {
-            x_vec.push(T::decode(reader).await?);
-        }
-
-        Ok(x_vec)
+impl<B: Buf> DecoderReadExt for B {
+    read_number!(u8, read_u8, get_u8);
+    read_number!(i8, read_i8, get_i8);
+    read_number!(u16, read_u16, get_u16);
+    read_number!(i16, read_i16, get_i16);
+    read_number!(u32, read_u32, get_u32);
+    read_number!(i32, read_i32, get_i32);
+    read_number!(u64, read_u64, get_u64);
+    read_number!(i64, read_i64, get_i64);
+    read_number!(u128, read_u128, get_u128);
+    read_number!(f32, read_f32, get_f32);
+    read_number!(f64, read_f64, get_f64);
+
+    /// Checks the length against what is left before allocating, whatever a length prefix claims
+    fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, DecodeError> {
+        ensure_remaining(self, length)?;
+
+        let mut bytes = vec![0; length];
+        self.copy_to_slice(&mut bytes);
+        Ok(bytes)
     }
-}
 
-impl<R: AsyncRead + Unpin> DecoderReadExt for R {
-    async fn read_bool(&mut self) -> Result<bool, DecodeError> {
-        match self.read_u8().await? {
+    fn read_bool(&mut self) -> Result<bool, DecodeError> {
+        match self.read_u8()? {
             0 => Ok(false),
             1 => Ok(true),
             _ => Err(DecodeError::NonBoolValue),
         }
     }
 
-    async fn read_byte_array(&mut self) -> Result<Vec<u8>, DecodeError> {
-        let length = self.read_var_i32().await?;
-
-        let mut buf = vec![0; length as usize];
-        self.read_exact(&mut buf).await?;
+    fn read_byte_array(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let length = self.read_length()?;
+        self.read_bytes(length)
+    }
 
-        Ok(buf)
+    /// A VarInt length prefix, which must not be negative
+    fn read_length(&mut self) -> Result<usize, DecodeError> {
+        match self.read_var_i32()? {
+            length if length < 0 => Err(DecodeError::NegativeLength(length)),
+            length => Ok(length as usize),
+        }
     }
 
-    async fn read_string(&mut self, max_length: u16) -> Result<String, DecodeError> {
-        let length = self.read_var_i32().await? as usize;
+    /// `max_length` counts UTF-16 code units like vanilla does, each of which takes up to 3 bytes of UTF-8
+    fn read_string(&mut self, max_length: u16) -> Result<String, DecodeError> {
+        let length = self.read_length()?;
 
-        if length as u16 > max_length {
+        if length > max_length as usize * 3 {
             return Err(DecodeError::StringTooLong { length, max_length });
         }
 
-        let mut buf = vec![0; length];
-        self.read_exact(&mut buf).await?;
+        let string = String::from_utf8(self.read_bytes(length)?)?;
+        let length = string.encode_utf16().count();
+        if length > max_length as usize {
+            return Err(DecodeError::StringTooLong { length, max_length });
+        }
 
-        Ok(String::from_utf8(buf)?)
+        Ok(string)
     }
 
     read_signed_var_int!(i32, read_var_i32, 5);
@@ -1,218 +1,238 @@
 use crate::{
     errors::EncodeError,
     types::{BitSet, Position, VarInt, VarLong},
-    utils::MAX_STRING_LEN,
 };
-use nbt::io::Nbt;
-use tokio::io::{AsyncWrite, AsyncWriteExt};
+use bytes::BufMut;
+use nbt::{io::Nbt, types::Tag};
 use uuid::Uuid;
 
+/// Writes a value into a buffer that grows as needed, like `Vec<u8>` and `BytesMut` do
 pub trait Encoder {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError>;
-}
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError>;
 
-/// Trait adds additional helper methods for `AsyncWrite` to write protocol data.
-pub trait EncoderWriteExt {
-    async fn write_bool(&mut self, value: bool) -> Result<(), EncodeError>;
-    async fn write_string(&mut self, value: &str, max_length: u16) -> Result<(), EncodeError>;
-    async fn write_byte_array(&mut self, value: &[u8]) -> Result<(), EncodeError>;
-    async fn write_var_i32(&mut self, value: VarInt) -> Result<(), EncodeError>;
-    async fn write_var_i64(&mut self, value: VarLong) -> Result<(), EncodeError>;
-    async fn write_uuid(&mut self, value: Uuid) -> Result<(), EncodeError>;
-}
+    /// How many bytes `encode` writes, used to reserve buffers up front. Only a guess for values that are costly to
+    /// measure, NBT counts as empty.
+    fn size_hint(&self) -> usize {
+        0
+    }
 
-pub trait SendToWriter {
-    async fn send<W>(&self, stream: &mut W) -> Result<(), EncodeError>
+    /// Writes `values` back to back, types with a cheaper way than one value at a time override it
+    fn encode_slice<B: BufMut>(values: &[Self], buf: &mut B) -> Result<(), EncodeError>
     where
-        W: AsyncWrite + Unpin;
+        Self: Sized,
+    {
+        for value in values {
+            value.encode(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Trait adds additional helper methods for `BufMut` to write protocol data.
+pub trait EncoderWriteExt {
+    fn write_bool(&mut self, value: bool);
+    fn write_string(&mut self, value: &str, max_length: u16) -> Result<(), EncodeError>;
+    fn write_byte_array(&mut self, value: &[u8]);
+    fn write_var_i32(&mut self, value: VarInt);
+    fn write_var_i64(&mut self, value: VarLong);
+    fn write_uuid(&mut self, value: Uuid);
 }
 
+/// Negative values are written through their unsigned counterpart, an arithmetic shift would never reach zero
 macro_rules! write_signed_var_int (
-    ($type: ident, $name: ident) => (
-        async fn $name(&mut self, mut value: $type) -> Result<(), EncodeError> {
+    ($type: ident, $unsigned: ty, $name: ident) => (
+        fn $name(&mut self, value: $type) {
+            let mut value = value.0 as $unsigned;
+
             loop {
-                let mut byte = (value.0 & 0b01111111) as u8;
-                value = $type(value.0 >> 7);
+                let mut byte = (value & 0b01111111) as u8;
+                value >>= 7;
 
-                if value.0 != 0 {
+                if value != 0 {
                     byte |= 0b10000000;
                 }
 
-                self.write_u8(byte).await?;
+                self.put_u8(byte);
 
-                if value.0 == 0 {
+                if value == 0 {
                    break;
                 }
             }
-
-            Ok(())
         }
     )
 );
 
-impl<W: AsyncWrite + Unpin> EncoderWriteExt for W {
-    async fn write_bool(&mut self, value: bool) -> Result<(), EncodeError> {
-        if value {
-            self.write_u8(1).await?;
-        } else {
-            self.write_u8(0).await?;
-        }
-
-        Ok(())
+impl<B: BufMut> EncoderWriteExt for B {
+    fn write_bool(&mut self, value: bool) {
+        self.put_u8(value as u8);
     }
 
-    async fn write_string(&mut self, value: &str, max_length: u16) -> Result<(), EncodeError> {
-        let length = value.len();
+    /// `max_length` counts UTF-16 code units like vanilla does, not bytes
+    fn write_string(&mut self, value: &str, max_length: u16) -> Result<(), EncodeError> {
+        let length = value.encode_utf16().count();
 
         if length > max_length as usize {
             return Err(EncodeError::StringTooLong { length, max_length });
         }
 
-        self.write_var_i32(value.len().into()).await?;
-        self.write_all(value.as_bytes()).await?;
-
+        self.write_byte_array(value.as_bytes());
         Ok(())
     }
 
-    async fn write_byte_array(&mut self, value: &[u8]) -> Result<(), EncodeError> {
-        self.write_var_i32(value.len().into()).await?;
-        self.write_all(value).await?;
-
-        Ok(())
+    fn write_byte_array(&mut self, value: &[u8]) {
+        self.write_var_i32(value.len().into());
+        self.put_slice(value);
     }
 
-    async fn write_uuid(&mut self, value: Uuid) -> Result<(), EncodeError> {
-        Ok(self.write_all(value.as_bytes()).await?)
+    fn write_uuid(&mut self, value: Uuid) {
+        self.put_slice(value.as_bytes());
     }
 
-    write_signed_var_int!(VarInt, write_var_i32);
-    write_signed_var_int!(VarLong, write_var_i64);
+    write_signed_var_int!(VarInt, u32, write_var_i32);
+    write_signed_var_int!(VarLong, u64, write_var_i64);
 }
 
+macro_rules! encode_number (
+    ($type: ty, $put: ident) => (
+        impl Encoder for $type {
+            #[inline]
+            fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+                buf.$put(*self);
+                Ok(())
+            }
+
+            #[inline]
+            fn size_hint(&self) -> usize {
+                std::mem::size_of::<$type>()
+            }
+        }
+    )
+);
+
 impl Encoder for u8 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_u8(*self).await?)
+    #[inline]
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.put_u8(*self);
+        Ok(())
     }
-}
 
-impl Encoder for i8 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_i8(*self).await?)
+    #[inline]
+    fn size_hint(&self) -> usize {
+        1
     }
-}
 
-impl Encoder for i16 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_i16(*self).await?)
+    fn encode_slice<B: BufMut>(values: &[Self], buf: &mut B) -> Result<(), EncodeError> {
+        buf.put_slice(values);
+        Ok(())
     }
 }
 
-impl Encoder for i32 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_i32(*self).await?)
-    }
-}
+encode_number!(i8, put_i8);
+encode_number!(i16, put_i16);
+encode_number!(i32, put_i32);
+encode_number!(u16, put_u16);
+encode_number!(u32, put_u32);
+encode_number!(i64, put_i64);
+encode_number!(u64, put_u64);
+encode_number!(f32, put_f32);
+encode_number!(f64, put_f64);
 
-impl Encoder for u16 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_u16(*self).await?)
+impl Encoder for String {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_string(self, 32_768)
     }
-}
 
-impl Encoder for u32 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_u32(*self).await?)
+    fn size_hint(&self) -> usize {
+        VarInt::from(self.len()).encoded_len() + self.len()
     }
 }
 
-impl Encoder for i64 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_i64(*self).await?)
+impl Encoder for &str {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_string(self, 32_768)
     }
-}
 
-impl Encoder for u64 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_u64(*self).await?)
+    fn size_hint(&self) -> usize {
+        VarInt::from(self.len()).encoded_len() + self.len()
     }
 }
 
-impl Encoder for f32 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_f32(*self).await?)
+impl Encoder for bool {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_bool(*self);
+        Ok(())
     }
-}
 
-impl Encoder for f64 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_f64(*self).await?)
+    fn size_hint(&self) -> usize {
+        1
     }
 }
 
-impl Encoder for String {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_string(self, 32_768).await?)
+impl<T: Encoder> Encoder for Vec<T> {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_var_i32(VarInt::from(self.len()));
+        T::encode_slice(self, buf)
     }
-}
 
-impl Encoder for &str {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_string(self, 32_768).await?)
+    fn size_hint(&self) -> usize {
+        VarInt::from(self.len()).encoded_len() + self.iter().map(Encoder::size_hint).sum::<usize>()
     }
 }
 
-impl Encoder for bool {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_bool(*self).await?)
+impl Encoder for Nbt {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        Ok(self.write_networked(&mut buf.writer())?)
     }
 }
 
-impl Encoder for Vec<u8> {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_byte_array(self).await?)
+impl Encoder for Tag {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        Ok(self.write_networked(&mut buf.writer())?)
     }
 }
 
-impl Encoder for Vec<String> {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        writer.write_var_i32(VarInt::from(self.len())).await?;
-
-        for val in self {
-            writer.write_string(val, MAX_STRING_LEN).await?;
-        }
-
+impl Encoder for VarInt {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_var_i32(*self);
         Ok(())
     }
-}
 
-impl Encoder for Nbt {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(self.to_networked_writer(writer).await?)
+    fn size_hint(&self) -> usize {
+        self.encoded_len()
     }
 }
 
-impl Encoder for VarInt {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_var_i32(*self).await?)
+impl Encoder for VarLong {
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_var_i64(*self);
+        Ok(())
     }
-}
 
-impl Encoder for VarLong {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_var_i64(*self).await?)
+    fn size_hint(&self) -> usize {
+        self.encoded_len()
     }
 }
 
 impl Encoder for Uuid {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(writer.write_u128(self.as_u128()).await?)
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_uuid(*self);
+        Ok(())
+    }
+
+    fn size_hint(&self) -> usize {
+        16
     }
 }
 
 impl Encoder for Position {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        let pos = ((self.x as u64 & 0x03FF_FFFF) << 38) | ((self.z as u64 & 0x03FF_FFFF) << 12) | (self.y as u64 & 0xFFF);
-        Ok(writer.write_u64(pos).await?)
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.put_u64(u64::from(*self));
+        Ok(())
+    }
+
+    fn size_hint(&self) -> usize {
+        8
     }
 }
 
@@ -220,33 +240,36 @@ impl<const N: usize> Encoder for [u8; N]
 where
     [u8; N]: Sized,
 {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        writer.write_var_i32(self.len().into()).await?;
-        writer.write_all(self).await?;
-
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        buf.write_byte_array(self);
         Ok(())
     }
+
+    fn size_hint(&self) -> usize {
+        VarInt::from(N).encoded_len() + N
+    }
 }
 
-// TODO: Decoder for BitSet
 impl Encoder for BitSet {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        writer.write_var_i32(self.0.len().into()).await?;
-
-        for val in &self.0 {
-            writer.write_i64(*val).await?;
-        }
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        self.0.encode(buf)
+    }
 
-        Ok(())
+    fn size_hint(&self) -> usize {
+        self.0.size_hint()
     }
 }
 
 impl<T: Encoder> Encoder for Option<T> {
-    async fn encode<W: AsyncWrite + Unpin + Send>(&self, writer: &mut W) -> Result<(), EncodeError> {
-        Ok(if let Some(val) = self {
-            val.encode(writer).await
+    fn encode<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError> {
+        if let Some(val) = self {
+            val.encode(buf)
         } else {
-            0u8.encode(writer).await // u8!!
-        }?)
+            0u8.encode(buf) // u8!!
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.as_ref().map_or(1, Encoder::size_hint)
     }
 }
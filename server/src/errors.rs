@@ -1,3 +1,4 @@
+use crate::packets::State;
 use std::{io::Error, string::FromUtf8Error};
 use thiserror::Error;
 
@@ -11,8 +12,32 @@ pub enum DecodeError {
     StringTooLong { length: usize, max_length: u16 },
     #[error("Byte array was not recognized as valid UTF-8 string.")]
     FromUtf8Error(#[from] FromUtf8Error),
+    #[error("Expected {needed} more bytes but only {remaining} are left.")]
+    UnexpectedEnd { needed: usize, remaining: usize },
     #[error("VarInt is too long")]
     VarIntTooLong { max_bytes: u32 },
+    #[error("Length prefix can't be negative.")]
+    NegativeLength(i32),
+    #[error("{value} is not a valid {name}.")]
+    UnknownVariant { name: &'static str, value: String },
+    #[error("Error while reading NBT data")]
+    NbtError(#[from] nbt::Error),
+    #[error("Compressed packet claims {0} bytes, below the compression threshold or above the protocol maximum.")]
+    InvalidDataLength(usize),
+    #[error("Compressed packet claims {expected} bytes but decompresses to {actual}.")]
+    DataLengthMismatch { expected: usize, actual: usize },
+}
+
+#[derive(Debug, Error)]
+pub enum FrameError {
+    #[error("Error while reading frame")]
+    IOError(#[from] Error),
+    #[error("Frame of {length} bytes is over the {max_length} bytes allowed in the {state:?} state.")]
+    FrameTooLong { state: State, length: usize, max_length: usize },
+    #[error("Frame is empty, it has to hold at least a packet id.")]
+    EmptyFrame,
+    #[error("Error while decoding frame")]
+    DecodeError(#[from] DecodeError),
 }
 
 #[derive(Debug, Error)]
@@ -21,4 +46,46 @@ pub enum EncodeError {
     IOError(#[from] Error),
     #[error("String length can't be more than provided value.")]
     StringTooLong { length: usize, max_length: u16 },
+    #[error("Field {0} has to be set unless its #[skip_if] condition holds.")]
+    MissingField(&'static str),
+    #[error("Error while writing NBT data")]
+    NbtError(#[from] nbt::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum DispatchError {
+    #[error("Packet {id:#04x} is not known in the {state:?} state.")]
+    UnknownPacket { state: State, id: i32 },
+    #[error("Packet {id:#04x} of the {state:?} state has no handler.")]
+    Unhandled { state: State, id: i32 },
+    #[error("Packet {id:#04x} ended before all of its fields were read.")]
+    PacketTooShort { id: i32 },
+    #[error("Packet {id:#04x} has {remaining} bytes left after its last field.")]
+    PacketTooLong { id: i32, remaining: usize },
+    #[error("Handshake asked for state {0}, only 1 (status) and 2 (login) exist.")]
+    InvalidNextState(i32),
+    #[error("Error while decoding packet")]
+    DecodeError(#[from] DecodeError),
+    #[error("Error while handling packet")]
+    HandlerError(#[from] EncodeError),
+    #[error("Error while authenticating player")]
+    AuthError(#[from] AuthError),
+}
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Error while using the RSA keypair")]
+    RsaError(#[from] rsa::Error),
+    #[error("Error while encoding the public key")]
+    PublicKeyError(#[from] rsa::pkcs8::spki::Error),
+    #[error("Error while asking the session server")]
+    HttpError(#[from] reqwest::Error),
+    #[error("{0} has not joined through the session server.")]
+    NotAuthenticated(String),
+    #[error("Verify token does not match the one that was sent.")]
+    VerifyTokenMismatch,
+    #[error("Shared secret has to be 16 bytes, got {0}.")]
+    InvalidSharedSecret(usize),
+    #[error("Encryption Response has to follow an Encryption Request.")]
+    UnexpectedEncryptionResponse,
 }
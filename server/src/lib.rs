@@ -0,0 +1,13 @@
+#![allow(async_fn_in_trait)]
+
+pub mod config;
+pub mod crypto;
+pub mod decoder;
+pub mod encoder;
+pub mod errors;
+pub mod packets;
+pub mod player;
+pub mod session;
+pub mod tcp;
+pub mod types;
+pub mod utils;
@@ -1,13 +1,4 @@
-use tcp::server::McServer;
-
-pub mod decoder;
-pub mod encoder;
-pub mod errors;
-pub mod packets;
-pub mod player;
-mod tcp;
-pub mod types;
-pub mod utils;
+use server::tcp::server::McServer;
 
 #[tokio::main]
 async fn main() {
@@ -1,44 +1,47 @@
-use crate::encoder::Encoder;
-use crate::encoder::EncoderWriteExt;
-use crate::errors::EncodeError;
 use crate::types::BitSet;
 use crate::types::Position;
 use crate::types::VarInt;
-use macros::{Serializable, Streamable};
+use macros::{Deserializable, Packet, Serializable};
 use nbt::io::Nbt;
-use nbt::types::Tag;
-use std::collections::HashMap;
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
+use serde::{Deserialize, Serialize};
 
-#[derive(Streamable)]
-#[packet_id(0x25)]
+#[derive(Packet)]
+#[packet(id = 0x25, state = Play, direction = Clientbound)]
 pub struct ChunkDataUpdateLight {
-    chunk_x: i32,
-    chunk_z: i32,
-    heightmaps: Nbt,
-    data: Vec<u8>,
-    block_entities: Vec<BlockEntity>,
-    sky_light_mask: BitSet,
-    block_light_mask: BitSet,
-    empty_sky_light_mask: BitSet,
-    empty_block_light_mask: BitSet,
-    sky_lights: Vec<Light>,
-    block_lights: Vec<Light>,
+    pub chunk_x: i32,
+    pub chunk_z: i32,
+    #[nbt]
+    pub heightmaps: Heightmaps,
+    pub data: Vec<u8>,
+    pub block_entities: Vec<BlockEntity>,
+    pub sky_light_mask: BitSet,
+    pub block_light_mask: BitSet,
+    pub empty_sky_light_mask: BitSet,
+    pub empty_block_light_mask: BitSet,
+    pub sky_lights: Vec<Light>,
+    pub block_lights: Vec<Light>,
 }
 
-#[derive(Serializable, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct Heightmaps {
+    #[serde(rename = "MOTION_BLOCKING", with = "nbt::array::long_array")]
+    pub motion_blocking: Vec<i64>,
+}
+
+#[derive(Serializable, Deserializable, Clone)]
 pub struct BlockEntity {
-    packed_xz: u8,
-    y: i16,
-    typee: VarInt,
-    data: Nbt,
+    pub packed_xz: u8,
+    pub y: i16,
+    #[varint]
+    pub typee: i32,
+    pub data: Nbt,
 }
 
-#[derive(Serializable, Clone)]
+#[derive(Serializable, Deserializable, Clone)]
 pub struct Light {
-    length: VarInt,
-    sky_light_array: [u8; 2048],
+    pub length: VarInt,
+    #[fixed_len]
+    pub sky_light_array: [u8; 2048],
 }
 
 // https://wiki.vg/Chunk_Format
@@ -47,12 +50,7 @@ impl Default for ChunkDataUpdateLight {
         Self {
             chunk_x: 0,
             chunk_z: 0,
-            heightmaps: Nbt::new("", {
-                let mut map = HashMap::new();
-                map.insert("MOTION_BLOCKING", Tag::List(vec![]));
-
-                map
-            }),
+            heightmaps: Heightmaps { motion_blocking: vec![] },
             data: vec![],
             block_entities: vec![],
             sky_light_mask: BitSet::new(1),
@@ -65,8 +63,8 @@ impl Default for ChunkDataUpdateLight {
     }
 }
 
-#[derive(Streamable)]
-#[packet_id(0x54)]
+#[derive(Packet)]
+#[packet(id = 0x54, state = Play, direction = Clientbound)]
 pub struct SetDefaultSpawnPosition {
     location: Position,
     angle: f32,
@@ -81,8 +79,8 @@ impl Default for SetDefaultSpawnPosition {
     }
 }
 
-#[derive(Streamable)]
-#[packet_id(0x3E)]
+#[derive(Packet)]
+#[packet(id = 0x3E, state = Play, direction = Clientbound)]
 pub struct SynchronizePlayerPosition {
     x: f64,
     y: f64,
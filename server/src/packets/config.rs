@@ -1,36 +1,60 @@
-use crate::encoder::Encoder;
-use crate::{decoder::Decoder, types::VarInt};
-use macros::{Receivable, Streamable};
+use crate::{
+    errors::EncodeError,
+    packets::{
+        chunk::{ChunkDataUpdateLight, SetDefaultSpawnPosition, SynchronizePlayerPosition},
+        event::GameEvent,
+        play::PlayLogin,
+        DynPacket, State,
+    },
+    tcp::server::Connection,
+};
+use macros::{Deserializable, Packet, Serializable};
 use nbt::io::Nbt;
 use std::fs::File;
 use std::io::{Cursor, Read};
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
 
-#[derive(Streamable, Default)]
-#[packet_id(0x02)]
+#[derive(Packet, Default)]
+#[packet(id = 0x02, state = Configuration, direction = Clientbound)]
 pub struct FinishConfiguration {}
 
-#[derive(Receivable, Debug)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x00, state = Configuration, direction = Serverbound)]
 pub struct ClientInformation {
     pub locale: String,
     pub view_distance: i8,
-    pub chat_mode: VarInt,
+    pub chat_mode: ChatMode,
     pub chat_colors: bool,
     pub displayed_skin_parts: u8,
-    pub main_hand: VarInt,
+    pub main_hand: MainHand,
     pub enable_text_filtering: bool,
     pub allow_server_listing: bool,
 }
 
-#[derive(Receivable, Debug)]
+#[derive(Serializable, Deserializable, Clone, Copy, Debug, PartialEq, Eq)]
+#[serialize_as(VarInt)]
+pub enum ChatMode {
+    Enabled = 0,
+    CommandsOnly = 1,
+    Hidden = 2,
+}
+
+#[derive(Serializable, Deserializable, Clone, Copy, Debug, PartialEq, Eq)]
+#[serialize_as(VarInt)]
+pub enum MainHand {
+    Left = 0,
+    Right = 1,
+}
+
+#[derive(Packet, Debug)]
+#[packet(id = 0x01, state = Configuration, direction = Serverbound)]
 pub struct ServerboundPluginMessage {
     pub channel: String,
+    #[rest]
     pub data: Vec<u8>,
 }
 
-#[derive(Streamable, Clone)]
-#[packet_id(0x05)]
+#[derive(Packet, Clone)]
+#[packet(id = 0x05, state = Configuration, direction = Clientbound)]
 pub struct RegistryData {
     registry_codec: Nbt,
 }
@@ -47,5 +71,39 @@ impl RegistryData {
     }
 }
 
-#[derive(Receivable)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x02, state = Configuration, direction = Serverbound)]
 pub struct ReceiveFinishConfiguration {}
+
+impl ClientInformation {
+    pub async fn handle(_: &mut Connection, client_information: Self) -> Result<(), EncodeError> {
+        println!("{:?}", client_information);
+        Ok(())
+    }
+}
+
+impl ServerboundPluginMessage {
+    pub async fn handle(_: &mut Connection, plugin_message: Self) -> Result<(), EncodeError> {
+        println!("{:?}", plugin_message);
+        Ok(())
+    }
+}
+
+impl ReceiveFinishConfiguration {
+    pub async fn handle(connection: &mut Connection, _: Self) -> Result<(), EncodeError> {
+        println!("[Config] Finishing configuration");
+        connection.state = State::Play;
+
+        //TODO Keep-Alive task should start here but can't because the tokio TcpStream can't be cloned
+        // task::spawn(async move { Self::handle_keep_alive(&mut write) });
+
+        let spawn: [Box<dyn DynPacket>; 5] = [
+            Box::new(PlayLogin::default()),
+            Box::new(ChunkDataUpdateLight::default()),
+            Box::new(SynchronizePlayerPosition::default()),
+            Box::new(GameEvent::default()),
+            Box::new(SetDefaultSpawnPosition::default()),
+        ];
+        connection.send_all(&spawn).await
+    }
+}
@@ -1,10 +1,7 @@
-use crate::encoder::Encoder;
-use macros::Streamable;
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
+use macros::Packet;
 
-#[derive(Streamable)]
-#[packet_id(0x20)]
+#[derive(Packet)]
+#[packet(id = 0x20, state = Play, direction = Clientbound)]
 pub struct GameEvent {
     event: u8,
     value: f32,
@@ -0,0 +1,25 @@
+use crate::{errors::EncodeError, packets::outgoing::play_disconnect::PlayDisconnect, tcp::server::Connection};
+use macros::Packet;
+
+#[derive(Packet, Debug)]
+#[packet(id = 0x05, state = Play, direction = Serverbound)]
+pub struct ChatMessage {
+    #[max_len(256)]
+    pub message: String,
+    pub timestamp: i64,
+    pub salt: i64,
+    #[prefixed]
+    #[fixed_len]
+    pub signature: Option<[u8; 256]>,
+    #[varint]
+    pub message_count: i32,
+    /// Fixed bit set of the last 20 messages seen
+    #[fixed_len]
+    pub acknowledged: [u8; 3],
+}
+
+impl ChatMessage {
+    pub async fn handle(connection: &mut Connection, chat_message: Self) -> Result<(), EncodeError> {
+        connection.send(&PlayDisconnect::from_text(chat_message.message.repeat(50))).await
+    }
+}
@@ -1,46 +1,32 @@
-use crate::decoder::{Decoder, ReceiveFromStream};
-use crate::encoder::SendToWriter;
-use crate::packets::status::PlayerListResponse;
-use crate::tcp::server::GameplayState;
+use crate::errors::DispatchError;
+use crate::packets::State;
+use crate::tcp::server::Connection;
 use crate::types::VarInt;
-use macros::Receivable;
-use std::io::Cursor;
-use tokio::io::AsyncWriteExt;
-use tokio::net::tcp::OwnedWriteHalf;
+use macros::Packet;
 
-#[derive(Receivable)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x00, state = Handshake, direction = Serverbound)]
 pub struct HandShake {
     pub protocol_version: VarInt,
-    pub server_address: String, // <--- max_len 255
+    #[max_len(255)]
+    pub server_address: String,
     pub server_port: u16,
     pub next_state: VarInt,
 }
 
 impl HandShake {
-    pub async fn handle(cursor: &mut Cursor<Vec<u8>>, state: &mut GameplayState, stream: &mut OwnedWriteHalf) {
-        let handshake = HandShake::receive(cursor).await.unwrap();
-
+    pub async fn handle(connection: &mut Connection, handshake: Self) -> Result<(), DispatchError> {
         println!(
             "[HandShake] ProtocolVersion: {} | Address: {} | Port: {} | NextState: {}",
             handshake.protocol_version.0, handshake.server_address, handshake.server_port, handshake.next_state.0
         );
 
         match handshake.next_state {
-            VarInt(1) => {
-                PlayerListResponse::new(
-                    String::from("1.20.4"),
-                    765,
-                    123_456,
-                    String::from("https://www.youtube.com/watch?v=8gGQFRk5hJw"),
-                )
-                .send(stream)
-                .await
-                .unwrap();
-
-                *state = GameplayState::Status;
-            }
-            VarInt(2) => *state = GameplayState::Login,
-            _ => stream.shutdown().await.unwrap(),
+            VarInt(1) => connection.state = State::Status,
+            VarInt(2) => connection.state = State::Login,
+            VarInt(next_state) => return Err(DispatchError::InvalidNextState(next_state)),
         };
+
+        Ok(())
     }
 }
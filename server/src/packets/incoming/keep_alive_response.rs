@@ -1,7 +1,15 @@
-use crate::decoder::Decoder;
-use macros::Receivable;
+use crate::{errors::EncodeError, tcp::server::Connection};
+use macros::Packet;
 
-#[derive(Receivable)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x15, state = Play, direction = Serverbound)]
 pub struct KeepAliveResponse {
     pub id: i64,
 }
+
+impl KeepAliveResponse {
+    pub async fn handle(_: &mut Connection, response: Self) -> Result<(), EncodeError> {
+        println!("KeepAlive response: {}", response.id);
+        Ok(())
+    }
+}
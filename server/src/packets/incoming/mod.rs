@@ -1,3 +1,4 @@
+pub mod chat_message;
 pub mod handshake;
 pub mod keep_alive_response;
 pub mod player_position;
@@ -1,10 +1,18 @@
-use crate::decoder::Decoder;
-use macros::Receivable;
+use crate::{errors::EncodeError, tcp::server::Connection};
+use macros::Packet;
 
-#[derive(Receivable, Debug)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x17, state = Play, direction = Serverbound)]
 pub struct PlayerPosition {
     pub x: f64,
     pub y: f64,
     pub z: f64,
     pub on_ground: bool,
 }
+
+impl PlayerPosition {
+    pub async fn handle(_: &mut Connection, pos: Self) -> Result<(), EncodeError> {
+        println!("{},{},{} [{}]", pos.x, pos.y, pos.z, pos.on_ground);
+        Ok(())
+    }
+}
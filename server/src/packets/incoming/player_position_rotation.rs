@@ -1,7 +1,8 @@
-use crate::decoder::Decoder;
-use macros::Receivable;
+use crate::{errors::EncodeError, tcp::server::Connection};
+use macros::Packet;
 
-#[derive(Receivable)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x18, state = Play, direction = Serverbound)]
 pub struct PlayerPositionRotation {
     pub x: f64,
     pub y: f64,
@@ -10,3 +11,13 @@ pub struct PlayerPositionRotation {
     pub pitch: f32,
     pub on_ground: bool,
 }
+
+impl PlayerPositionRotation {
+    pub async fn handle(_: &mut Connection, pos_rot: Self) -> Result<(), EncodeError> {
+        println!(
+            "{},{},{} | {} | {} | [{}]",
+            pos_rot.x, pos_rot.y, pos_rot.z, pos_rot.yaw, pos_rot.pitch, pos_rot.on_ground
+        );
+        Ok(())
+    }
+}
@@ -1,9 +1,17 @@
-use crate::decoder::Decoder;
-use macros::Receivable;
+use crate::{errors::EncodeError, tcp::server::Connection};
+use macros::Packet;
 
-#[derive(Receivable)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x19, state = Play, direction = Serverbound)]
 pub struct PlayerRotation {
     pub yaw: f32,
     pub pitch: f32,
     pub on_ground: bool,
 }
+
+impl PlayerRotation {
+    pub async fn handle(_: &mut Connection, rot: Self) -> Result<(), EncodeError> {
+        println!("{} | {} | [{}]", rot.yaw, rot.pitch, rot.on_ground);
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! The server list ping of clients before 1.7, which predates VarInt framing
+//!
+//! https://wiki.vg/Server_List_Ping#1.6
+
+use crate::{packets::status::ServerStatus, tcp::server::Connection};
+use std::io;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    time,
+};
+
+/// First byte of every legacy ping, a modern handshake can't start with it
+const PING: u8 = 0xFE;
+/// Legacy kick packet, the response is sent as its reason
+const KICK: u8 = 0xFF;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3 send a lone `0xFE`
+    Beta,
+    /// 1.4 and 1.5 add a `0x01`, 1.6 follows it with an `MC|PingHost` plugin message the response doesn't depend on
+    V1_4,
+}
+
+impl LegacyPing {
+    /// Tells the ping variant from everything the client sent, `None` for modern clients
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [PING] => Some(Self::Beta),
+            [PING, 0x01, ..] => Some(Self::V1_4),
+            _ => None,
+        }
+    }
+
+    /// Reads a legacy ping if the connection starts with one, leaving modern handshakes unread
+    ///
+    /// Fails on a `0xFE` no legacy client would send, nothing can be made of the connection after it. Also fails if
+    /// nothing arrives within the handshake timeout, so a silent client can't hold on to the connection forever.
+    pub async fn read(connection: &mut Connection) -> io::Result<Option<Self>> {
+        let timeout = connection.config.handshake_timeout;
+        let stream = connection.read.get_mut();
+
+        let mut first = [0];
+        let peeked = match time::timeout(timeout, stream.peek(&mut first)).await {
+            Ok(peeked) => peeked?,
+            Err(_) => return Err(io::ErrorKind::TimedOut.into()),
+        };
+        if peeked == 0 || first[0] != PING {
+            return Ok(None);
+        }
+
+        // Like vanilla this goes by what arrived in one go, a lone 0xFE is a beta client
+        let mut bytes = [0; 1024];
+        let read = stream.read(&mut bytes).await?;
+        match Self::parse(&bytes[..read]) {
+            Some(ping) => Ok(Some(ping)),
+            None => Err(io::ErrorKind::InvalidData.into()),
+        }
+    }
+
+    /// The kick packet carrying the server list entry
+    pub fn response(self, status: &ServerStatus) -> Vec<u8> {
+        let reason = match self {
+            Self::Beta => format!("{}§{}§{}", status.motd.replace('§', ""), status.online, status.max_players),
+            Self::V1_4 => format!(
+                "§1\0{}\0{}\0{}\0{}\0{}",
+                status.protocol,
+                status.version_name,
+                status.motd.replace('\0', ""),
+                status.online,
+                status.max_players
+            ),
+        };
+        let reason: Vec<u16> = reason.encode_utf16().collect();
+
+        let mut response = vec![KICK];
+        response.extend_from_slice(&(reason.len() as u16).to_be_bytes());
+        response.extend(reason.iter().flat_map(|unit| unit.to_be_bytes()));
+        response
+    }
+
+    /// Answers the ping and closes the connection, legacy clients don't expect anything else
+    pub async fn respond(self, connection: &mut Connection) -> io::Result<()> {
+        let response = self.response(&ServerStatus::new(connection));
+        connection.write.write_all(&response).await?;
+        connection.write.shutdown().await
+    }
+}
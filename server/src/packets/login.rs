@@ -1,94 +1,217 @@
 use crate::{
-    decoder::{Decoder, ReceiveFromStream},
-    encoder::{Encoder, EncoderWriteExt, SendToWriter},
-    errors::EncodeError,
-    packets::config::{FinishConfiguration, RegistryData},
-    player::mc_player::McPlayer,
-    tcp::server::GameplayState,
-    types::VarInt,
+    crypto::server_hash,
+    errors::{AuthError, DispatchError, EncodeError},
+    packets::{
+        config::{FinishConfiguration, RegistryData},
+        State,
+    },
+    session::GameProfile,
+    tcp::server::Connection,
 };
-use macros::{Receivable, Serializable, Streamable};
-use std::{
-    io::Cursor,
-    ops::Add,
-    sync::{Arc, Mutex},
-};
-use tokio::io::AsyncWriteExt;
-use tokio::{io::AsyncWrite, net::tcp::OwnedWriteHalf};
+use macros::{Deserializable, Packet, Serializable};
 use uuid::Uuid;
 
-#[allow(dead_code)]
-#[derive(Receivable)]
+#[derive(Packet, Debug)]
+#[packet(id = 0x00, state = Login, direction = Serverbound)]
 pub struct LoginStart {
+    #[max_len(16)]
     pub username: String,
     pub uuid: Uuid,
 }
 
-#[derive(Receivable)]
+impl LoginStart {
+    pub async fn handle(connection: &mut Connection, login_start: Self) -> Result<(), EncodeError> {
+        println!("[Login] Username: {} | UUID: {}", login_start.username, login_start.uuid);
+
+        let Some(keys) = connection.keys.clone() else {
+            return Self::finish(connection, GameProfile::offline(login_start.username)).await;
+        };
+
+        let verify_token: [u8; 4] = rand::random();
+        connection
+            .send(&EncryptionRequest {
+                server_id: String::new(),
+                public_key: keys.public_key().to_vec(),
+                verify_token: verify_token.to_vec(),
+            })
+            .await?;
+
+        connection.login = Some(PendingLogin {
+            username: login_start.username,
+            verify_token,
+        });
+
+        Ok(())
+    }
+
+    /// Enables compression and lets the player in as `profile`
+    async fn finish(connection: &mut Connection, profile: GameProfile) -> Result<(), EncodeError> {
+        if let Some(threshold) = connection.config.compression_threshold {
+            connection
+                .send(&SetCompression {
+                    threshold: threshold.try_into().unwrap_or(i32::MAX),
+                })
+                .await?;
+            connection.codec.compression = Some(threshold);
+        }
+
+        connection.send(&LoginSuccess::new(profile)).await?;
+
+        if let Ok(_players) = connection.players.clone().lock() {
+            // TODO: There are 2 different KeepAlives! 1x for Configuration and 1x while Playing, and KeepAlive should only be sent for authorized users (valid uuid)
+            // players.push(McPlayer {
+            //     username: login_start.username,
+            //     uuid,
+            // });
+        }
+
+        Ok(())
+    }
+}
+
+/// A player that was sent an Encryption Request
+#[derive(Debug)]
+pub struct PendingLogin {
+    pub username: String,
+    pub verify_token: [u8; 4],
+}
+
+/// Asks the client to encrypt the connection, sent instead of Login Success in online mode
+#[derive(Packet, Debug)]
+#[packet(id = 0x01, state = Login, direction = Clientbound)]
+pub struct EncryptionRequest {
+    /// Always empty since 1.7
+    #[max_len(20)]
+    pub server_id: String,
+    /// ASN.1 DER encoded
+    pub public_key: Vec<u8>,
+    pub verify_token: Vec<u8>,
+}
+
+/// The shared secret and the verify token, both encrypted with the server's public key
+#[derive(Packet, Debug)]
+#[packet(id = 0x01, state = Login, direction = Serverbound)]
+pub struct EncryptionResponse {
+    pub shared_secret: Vec<u8>,
+    pub verify_token: Vec<u8>,
+}
+
+impl EncryptionResponse {
+    pub async fn handle(connection: &mut Connection, response: Self) -> Result<(), DispatchError> {
+        let profile = match Self::verify(connection, response).await {
+            Ok(profile) => profile,
+            Err(err) => {
+                // Vanilla tells the player why before closing the connection
+                let reason = match err {
+                    AuthError::HttpError(_) => "Authentication servers are down. Please try again later, sorry!",
+                    _ => "Failed to verify username!",
+                };
+                connection.send(&LoginDisconnect::from_text(reason)).await?;
+                return Err(err.into());
+            }
+        };
+
+        Ok(LoginStart::finish(connection, profile).await?)
+    }
+
+    /// Checks the verify token, enables encryption and asks the session server for the player's profile
+    async fn verify(connection: &mut Connection, response: Self) -> Result<GameProfile, AuthError> {
+        let (Some(login), Some(keys)) = (connection.login.take(), connection.keys.clone()) else {
+            return Err(AuthError::UnexpectedEncryptionResponse);
+        };
+
+        if keys.decrypt(&response.verify_token)? != login.verify_token {
+            return Err(AuthError::VerifyTokenMismatch);
+        }
+
+        let shared_secret = keys.decrypt(&response.shared_secret)?;
+        let shared_secret: [u8; 16] = shared_secret
+            .as_slice()
+            .try_into()
+            .map_err(|_| AuthError::InvalidSharedSecret(shared_secret.len()))?;
+
+        // Everything after the Encryption Response is encrypted, in both directions
+        connection.read.enable(&shared_secret);
+        connection.write.enable(&shared_secret);
+
+        let hash = server_hash("", &shared_secret, keys.public_key());
+        connection.session.has_joined(&login.username, &hash).await
+    }
+}
+
+#[derive(Packet, Debug)]
+#[packet(id = 0x03, state = Login, direction = Serverbound)]
 pub struct LoginAcknowledge {}
 
 impl LoginAcknowledge {
-    pub async fn handle(stream: &mut OwnedWriteHalf, gameplay_state: &mut GameplayState) {
+    pub async fn handle(connection: &mut Connection, _: Self) -> Result<(), EncodeError> {
         println!("[LoginAck] Received");
-        *gameplay_state = GameplayState::Play;
+        connection.state = State::Configuration;
 
-        RegistryData::create().await.send(stream).await.unwrap();
-        FinishConfiguration::default().send(stream).await.unwrap();
+        connection.send(&RegistryData::create().await).await?;
+        connection.send(&FinishConfiguration::default()).await
+    }
+}
+
+/// Closes the connection during login, the reason is a JSON text component
+#[derive(Packet, Debug)]
+#[packet(id = 0x00, state = Login, direction = Clientbound)]
+pub struct LoginDisconnect {
+    pub reason: String,
+}
+
+impl LoginDisconnect {
+    pub fn from_text<S>(message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            reason: serde_json::json!({ "text": message.into() }).to_string(),
+        }
     }
 }
 
-#[derive(Streamable)]
-#[packet_id(0x02)]
-struct LoginSuccess {
+/// Enables compression for every packet that follows, in both directions
+#[derive(Packet, Debug)]
+#[packet(id = 0x03, state = Login, direction = Clientbound)]
+pub struct SetCompression {
+    /// Smallest packet size that gets compressed
+    #[varint]
+    pub threshold: i32,
+}
+
+#[derive(Packet)]
+#[packet(id = 0x02, state = Login, direction = Clientbound)]
+pub struct LoginSuccess {
     pub uuid: Uuid,
     pub username: String,
     pub property: Vec<Property>,
 }
 
-#[derive(Serializable, Clone)]
+#[derive(Serializable, Deserializable, Clone)]
 pub struct Property {
     pub username: String,
     pub value: String,
     pub is_signed: bool,
+    #[skip_if(!is_signed)]
     pub signature: Option<String>,
 }
 
 impl LoginSuccess {
-    pub fn new(uuid: Uuid, username: String) -> Self {
+    pub fn new(profile: GameProfile) -> Self {
         Self {
-            uuid,
-            username,
-            property: vec![],
+            uuid: profile.id,
+            username: profile.name,
+            property: profile
+                .properties
+                .into_iter()
+                .map(|property| Property {
+                    username: property.name,
+                    value: property.value,
+                    is_signed: property.signature.is_some(),
+                    signature: property.signature,
+                })
+                .collect(),
         }
     }
 }
-
-pub struct Login;
-impl Login {
-    pub async fn handle(
-        cursor: &mut Cursor<Vec<u8>>,
-        players: Arc<Mutex<Vec<McPlayer>>>,
-        state: &mut GameplayState,
-        stream: &mut OwnedWriteHalf,
-    ) {
-        let login_start = LoginStart::receive(cursor).await.unwrap();
-        println!("[Login] Username: {} | UUID: {}", login_start.username, login_start.uuid);
-
-        let uuid = Uuid::new_v3(
-            &Uuid::NAMESPACE_URL,
-            String::from("OfflinePlayer:").add(&login_start.username).as_bytes(),
-        );
-        LoginSuccess::new(uuid, login_start.username.clone()).send(stream).await.unwrap();
-
-        if let Ok(mut players) = players.clone().lock() {
-            // TODO: There are 2 different KeepAlives! 1x for Configuration and 1x while Playing, and KeepAlive should only be sent for authorized users (valid uuid)
-            // players.push(McPlayer {
-            //     stream: stream.try_clone().unwrap(),
-            //     username: login_start.username,
-            //     uuid,
-            // });
-        }
-
-        *state = GameplayState::LoginAcknowledge;
-    }
-}
@@ -1,8 +1,63 @@
+use crate::errors::{DecodeError, EncodeError};
+use bytes::{Buf, BufMut};
+
 pub mod chunk;
 pub mod config;
 pub mod event;
 pub mod incoming;
+pub mod legacy;
 pub mod login;
 pub mod outgoing;
 pub mod play;
+pub mod serverbound;
 pub mod status;
+
+/// Connection state a packet is exchanged in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum State {
+    Handshake,
+    Status,
+    Login,
+    Configuration,
+    Play,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Serverbound,
+    Clientbound,
+}
+
+/// Implemented by `#[derive(Packet)]`, the body is everything after the length and id prefix
+pub trait Packet: Sized + Send + Sync {
+    const ID: i32;
+    const STATE: State;
+    const DIRECTION: Direction;
+
+    fn encode_body<B: BufMut>(&self, buf: &mut B) -> Result<(), EncodeError>;
+    fn decode_body<B: Buf>(buf: &mut B) -> Result<Self, DecodeError>;
+
+    /// Size of the body, see [`Encoder::size_hint`](crate::encoder::Encoder::size_hint)
+    fn body_size_hint(&self) -> usize;
+}
+
+/// The object safe part of [`Packet`], so packets of different types can be queued as `Box<dyn DynPacket>`
+pub trait DynPacket: Send + Sync {
+    fn id(&self) -> i32;
+    fn size_hint(&self) -> usize;
+    fn write_body(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError>;
+}
+
+impl<P: Packet> DynPacket for P {
+    fn id(&self) -> i32 {
+        P::ID
+    }
+
+    fn size_hint(&self) -> usize {
+        self.body_size_hint()
+    }
+
+    fn write_body(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        self.encode_body(buf)
+    }
+}
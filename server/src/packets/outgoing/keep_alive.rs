@@ -1,10 +1,7 @@
-use crate::encoder::Encoder;
-use macros::Streamable;
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
+use macros::Packet;
 
-#[derive(Streamable)]
-#[packet_id(0x24)]
+#[derive(Packet)]
+#[packet(id = 0x24, state = Play, direction = Clientbound)]
 pub struct KeepAlive {
     id: i64,
 }
@@ -1,12 +1,8 @@
-use crate::encoder::Encoder;
-use macros::Streamable;
-use nbt::{io::Nbt, types::Tag};
-use std::collections::HashMap;
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
+use macros::Packet;
+use nbt::io::Nbt;
 
-#[derive(Streamable)]
-#[packet_id(0x1B)]
+#[derive(Packet)]
+#[packet(id = 0x1B, state = Play, direction = Clientbound)]
 pub struct PlayDisconnect {
     reason: Nbt,
 }
@@ -17,12 +13,7 @@ impl PlayDisconnect {
         S: Into<String>,
     {
         Self {
-            reason: Nbt::new("", {
-                let mut map = HashMap::new();
-                map.insert("text", Tag::String(message.into()));
-
-                map
-            }),
+            reason: nbt::nbt! { "text": message.into() },
         }
     }
 }
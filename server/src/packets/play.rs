@@ -1,12 +1,8 @@
-use crate::encoder::{Encoder, EncoderWriteExt};
-use crate::errors::EncodeError;
 use crate::types::{Position, VarInt};
-use macros::{Serializable, Streamable};
-use tokio::io::AsyncWrite;
-use tokio::io::AsyncWriteExt;
+use macros::{Deserializable, Packet, Serializable};
 
-#[derive(Streamable)]
-#[packet_id(0x29)]
+#[derive(Packet)]
+#[packet(id = 0x29, state = Play, direction = Clientbound)]
 pub struct PlayLogin {
     pub entity_id: i32,
     pub is_hardcore: bool,
@@ -20,26 +16,29 @@ pub struct PlayLogin {
     pub dimension_type: String,
     pub dimension_name: String,
     pub hashed_seed: i64,
-    pub game_mode: u8,
+    pub game_mode: Gamemode,
     pub previous_game_mode: i8,
     pub is_debug: bool,
     pub is_flat: bool,
+    #[prefixed]
     pub death: Option<Death>,
     pub portal_cooldown: VarInt,
 }
 
-#[derive(Serializable, Clone)]
+#[derive(Serializable, Deserializable, Clone)]
 pub struct Death {
     pub dimension_name: String,
     pub location: Position,
 }
 
-// #[derive(Serializable)]
-// pub enum Gamemode {
-//     Survival = 1,
-//     Adventure = 2,
-//     Creative = 3,
-// }
+#[derive(Serializable, Deserializable, Clone, Copy, Debug, PartialEq, Eq)]
+#[serialize_as(u8)]
+pub enum Gamemode {
+    Survival = 0,
+    Creative = 1,
+    Adventure = 2,
+    Spectator = 3,
+}
 
 impl Default for PlayLogin {
     fn default() -> Self {
@@ -60,7 +59,7 @@ impl Default for PlayLogin {
             dimension_type: "minecraft:overworld".into(),
             dimension_name: "minecraft:overworld".into(),
             hashed_seed: 123456789i64,
-            game_mode: 1,
+            game_mode: Gamemode::Creative,
             previous_game_mode: -1,
             is_debug: true,
             is_flat: false,
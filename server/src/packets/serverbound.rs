@@ -0,0 +1,217 @@
+use crate::{
+    errors::{DecodeError, DispatchError},
+    packets::{
+        config::{ClientInformation, ReceiveFinishConfiguration, ServerboundPluginMessage},
+        incoming::{
+            chat_message::ChatMessage, handshake::HandShake, keep_alive_response::KeepAliveResponse, player_position::PlayerPosition,
+            player_position_rotation::PlayerPositionRotation, player_rotation::PlayerRotation,
+        },
+        login::{EncryptionResponse, LoginAcknowledge, LoginStart},
+        status::{PingRequest, StatusRequest},
+        Direction, Packet, State,
+    },
+};
+use futures::future::BoxFuture;
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+/// Declares one enum per state holding every packet the client may send in it, and [`ServerboundPacket`] over all of them
+macro_rules! serverbound (
+    ($($state: ident => $name: ident { $($packet: ident),* $(,)? }),* $(,)?) => (
+        $(
+            #[derive(Debug)]
+            #[allow(clippy::large_enum_variant)]
+            pub enum $name {
+                $($packet($packet),)*
+            }
+
+            impl $name {
+                pub const STATE: State = State::$state;
+
+                /// Decodes the payload following the packet id into the matching variant
+                pub fn decode(id: i32, payload: &[u8]) -> Result<Self, DispatchError> {
+                    $(
+                        if id == <$packet as Packet>::ID {
+                            return Ok(Self::$packet(decode_payload(payload)?));
+                        }
+                    )*
+
+                    Err(DispatchError::UnknownPacket { state: Self::STATE, id })
+                }
+
+                pub fn id(&self) -> i32 {
+                    match self {
+                        $(Self::$packet(_) => <$packet as Packet>::ID,)*
+                    }
+                }
+            }
+
+            $(
+                impl From<$packet> for $name {
+                    fn from(packet: $packet) -> Self {
+                        Self::$packet(packet)
+                    }
+                }
+
+                impl TryFrom<ServerboundPacket> for $packet {
+                    type Error = ServerboundPacket;
+
+                    fn try_from(packet: ServerboundPacket) -> Result<Self, Self::Error> {
+                        match packet {
+                            ServerboundPacket::$state($name::$packet(packet)) => Ok(packet),
+                            other => Err(other),
+                        }
+                    }
+                }
+
+                const _: () = assert!(
+                    matches!(<$packet as Packet>::STATE, State::$state) && matches!(<$packet as Packet>::DIRECTION, Direction::Serverbound),
+                    concat!(stringify!($packet), " is not a serverbound packet of the ", stringify!($state), " state"),
+                );
+            )*
+        )*
+
+        /// Any packet the client may send, in the enum of the state it belongs to
+        #[derive(Debug)]
+        #[allow(clippy::large_enum_variant)]
+        pub enum ServerboundPacket {
+            $($state($name),)*
+        }
+
+        impl ServerboundPacket {
+            /// The state and id of every packet listed above
+            pub const PACKETS: &'static [(State, i32)] = &[$($((State::$state, <$packet as Packet>::ID),)*)*];
+
+            /// Decodes the payload with the enum of `state`
+            pub fn decode(state: State, id: i32, payload: &[u8]) -> Result<Self, DispatchError> {
+                match state {
+                    $(State::$state => Ok(Self::$state($name::decode(id, payload)?)),)*
+                }
+            }
+
+            pub fn state(&self) -> State {
+                match self {
+                    $(Self::$state(_) => State::$state,)*
+                }
+            }
+
+            pub fn id(&self) -> i32 {
+                match self {
+                    $(Self::$state(packet) => packet.id(),)*
+                }
+            }
+        }
+    )
+);
+
+serverbound! {
+    Handshake => HandshakePacket { HandShake },
+    Status => StatusPacket { StatusRequest, PingRequest },
+    Login => LoginPacket { LoginStart, EncryptionResponse, LoginAcknowledge },
+    Configuration => ConfigurationPacket { ClientInformation, ServerboundPluginMessage, ReceiveFinishConfiguration },
+    Play => PlayPacket { ChatMessage, KeepAliveResponse, PlayerPosition, PlayerPositionRotation, PlayerRotation },
+}
+
+/// Decodes `P` from a whole payload, which has to end exactly where the packet does
+pub fn decode_payload<P: Packet>(mut payload: &[u8]) -> Result<P, DispatchError> {
+    let packet = match P::decode_body(&mut payload) {
+        Err(DecodeError::UnexpectedEnd { .. }) => {
+            return Err(DispatchError::PacketTooShort { id: P::ID });
+        }
+        result => result?,
+    };
+
+    if !payload.is_empty() {
+        return Err(DispatchError::PacketTooLong {
+            id: P::ID,
+            remaining: payload.len(),
+        });
+    }
+
+    Ok(packet)
+}
+
+/// An `async fn(&mut C, P)`, spelled out as a trait so the returned future can borrow the context
+///
+/// Handlers may fail with anything a `DispatchError` can be made from, usually an `EncodeError` from sending a reply.
+pub trait Handler<'a, C: 'a, P>: Fn(&'a mut C, P) -> Self::Future {
+    type Error: Into<DispatchError>;
+    type Future: Future<Output = Result<(), Self::Error>> + Send + 'a;
+}
+
+impl<'a, C: 'a, P, F, Fut, E> Handler<'a, C, P> for F
+where
+    F: Fn(&'a mut C, P) -> Fut,
+    Fut: Future<Output = Result<(), E>> + Send + 'a,
+    E: Into<DispatchError>,
+{
+    type Error = E;
+    type Future = Fut;
+}
+
+type BoxedHandler<C> = Box<dyn for<'a> Fn(&'a mut C, ServerboundPacket) -> BoxFuture<'a, Result<(), DispatchError>> + Send + Sync>;
+
+/// Handlers registered per packet type, looked up by the state and id of incoming packets
+///
+/// Only packets listed in [`ServerboundPacket`] can be handled, incoming payloads are decoded through it.
+pub struct Handlers<C> {
+    handlers: HashMap<(State, i32), BoxedHandler<C>>,
+}
+
+impl<C> Default for Handlers<C> {
+    fn default() -> Self {
+        Self { handlers: HashMap::new() }
+    }
+}
+
+impl<C: Send> Handlers<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` for `P`, replacing any handler registered for it before
+    pub fn on<P, F>(&mut self, handler: F) -> &mut Self
+    where
+        P: Packet + TryFrom<ServerboundPacket, Error = ServerboundPacket> + 'static,
+        F: for<'a> Handler<'a, C, P> + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        self.handlers.insert(
+            (P::STATE, P::ID),
+            boxed(move |context, packet| {
+                let handler = handler.clone();
+                Box::pin(async move {
+                    let packet = P::try_from(packet).map_err(|packet| DispatchError::UnknownPacket {
+                        state: packet.state(),
+                        id: packet.id(),
+                    })?;
+                    handler(context, packet).await.map_err(Into::into)
+                })
+            }),
+        );
+
+        self
+    }
+
+    /// State and id of every packet a handler is registered for
+    pub fn packets(&self) -> impl Iterator<Item = (State, i32)> + '_ {
+        self.handlers.keys().copied()
+    }
+
+    /// Decodes the payload through [`ServerboundPacket`] and runs the handler registered for it
+    pub async fn dispatch(&self, context: &mut C, state: State, id: i32, payload: &[u8]) -> Result<(), DispatchError> {
+        let packet = ServerboundPacket::decode(state, id, payload)?;
+
+        match self.handlers.get(&(state, id)) {
+            Some(handler) => handler(context, packet).await,
+            None => Err(DispatchError::Unhandled { state, id }),
+        }
+    }
+}
+
+/// Pins down the higher-ranked signature, closures don't infer it from the boxed type
+fn boxed<C, F>(handler: F) -> BoxedHandler<C>
+where
+    F: for<'a> Fn(&'a mut C, ServerboundPacket) -> BoxFuture<'a, Result<(), DispatchError>> + Send + Sync + 'static,
+{
+    Box::new(handler)
+}
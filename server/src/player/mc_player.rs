@@ -1,6 +1,6 @@
-use crate::{encoder::SendToWriter, packets::outgoing::play_disconnect::PlayDisconnect};
+use crate::{packets::outgoing::play_disconnect::PlayDisconnect, tcp::server::Connection};
 use std::io;
-use tokio::{io::AsyncWriteExt, net::TcpStream};
+use tokio::io::AsyncWriteExt;
 use uuid::Uuid;
 
 pub trait Player {
@@ -11,18 +11,18 @@ pub trait Player {
 }
 
 pub struct McPlayer {
-    pub(crate) stream: TcpStream,
     pub username: String,
     pub uuid: Uuid,
 }
 
-impl Player for McPlayer {
+/// Goes through the connection, the client only understands frames that are compressed and encrypted like the rest
+impl Player for Connection {
     async fn disconnect<S>(&mut self, reason: S) -> io::Result<bool>
     where
         S: Into<String>,
     {
-        if let Ok(_) = PlayDisconnect::from_text(reason.into()).send(&mut self.stream).await {
-            self.stream.shutdown().await?;
+        if self.send(&PlayDisconnect::from_text(reason.into())).await.is_ok() {
+            self.write.shutdown().await?;
             return Ok(true);
         }
 
@@ -0,0 +1,91 @@
+use crate::errors::AuthError;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+use std::{ops::Add, time::Duration};
+use uuid::Uuid;
+
+/// A player as the session server knows them
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct GameProfile {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<ProfileProperty>,
+}
+
+/// A signed profile property, the skin and cape are sent as a `textures` property
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    pub signature: Option<String>,
+}
+
+impl GameProfile {
+    /// The profile vanilla makes up for players in offline mode
+    pub fn offline(username: String) -> Self {
+        Self {
+            id: Uuid::new_v3(&Uuid::NAMESPACE_URL, String::from("OfflinePlayer:").add(&username).as_bytes()),
+            name: username,
+            properties: vec![],
+        }
+    }
+}
+
+/// Asks whether a player joined the server through the session server, yielding their profile if they did
+pub trait SessionVerifier: Send + Sync {
+    fn has_joined<'a>(&'a self, username: &'a str, server_hash: &'a str) -> BoxFuture<'a, Result<GameProfile, AuthError>>;
+}
+
+/// Verifies players against Mojang's session server
+pub struct HttpSessionVerifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl HttpSessionVerifier {
+    pub const MOJANG: &'static str = "https://sessionserver.mojang.com/session/minecraft/hasJoined";
+    /// How long a request may take before the login fails, a hanging session server would otherwise hold it open forever
+    pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_timeout(url, Self::TIMEOUT)
+    }
+
+    pub fn with_timeout(url: impl Into<String>, timeout: Duration) -> Self {
+        // Only fails where `reqwest::Client::new` would panic as well, if no TLS backend can be set up
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to set up the HTTP client");
+
+        Self { client, url: url.into() }
+    }
+}
+
+impl Default for HttpSessionVerifier {
+    fn default() -> Self {
+        Self::new(Self::MOJANG)
+    }
+}
+
+impl SessionVerifier for HttpSessionVerifier {
+    fn has_joined<'a>(&'a self, username: &'a str, server_hash: &'a str) -> BoxFuture<'a, Result<GameProfile, AuthError>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get(&self.url)
+                .query(&[("username", username), ("serverId", server_hash)])
+                .send()
+                .await?
+                .error_for_status()?;
+
+            // The session server answers with 204 No Content when the player didn't join
+            if response.status() == reqwest::StatusCode::NO_CONTENT {
+                return Err(AuthError::NotAuthenticated(username.to_string()));
+            }
+
+            Ok(response.json().await?)
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{
+    decoder::DecoderReadExt,
+    errors::{EncodeError, FrameError},
+    packets::{DynPacket, State},
+    types::VarInt,
+    utils::{decompress_frame, prepare_compressed_response, prepare_response},
+};
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Largest frame vanilla accepts, the length prefix is at most a 3 byte VarInt
+pub const MAX_FRAME_LEN: usize = 2_097_151;
+
+/// Splits the stream into length-prefixed packets and frames outgoing ones
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameCodec {
+    /// Threshold of the Set Compression packet once it was sent, frames in both directions are compressed from then on
+    pub compression: Option<usize>,
+}
+
+impl FrameCodec {
+    /// Largest frame a client may send in `state`
+    pub fn max_frame_len(state: State) -> usize {
+        match state {
+            // A handshake with a 255 character address, or a status request
+            State::Handshake | State::Status => 1024,
+            // Login Start and an Encryption Response for a 1024 bit key
+            State::Login => 4096,
+            State::Configuration | State::Play => MAX_FRAME_LEN,
+        }
+    }
+
+    /// Reads the next packet id and payload, `None` once the client closed the connection between two frames
+    ///
+    /// The length is checked against the limit of `state` before anything is allocated for the frame.
+    pub async fn read<R: AsyncRead + Unpin>(&self, reader: &mut R, state: State) -> Result<Option<(i32, Vec<u8>)>, FrameError> {
+        let first = match reader.read_u8().await {
+            Ok(byte) => byte,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(FrameError::IOError(err)),
+        };
+        let length = Self::read_length(reader, first).await?;
+
+        let max_length = Self::max_frame_len(state);
+        if length > max_length {
+            return Err(FrameError::FrameTooLong { state, length, max_length });
+        }
+        if length == 0 {
+            return Err(FrameError::EmptyFrame);
+        }
+
+        let mut frame = vec![0; length];
+        reader.read_exact(&mut frame).await?;
+
+        if let Some(threshold) = self.compression {
+            frame = decompress_frame(frame, threshold)?;
+        }
+
+        let mut payload = frame.as_slice();
+        let id = payload.read_var_i32()?;
+        let id_length = frame.len() - payload.len();
+        frame.drain(..id_length);
+
+        Ok(Some((id, frame)))
+    }
+
+    /// The VarInt length prefix following `first`, taken off the stream a byte at a time since it says how much to read
+    async fn read_length<R: AsyncRead + Unpin>(reader: &mut R, first: u8) -> Result<usize, FrameError> {
+        let mut prefix = vec![first];
+        while prefix.len() < 5 && prefix[prefix.len() - 1] & 0x80 != 0 {
+            prefix.push(reader.read_u8().await?);
+        }
+
+        Ok(prefix.as_slice().read_length()?)
+    }
+
+    /// Frames `packet` the way the client currently expects
+    pub fn encode(&self, packet: &dyn DynPacket) -> Result<Vec<u8>, EncodeError> {
+        let mut data = Vec::with_capacity(packet.size_hint());
+        packet.write_body(&mut data)?;
+
+        match self.compression {
+            Some(threshold) => prepare_compressed_response(VarInt(packet.id()), data, threshold),
+            None => Ok(prepare_response(VarInt(packet.id()), data)),
+        }
+    }
+}
@@ -1,55 +1,82 @@
-use crate::decoder::{DecoderReadExt, ReceiveFromStream};
-use crate::encoder::SendToWriter;
 use crate::{
+    config::ServerConfig,
+    crypto::{CipherReader, CipherWriter, ServerKeys},
+    errors::{AuthError, DispatchError, EncodeError},
     packets::{
-        chunk::{ChunkDataUpdateLight, SetDefaultSpawnPosition, SynchronizePlayerPosition},
         config::{ClientInformation, ReceiveFinishConfiguration, ServerboundPluginMessage},
-        event::GameEvent,
         incoming::{
-            handshake::HandShake, keep_alive_response::KeepAliveResponse, player_position::PlayerPosition,
+            chat_message::ChatMessage, handshake::HandShake, keep_alive_response::KeepAliveResponse, player_position::PlayerPosition,
             player_position_rotation::PlayerPositionRotation, player_rotation::PlayerRotation,
         },
-        login::{Login, LoginAcknowledge},
-        outgoing::{keep_alive::KeepAlive, play_disconnect::PlayDisconnect},
-        play::PlayLogin,
-        status::Status,
+        legacy::LegacyPing,
+        login::{EncryptionResponse, LoginAcknowledge, LoginStart, PendingLogin},
+        outgoing::keep_alive::KeepAlive,
+        serverbound::Handlers,
+        status::{PingRequest, StatusRequest},
+        DynPacket, State,
     },
     player::mc_player::McPlayer,
+    session::{HttpSessionVerifier, SessionVerifier},
+    tcp::codec::FrameCodec,
 };
 use std::{
+    error::Error,
     fmt::Debug,
     io,
-    io::Cursor,
     sync::{Arc, Mutex},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::{
-    io::AsyncReadExt,
-    net::{TcpListener, TcpStream, ToSocketAddrs},
+    io::AsyncWriteExt,
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream, ToSocketAddrs,
+    },
     task, time,
 };
 use uuid::Uuid;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-pub enum GameplayState {
-    None = -1,
-    Status = 1,
-    Login = 2,
-    LoginAcknowledge = 3,
-    Play = 4,
+/// What packet handlers get to work with
+pub struct Connection {
+    pub state: State,
+    pub read: CipherReader<OwnedReadHalf>,
+    pub write: CipherWriter<OwnedWriteHalf>,
+    pub players: Arc<Mutex<Vec<McPlayer>>>,
+    pub config: Arc<ServerConfig>,
+    pub codec: FrameCodec,
+    /// Present in online mode
+    pub keys: Option<Arc<ServerKeys>>,
+    pub session: Arc<dyn SessionVerifier>,
+    /// The player waiting for their Encryption Response
+    pub login: Option<PendingLogin>,
 }
 
-pub enum IngameState {
-    Config,
-    Playing,
+impl Connection {
+    /// Writes `packet` framed the way the client currently expects
+    pub async fn send(&mut self, packet: &dyn DynPacket) -> Result<(), EncodeError> {
+        let buffer = self.codec.encode(packet)?;
+        self.write.write_all(&buffer).await?;
+        Ok(self.write.flush().await?)
+    }
+
+    /// Writes `packets` in order with a single flush
+    pub async fn send_all(&mut self, packets: &[Box<dyn DynPacket>]) -> Result<(), EncodeError> {
+        for packet in packets {
+            let buffer = self.codec.encode(packet.as_ref())?;
+            self.write.write_all(&buffer).await?;
+        }
+        Ok(self.write.flush().await?)
+    }
 }
 
 // Lol
+#[allow(dead_code)]
 pub enum Either<T, U> {
     Either(T),
     Or(U),
 }
 
+#[allow(dead_code)]
 pub trait Server {
     type Player;
 
@@ -65,6 +92,16 @@ pub trait Server {
 
 pub struct McServer {
     players: Arc<Mutex<Vec<McPlayer>>>,
+    handlers: Arc<Handlers<Connection>>,
+    config: Arc<ServerConfig>,
+    keys: Option<Arc<ServerKeys>>,
+    session: Arc<dyn SessionVerifier>,
+}
+
+impl Default for McServer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 // impl Server for McServer {
@@ -111,12 +148,33 @@ pub struct McServer {
 // }
 
 impl McServer {
+    /// An offline mode server, which has no keypair to generate and so can't fail
     pub fn new() -> Self {
+        Self::build(ServerConfig::default(), None)
+    }
+
+    /// Fails if online mode is on and the RSA keypair can't be generated
+    pub fn with_config(config: ServerConfig) -> Result<Self, AuthError> {
+        let keys = config.online_mode.then(ServerKeys::generate).transpose()?;
+        Ok(Self::build(config, keys.map(Arc::new)))
+    }
+
+    fn build(config: ServerConfig, keys: Option<Arc<ServerKeys>>) -> Self {
         Self {
             players: Arc::new(Mutex::new(vec![])),
+            handlers: Arc::new(Self::handlers()),
+            config: Arc::new(config),
+            keys,
+            session: Arc::new(HttpSessionVerifier::default()),
         }
     }
 
+    /// Replaces the session server players are verified against in online mode
+    pub fn with_session_verifier(mut self, session: impl SessionVerifier + 'static) -> Self {
+        self.session = Arc::new(session);
+        self
+    }
+
     pub async fn start<E>(&self, endpoint: &E) -> std::io::Result<()>
     where
         E: ToSocketAddrs + Debug,
@@ -124,18 +182,42 @@ impl McServer {
         let listener = TcpListener::bind(endpoint).await?;
         println!("Server started @ {endpoint:?}");
 
+        self.serve(listener).await
+    }
+
+    /// Accepts connections from an already bound `listener`
+    pub async fn serve(&self, listener: TcpListener) -> std::io::Result<()> {
         loop {
-            let players = self.players.clone();
             let (socket, _) = listener.accept().await?;
+            let connection = self.connection(socket);
+            let handlers = self.handlers.clone();
 
-            // task::spawn(async move { Self::handle_keep_alive(&mut socket) });
-            task::spawn(async move { Self::handle_connection(players.clone(), socket).await })
-                .await
-                .unwrap();
+            // task::spawn(async move { Self::handle_keep_alive(&mut connection) });
+            task::spawn(Self::handle_connection(handlers, connection));
         }
     }
 
-    async fn handle_keep_alive(mut write: &mut TcpStream) {
+    fn connection(&self, stream: TcpStream) -> Connection {
+        if let Ok(peer) = stream.peer_addr() {
+            println!("{peer} connected");
+        }
+        let (read, write) = stream.into_split();
+
+        Connection {
+            state: State::Handshake,
+            read: CipherReader::new(read),
+            write: CipherWriter::new(write),
+            players: self.players.clone(),
+            config: self.config.clone(),
+            codec: FrameCodec::default(),
+            keys: self.keys.clone(),
+            session: self.session.clone(),
+            login: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    async fn handle_keep_alive(connection: &mut Connection) -> Result<(), EncodeError> {
         println!("Starting KeepAlive thread...");
 
         let mut interval_timer = time::interval(Duration::from_secs(15));
@@ -145,114 +227,91 @@ impl McServer {
             interval_timer.tick().await;
 
             println!("[KeepAlive] sending to player...");
-            KeepAlive::new(SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64)
-                .send(&mut write)
-                .await
-                .unwrap();
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            connection.send(&KeepAlive::new(now.as_secs() as i64)).await?;
         }
     }
 
-    async fn handle_connection(players: Arc<Mutex<Vec<McPlayer>>>, stream: TcpStream) {
-        println!("{} connected", stream.peer_addr().unwrap());
-        let mut gameplay_state = GameplayState::None;
-        let mut ingame_state = IngameState::Config;
-        let (mut read, mut write) = stream.into_split();
-
-        loop {
-            let len = read.read_var_i32().await.unwrap_or(0) as usize;
-
-            if len == 0 {
-                println!("{} disconnected", read.peer_addr().unwrap());
-                break;
+    async fn handle_connection(handlers: Arc<Handlers<Connection>>, mut connection: Connection) {
+        let peer = match connection.read.get_ref().peer_addr() {
+            Ok(peer) => peer.to_string(),
+            Err(_) => String::from("Client"),
+        };
+
+        match LegacyPing::read(&mut connection).await {
+            Ok(None) => {}
+            Ok(Some(ping)) => {
+                if let Err(err) = ping.respond(&mut connection).await {
+                    println!("{peer} disconnected: {}", reason(&err));
+                }
+                return;
+            }
+            Err(err) => {
+                println!("{peer} disconnected: {}", reason(&err));
+                return;
             }
+        }
 
-            let mut packet_buffer: Vec<u8> = vec![0u8; len];
-            read.read_exact(&mut packet_buffer).await.unwrap();
-
-            let mut cursor = Cursor::new(packet_buffer);
-            let packet_id = cursor.read_var_i32().await.unwrap();
-
-            match gameplay_state {
-                GameplayState::None => HandShake::handle(&mut cursor, &mut gameplay_state, &mut write).await,
-                GameplayState::Status => Status::handle(&mut write).await,
-                GameplayState::Login => Login::handle(&mut cursor, players.clone(), &mut gameplay_state, &mut write).await,
-                GameplayState::LoginAcknowledge => LoginAcknowledge::handle(&mut write, &mut gameplay_state).await,
-                GameplayState::Play => match ingame_state {
-                    IngameState::Config => match packet_id {
-                        0x00 => {
-                            let client_information = ClientInformation::receive(&mut cursor).await.unwrap();
-                            println!("{:?}", client_information);
-                        }
-
-                        0x01 => {
-                            let plugin_response = ServerboundPluginMessage::receive(&mut cursor).await.unwrap();
-                            println!("{:?}", plugin_response);
-                        }
-
-                        0x02 => {
-                            ReceiveFinishConfiguration::receive(&mut cursor).await.unwrap();
-                            println!("[Config] Finishing configuration");
-                            ingame_state = IngameState::Playing;
-
-                            //TODO Keep-Alive task should start here but can't because the tokio TcpStream can't be cloned
-                            // task::spawn(async move { Self::handle_keep_alive(&mut write) });
-
-                            PlayLogin::default().send(&mut write).await.unwrap();
-                            ChunkDataUpdateLight::default().send(&mut write).await.unwrap();
-                            SynchronizePlayerPosition::default().send(&mut write).await.unwrap();
-                            GameEvent::default().send(&mut write).await.unwrap();
-                            SetDefaultSpawnPosition::default().send(&mut write).await.unwrap();
-                        }
-
-                        _ => {
-                            println!("len_{len} packetId_{packet_id}");
-                            println!("{}", String::from_utf8_lossy(&cursor.into_inner()).to_string())
-                        }
-                    },
-                    IngameState::Playing => match packet_id {
-                        // Chat Message
-                        0x05 => {
-                            let message = cursor.read_string(256).await.unwrap();
-                            PlayDisconnect::from_text(format!("{}", message.repeat(50)))
-                                .send(&mut write)
-                                .await
-                                .unwrap();
-                        }
-
-                        // KeepAlive Response
-                        0x15 => {
-                            let res = KeepAliveResponse::receive(&mut cursor).await.unwrap();
-                            println!("KeepAlive response: {}", res.id);
-                        }
-
-                        // Player Position
-                        0x17 => {
-                            let pos = PlayerPosition::receive(&mut cursor).await.unwrap();
-                            println!("{},{},{} [{}]", pos.x, pos.y, pos.z, pos.on_ground);
-                        }
-
-                        // Player Position and rotation
-                        0x18 => {
-                            let pos_rot = PlayerPositionRotation::receive(&mut cursor).await.unwrap();
-                            println!(
-                                "{},{},{} | {} | {} | [{}]",
-                                pos_rot.x, pos_rot.y, pos_rot.z, pos_rot.yaw, pos_rot.pitch, pos_rot.on_ground
-                            );
-                        }
-
-                        // Player rotation
-                        0x19 => {
-                            let rot = PlayerRotation::receive(&mut cursor).await.unwrap();
-                            println!("{} | {} | [{}]", rot.yaw, rot.pitch, rot.on_ground);
-                        }
-
-                        _ => {
-                            println!("len_{len} packetId_{packet_id}");
-                            println!("{}", String::from_utf8_lossy(&cursor.into_inner()).to_string())
-                        }
-                    },
-                },
+        loop {
+            let state = connection.state;
+            let (packet_id, payload) = match connection.codec.read(&mut connection.read, state).await {
+                Ok(Some(packet)) => packet,
+                Ok(None) => {
+                    println!("{peer} disconnected");
+                    break;
+                }
+                Err(err) => {
+                    println!("{peer} disconnected: {}", reason(&err));
+                    break;
+                }
+            };
+
+            match handlers.dispatch(&mut connection, state, packet_id, &payload).await {
+                Ok(()) => {}
+                // Only the later states have packets a client may send that the server does not handle yet
+                Err(err @ (DispatchError::UnknownPacket { .. } | DispatchError::Unhandled { .. }))
+                    if matches!(state, State::Configuration | State::Play) =>
+                {
+                    println!("{err}")
+                }
+                Err(err) => {
+                    println!("{peer} disconnected: {}", reason(&err));
+                    break;
+                }
             }
         }
     }
+
+    /// The handlers every connection is served with
+    pub fn handlers() -> Handlers<Connection> {
+        let mut handlers = Handlers::new();
+        handlers
+            .on(HandShake::handle)
+            .on(StatusRequest::handle)
+            .on(PingRequest::handle)
+            .on(LoginStart::handle)
+            .on(EncryptionResponse::handle)
+            .on(LoginAcknowledge::handle)
+            .on(ClientInformation::handle)
+            .on(ServerboundPluginMessage::handle)
+            .on(ReceiveFinishConfiguration::handle)
+            .on(ChatMessage::handle)
+            .on(KeepAliveResponse::handle)
+            .on(PlayerPosition::handle)
+            .on(PlayerPositionRotation::handle)
+            .on(PlayerRotation::handle);
+
+        handlers
+    }
+}
+
+/// `err` followed by everything that caused it
+fn reason(err: &dyn Error) -> String {
+    let mut reason = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        reason = format!("{reason}: {err}");
+        source = err.source();
+    }
+    reason
 }
@@ -3,6 +3,13 @@ use std::fmt::Display;
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct VarInt(pub i32);
 
+impl VarInt {
+    /// Bytes the VarInt takes up on the wire
+    pub fn encoded_len(self) -> usize {
+        (32 - (self.0 as u32).leading_zeros() as usize).max(1).div_ceil(7)
+    }
+}
+
 impl From<VarInt> for i32 {
     fn from(value: VarInt) -> Self {
         value.0
@@ -24,6 +31,13 @@ impl Display for VarInt {
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct VarLong(pub i64);
 
+impl VarLong {
+    /// Bytes the VarLong takes up on the wire
+    pub fn encoded_len(self) -> usize {
+        (64 - (self.0 as u64).leading_zeros() as usize).max(1).div_ceil(7)
+    }
+}
+
 impl From<VarLong> for i64 {
     fn from(value: VarLong) -> Self {
         value.0
@@ -81,35 +95,28 @@ impl Position {
     }
 }
 
+/// Arithmetic shifts sign extend each coordinate before it is converted to a float
 impl From<u64> for Position {
     fn from(value: u64) -> Self {
-        let mut x: f32 = (value >> 38) as f32;
-        let mut z: f32 = (value << 26 >> 38) as f32;
-        let mut y: f32 = (value << 52 >> 52) as f32;
+        let value = value as i64;
 
-        if x >= (1u64 << 25u64) as f32 {
-            x -= (1 << 26) as f32;
+        Self {
+            x: (value >> 38) as f32,
+            y: (value << 52 >> 52) as f32,
+            z: (value << 26 >> 38) as f32,
         }
-
-        if z >= (1u64 << 25u64) as f32 {
-            z -= (1 << 26) as f32;
-        }
-
-        if y >= (1u64 << 11u64) as f32 {
-            y -= (1 << 12) as f32;
-        }
-
-        Self { x, z, y }
     }
 }
 
+/// Coordinates go through `i64` so negative ones keep their two's complement bits, `f32 as u64` would clamp them to 0
 impl From<Position> for u64 {
     fn from(value: Position) -> Self {
-        ((value.x as u64 & 0x03FF_FFFF) << 38) | ((value.z as u64 & 0x03FF_FFFF) << 12) | (value.y as u64 & 0xFFF)
+        ((value.x as i64 as u64 & 0x03FF_FFFF) << 38) | ((value.z as i64 as u64 & 0x03FF_FFFF) << 12) | (value.y as i64 as u64 & 0xFFF)
     }
 }
 
 /// <https://wiki.vg/Protocol#BitSet>
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct BitSet(pub Vec<i64>);
 impl BitSet {
     /// Constructor to create a new `BitSet` with a specified size
@@ -122,7 +129,7 @@ impl BitSet {
     ///
     /// A new `BitSet` with all bits initially set to 0
     pub fn new(size: usize) -> Self {
-        let num_i64s = (size + 63) / 64;
+        let num_i64s = size.div_ceil(64);
         let bits = vec![0; num_i64s];
         Self(bits)
     }
@@ -1,8 +1,20 @@
-use crate::{encoder::EncoderWriteExt, types::VarInt};
-use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
+use crate::{
+    decoder::DecoderReadExt,
+    encoder::EncoderWriteExt,
+    errors::{DecodeError, EncodeError},
+    types::VarInt,
+};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use std::{
+    io::{Read, Write},
+    time::{SystemTime, SystemTimeError, UNIX_EPOCH},
+};
 
 pub const MAX_STRING_LEN: u16 = 32767;
 
+/// Largest size a compressed packet may claim once decompressed, the same limit the client enforces
+pub const MAX_DATA_LEN: usize = 8_388_608;
+
 pub fn system_time_millis() -> Result<u64, SystemTimeError> {
     let start = SystemTime::now();
     let since_the_epoch = start.duration_since(UNIX_EPOCH)?;
@@ -12,14 +24,65 @@ pub fn system_time_millis() -> Result<u64, SystemTimeError> {
 }
 
 #[must_use]
-pub async fn prepare_response(packet_id: VarInt, mut data: Vec<u8>) -> Vec<u8> {
-    let mut temp_buffer = vec![];
-    temp_buffer.write_var_i32(packet_id).await.unwrap();
-    temp_buffer.append(&mut data);
+pub fn prepare_response(packet_id: VarInt, data: Vec<u8>) -> Vec<u8> {
+    let length = VarInt::from(packet_id.encoded_len() + data.len());
 
-    let mut buffer = Vec::new();
-    buffer.write_var_i32(VarInt(temp_buffer.len() as i32)).await.unwrap();
-    buffer.append(&mut temp_buffer);
+    let mut buffer = Vec::with_capacity(length.encoded_len() + length.0 as usize);
+    buffer.write_var_i32(length);
+    buffer.write_var_i32(packet_id);
+    buffer.extend_from_slice(&data);
 
     buffer
 }
+
+/// Frames a packet like [`prepare_response`] once compression is enabled, zlib compressing packets of at least `threshold` bytes
+pub fn prepare_compressed_response(packet_id: VarInt, data: Vec<u8>, threshold: usize) -> Result<Vec<u8>, EncodeError> {
+    let mut packet = Vec::with_capacity(packet_id.encoded_len() + data.len());
+    packet.write_var_i32(packet_id);
+    packet.extend_from_slice(&data);
+
+    let mut body = vec![];
+    if packet.len() >= threshold {
+        body.write_var_i32(VarInt::from(packet.len()));
+        let mut encoder = ZlibEncoder::new(body, Compression::default());
+        encoder.write_all(&packet)?;
+        body = encoder.finish()?;
+    } else {
+        // A data length of zero marks the packet as sent uncompressed
+        body.write_var_i32(VarInt(0));
+        body.append(&mut packet);
+    }
+
+    let mut buffer = vec![];
+    buffer.write_var_i32(VarInt::from(body.len()));
+    buffer.append(&mut body);
+
+    Ok(buffer)
+}
+
+/// Returns the packet id and data of a frame read once compression is enabled
+pub fn decompress_frame(frame: Vec<u8>, threshold: usize) -> Result<Vec<u8>, DecodeError> {
+    let mut reader = frame.as_slice();
+    let data_len = reader.read_length()?;
+
+    if data_len == 0 {
+        return Ok(reader.to_vec());
+    }
+
+    if data_len < threshold || data_len > MAX_DATA_LEN {
+        return Err(DecodeError::InvalidDataLength(data_len));
+    }
+
+    // Reading one byte past the claimed length is enough to tell it was a lie
+    let mut packet = Vec::with_capacity(data_len);
+    ZlibDecoder::new(reader).take(data_len as u64 + 1).read_to_end(&mut packet)?;
+
+    if packet.len() != data_len {
+        return Err(DecodeError::DataLengthMismatch {
+            expected: data_len,
+            actual: packet.len(),
+        });
+    }
+
+    Ok(packet)
+}
@@ -0,0 +1,253 @@
+#[path = "../../nbt/tests/support/mod.rs"]
+mod support;
+
+use nbt::{io::Nbt, types::Tag};
+use proptest::{collection::vec, prelude::*};
+use server::{
+    decoder::{Decoder, DecoderReadExt},
+    encoder::{Encoder, EncoderWriteExt},
+    errors::{DecodeError, EncodeError},
+    packets::{
+        config::{ChatMode, MainHand},
+        play::Gamemode,
+    },
+    types::{BitSet, Position, VarInt, VarLong},
+};
+use std::fmt::Debug;
+use support::{compound_of, tag};
+use uuid::Uuid;
+
+/// Encodes `value`, decodes it back and checks that exactly the encoded bytes were consumed
+fn round_trip<T>(value: &T) -> T
+where
+    T: Encoder + Decoder<Output = T>,
+{
+    let mut bytes = vec![];
+    value.encode(&mut bytes).unwrap();
+    // NBT is the only thing not measured up front
+    assert!(matches!(value.size_hint(), 0) || value.size_hint() == bytes.len());
+
+    let mut reader = bytes.as_slice();
+    let decoded = T::decode(&mut reader).unwrap();
+    assert!(reader.is_empty(), "{} bytes left over", reader.len());
+
+    decoded
+}
+
+fn assert_round_trip<T>(value: T)
+where
+    T: Encoder + Decoder<Output = T> + PartialEq + Debug,
+{
+    assert_eq!(round_trip(&value), value);
+}
+
+fn decode<T: Decoder>(mut bytes: &[u8]) -> Result<T::Output, DecodeError> {
+    T::decode(&mut bytes)
+}
+
+proptest! {
+    #[test]
+    fn primitives(a in any::<u8>(), b in any::<i8>(), c in any::<i16>(), d in any::<u16>(), e in any::<i32>(), f in any::<u32>(), g in any::<i64>(), h in any::<u64>(), i in any::<bool>()) {
+        assert_round_trip(a);
+        assert_round_trip(b);
+        assert_round_trip(c);
+        assert_round_trip(d);
+        assert_round_trip(e);
+        assert_round_trip(f);
+        assert_round_trip(g);
+        assert_round_trip(h);
+        assert_round_trip(i);
+    }
+
+    #[test]
+    fn floats(a in any::<f32>(), b in any::<f64>()) {
+        prop_assert_eq!(round_trip(&a).to_bits(), a.to_bits());
+        prop_assert_eq!(round_trip(&b).to_bits(), b.to_bits());
+    }
+
+    #[test]
+    fn var_int(value in any::<i32>()) {
+        let mut bytes = vec![];
+        bytes.write_var_i32(VarInt(value));
+
+        prop_assert!(bytes.len() <= 5);
+        prop_assert_eq!(decode::<VarInt>(&bytes).unwrap(), VarInt(value));
+    }
+
+    #[test]
+    fn var_long(value in any::<i64>()) {
+        let mut bytes = vec![];
+        bytes.write_var_i64(VarLong(value));
+
+        prop_assert!(bytes.len() <= 10);
+        prop_assert_eq!(decode::<VarLong>(&bytes).unwrap(), VarLong(value));
+    }
+
+    /// Coordinates are stored as `f32`, which only holds every integer up to 2^24 while x and z span 26 bits
+    #[test]
+    fn position(x in -(1 << 24)..(1 << 24), y in -(1 << 11)..(1 << 11), z in -(1 << 24)..(1 << 24)) {
+        assert_round_trip(Position::new(x as f32, y as f32, z as f32));
+    }
+
+    #[test]
+    fn bit_set(longs in vec(any::<i64>(), 0..8)) {
+        assert_round_trip(BitSet(longs));
+    }
+
+    #[test]
+    fn uuid(value in any::<u128>()) {
+        assert_round_trip(Uuid::from_u128(value));
+    }
+
+    #[test]
+    fn strings(value in ".{0,64}", values in vec(".{0,16}", 0..8)) {
+        assert_round_trip(value);
+        assert_round_trip(values);
+    }
+
+    #[test]
+    fn byte_arrays(value in vec(any::<u8>(), 0..64)) {
+        assert_round_trip(value);
+    }
+
+    #[test]
+    fn nbt(tag in tag(), content in compound_of(tag())) {
+        assert_round_trip(Some(tag.clone()));
+        assert_round_trip(tag);
+        assert_round_trip(Nbt::from(content));
+    }
+
+    #[test]
+    fn decoding_never_panics(bytes in vec(any::<u8>(), 0..64)) {
+        let _ = decode::<VarInt>(&bytes);
+        let _ = decode::<VarLong>(&bytes);
+        let _ = decode::<String>(&bytes);
+        let _ = decode::<BitSet>(&bytes);
+        let _ = decode::<Vec<u8>>(&bytes);
+        let _ = decode::<Vec<String>>(&bytes);
+        let _ = decode::<Option<Tag>>(&bytes);
+        let _ = decode::<Nbt>(&bytes);
+        let _ = decode::<Gamemode>(&bytes);
+    }
+}
+
+#[test]
+fn none_is_written_as_tag_end() {
+    assert_round_trip(None::<Tag>);
+}
+
+#[test]
+fn var_int_longer_than_five_bytes_is_rejected() {
+    assert!(matches!(
+        decode::<VarInt>(&[0x80; 6]),
+        Err(DecodeError::VarIntTooLong { max_bytes: 5 })
+    ));
+}
+
+#[test]
+fn string_length_is_not_truncated_to_16_bits() {
+    // 131_073 would pass a check against the length cast to u16
+    let mut bytes = vec![];
+    bytes.write_var_i32(VarInt(131_073));
+
+    assert!(matches!(
+        decode::<String>(&bytes),
+        Err(DecodeError::StringTooLong { length: 131_073, .. })
+    ));
+}
+
+#[test]
+fn string_limits_count_characters_not_bytes() {
+    // Three bytes of UTF-8 each, the limit is reached at 16 characters and 48 bytes
+    let mut bytes = vec![];
+    bytes.write_string(&"€".repeat(16), 16).unwrap();
+    assert_eq!(bytes.len(), 1 + 48);
+    assert_eq!(bytes.as_slice().read_string(16).unwrap(), "€".repeat(16));
+
+    let mut bytes = vec![];
+    assert!(matches!(
+        bytes.write_string(&"€".repeat(17), 16),
+        Err(EncodeError::StringTooLong {
+            length: 17,
+            max_length: 16
+        })
+    ));
+    // Few enough bytes to be read, but one character too many
+    bytes.write_string(&("€".repeat(15) + "ab"), 17).unwrap();
+    assert!(matches!(
+        bytes.as_slice().read_string(16),
+        Err(DecodeError::StringTooLong {
+            length: 17,
+            max_length: 16
+        })
+    ));
+
+    // Characters outside the Basic Multilingual Plane are two UTF-16 units, like vanilla counts them
+    let mut bytes = vec![];
+    bytes.write_string(&"😀".repeat(8), 16).unwrap();
+    assert_eq!(bytes.as_slice().read_string(16).unwrap(), "😀".repeat(8));
+    assert!(matches!(
+        vec![].write_string(&"😀".repeat(9), 16),
+        Err(EncodeError::StringTooLong { length: 18, .. })
+    ));
+
+    // More bytes than the limit allows for any characters are refused before they are read
+    let mut bytes = vec![];
+    bytes.write_var_i32(VarInt(49));
+    assert!(matches!(
+        bytes.as_slice().read_string(16),
+        Err(DecodeError::StringTooLong {
+            length: 49,
+            max_length: 16
+        })
+    ));
+}
+
+#[test]
+fn running_out_of_bytes_is_an_error() {
+    assert!(matches!(
+        decode::<i64>(&[0; 3]),
+        Err(DecodeError::UnexpectedEnd { needed: 8, remaining: 3 })
+    ));
+
+    // The length prefix is checked against what is left before anything is allocated
+    let mut bytes = vec![];
+    bytes.write_var_i32(VarInt(i32::MAX));
+    assert!(matches!(
+        decode::<Vec<u8>>(&bytes),
+        Err(DecodeError::UnexpectedEnd { remaining: 0, .. })
+    ));
+}
+
+#[test]
+fn negative_lengths_are_rejected() {
+    let mut bytes = vec![];
+    bytes.write_var_i32(VarInt(-1));
+
+    assert!(matches!(decode::<Vec<u8>>(&bytes), Err(DecodeError::NegativeLength(-1))));
+    assert!(matches!(bytes.as_slice().read_byte_array(), Err(DecodeError::NegativeLength(-1))));
+}
+
+#[test]
+fn enums_are_written_as_their_discriminant() {
+    for game_mode in [Gamemode::Survival, Gamemode::Creative, Gamemode::Adventure, Gamemode::Spectator] {
+        assert_round_trip(game_mode);
+    }
+    assert_round_trip(vec![ChatMode::Enabled, ChatMode::CommandsOnly, ChatMode::Hidden]);
+
+    let mut bytes = vec![];
+    Gamemode::Adventure.encode(&mut bytes).unwrap();
+    assert_eq!(bytes, [2]);
+}
+
+#[test]
+fn unknown_discriminants_are_rejected() {
+    assert!(matches!(
+        decode::<Gamemode>(&[4]),
+        Err(DecodeError::UnknownVariant { name: "Gamemode", value }) if value == "4"
+    ));
+    assert!(matches!(
+        decode::<MainHand>(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+        Err(DecodeError::UnknownVariant { name: "MainHand", value }) if value == "-1"
+    ));
+}
@@ -0,0 +1,244 @@
+use futures::future::BoxFuture;
+use rsa::{pkcs8::DecodePublicKey, Pkcs1v15Encrypt, RsaPublicKey};
+use server::{
+    config::ServerConfig,
+    crypto::{server_hash, CipherReader, CipherWriter},
+    errors::AuthError,
+    packets::{
+        incoming::handshake::HandShake,
+        login::{EncryptionRequest, EncryptionResponse, LoginDisconnect, LoginStart, LoginSuccess, SetCompression},
+        DynPacket, Packet, State,
+    },
+    session::{GameProfile, HttpSessionVerifier, ProfileProperty, SessionVerifier},
+    tcp::{codec::FrameCodec, server::McServer},
+    types::VarInt,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpListener, TcpStream,
+    },
+    time,
+};
+use uuid::Uuid;
+
+/// Lets in whoever asks unless it `rejects` everyone, and remembers the server hash it was asked about
+#[derive(Clone, Default)]
+struct MockSession {
+    hashes: Arc<Mutex<Vec<String>>>,
+    rejects: bool,
+}
+
+impl SessionVerifier for MockSession {
+    fn has_joined<'a>(&'a self, username: &'a str, server_hash: &'a str) -> BoxFuture<'a, Result<GameProfile, AuthError>> {
+        self.hashes.lock().unwrap().push(server_hash.to_string());
+
+        Box::pin(async move {
+            if self.rejects {
+                return Err(AuthError::NotAuthenticated(username.to_string()));
+            }
+
+            Ok(GameProfile {
+                id: Uuid::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5),
+                name: username.to_string(),
+                properties: vec![ProfileProperty {
+                    name: String::from("textures"),
+                    value: String::from("e30="),
+                    signature: Some(String::from("c2lnbmF0dXJl")),
+                }],
+            })
+        })
+    }
+}
+
+struct Client {
+    read: CipherReader<OwnedReadHalf>,
+    write: CipherWriter<OwnedWriteHalf>,
+}
+
+impl Client {
+    /// Starts a server with `config` and sends it a handshake followed by Login Start
+    async fn login(config: ServerConfig, session: MockSession) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = McServer::with_config(config).unwrap().with_session_verifier(session);
+        tokio::spawn(async move { server.serve(listener).await });
+
+        let (read, write) = TcpStream::connect(address).await.unwrap().into_split();
+        let mut client = Self {
+            read: CipherReader::new(read),
+            write: CipherWriter::new(write),
+        };
+
+        client
+            .send(&HandShake {
+                protocol_version: VarInt(765),
+                server_address: String::from("localhost"),
+                server_port: address.port(),
+                next_state: VarInt(2),
+            })
+            .await;
+        client
+            .send(&LoginStart {
+                username: String::from("Notch"),
+                uuid: Uuid::nil(),
+            })
+            .await;
+
+        client
+    }
+
+    /// Serverbound frames are never compressed here, the server only expects that after Login Success
+    async fn send(&mut self, packet: &dyn DynPacket) {
+        let frame = FrameCodec::default().encode(packet).unwrap();
+        self.write.write_all(&frame).await.unwrap();
+    }
+
+    async fn receive<P: Packet>(&mut self, compressed: bool) -> P {
+        let codec = FrameCodec {
+            compression: compressed.then_some(256),
+        };
+        let (id, payload) = codec.read(&mut self.read, State::Play).await.unwrap().unwrap();
+
+        assert_eq!(id, P::ID);
+        P::decode_body(&mut payload.as_slice()).unwrap()
+    }
+}
+
+fn encrypt(public_key: &RsaPublicKey, data: &[u8]) -> Vec<u8> {
+    public_key.encrypt(&mut rand::thread_rng(), Pkcs1v15Encrypt, data).unwrap()
+}
+
+#[test]
+fn server_hashes_are_signed_hex_digests() {
+    assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+    assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+    assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+}
+
+fn online() -> ServerConfig {
+    ServerConfig {
+        online_mode: true,
+        ..ServerConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn online_players_get_the_profile_of_the_session_server() {
+    let session = MockSession::default();
+    let mut client = Client::login(online(), session.clone()).await;
+
+    let request: EncryptionRequest = client.receive(false).await;
+    let public_key = RsaPublicKey::from_public_key_der(&request.public_key).unwrap();
+    let shared_secret = [7; 16];
+    client
+        .send(&EncryptionResponse {
+            shared_secret: encrypt(&public_key, &shared_secret),
+            verify_token: encrypt(&public_key, &request.verify_token),
+        })
+        .await;
+
+    client.read.enable(&shared_secret);
+    client.write.enable(&shared_secret);
+
+    let compression: SetCompression = client.receive(false).await;
+    assert_eq!(compression.threshold, 256);
+
+    let success: LoginSuccess = client.receive(true).await;
+    assert_eq!(success.uuid, Uuid::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5));
+    assert_eq!(success.username, "Notch");
+    assert_eq!(success.property.len(), 1);
+    assert_eq!(success.property[0].signature.as_deref(), Some("c2lnbmF0dXJl"));
+
+    assert_eq!(
+        *session.hashes.lock().unwrap(),
+        [server_hash("", &shared_secret, &request.public_key)]
+    );
+}
+
+#[tokio::test]
+async fn wrong_verify_tokens_close_the_connection() {
+    let session = MockSession::default();
+    let mut client = Client::login(online(), session.clone()).await;
+
+    let request: EncryptionRequest = client.receive(false).await;
+    let public_key = RsaPublicKey::from_public_key_der(&request.public_key).unwrap();
+    let mut verify_token = request.verify_token.clone();
+    verify_token[0] ^= 1;
+    client
+        .send(&EncryptionResponse {
+            shared_secret: encrypt(&public_key, &[7; 16]),
+            verify_token: encrypt(&public_key, &verify_token),
+        })
+        .await;
+
+    // Encryption was never enabled, so the reason is sent in the clear
+    let disconnect: LoginDisconnect = client.receive(false).await;
+    assert_eq!(disconnect.reason, r#"{"text":"Failed to verify username!"}"#);
+    assert!(!matches!(
+        FrameCodec::default().read(&mut client.read, State::Play).await,
+        Ok(Some(_))
+    ));
+    assert!(session.hashes.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn players_the_session_server_rejects_are_told_why() {
+    let session = MockSession {
+        rejects: true,
+        ..MockSession::default()
+    };
+    let mut client = Client::login(online(), session.clone()).await;
+
+    let request: EncryptionRequest = client.receive(false).await;
+    let public_key = RsaPublicKey::from_public_key_der(&request.public_key).unwrap();
+    let shared_secret = [7; 16];
+    client
+        .send(&EncryptionResponse {
+            shared_secret: encrypt(&public_key, &shared_secret),
+            verify_token: encrypt(&public_key, &request.verify_token),
+        })
+        .await;
+
+    client.read.enable(&shared_secret);
+    client.write.enable(&shared_secret);
+
+    let disconnect: LoginDisconnect = client.receive(false).await;
+    assert_eq!(disconnect.reason, r#"{"text":"Failed to verify username!"}"#);
+    assert!(!matches!(
+        FrameCodec::default().read(&mut client.read, State::Play).await,
+        Ok(Some(_))
+    ));
+    assert_eq!(session.hashes.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn offline_players_get_an_offline_uuid() {
+    // Offline mode is the default
+    let mut client = Client::login(ServerConfig::default(), MockSession::default()).await;
+
+    let _: SetCompression = client.receive(false).await;
+    let success: LoginSuccess = client.receive(true).await;
+    assert_eq!(success.uuid, GameProfile::offline(String::from("Notch")).id);
+    assert!(success.property.is_empty());
+}
+
+#[tokio::test]
+async fn a_silent_session_server_times_out() {
+    // Accepts the request and never answers it
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hasJoined", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (_stream, _) = listener.accept().await.unwrap();
+        std::future::pending::<()>().await
+    });
+
+    let session = HttpSessionVerifier::with_timeout(url, Duration::from_millis(100));
+    let result = time::timeout(Duration::from_secs(5), session.has_joined("Notch", "hash")).await;
+    assert!(matches!(result, Ok(Err(AuthError::HttpError(err))) if err.is_timeout()));
+}
@@ -0,0 +1,422 @@
+use futures::executor::block_on;
+use server::{
+    config::ServerConfig,
+    crypto::{CipherReader, CipherWriter},
+    decoder::DecoderReadExt,
+    encoder::{Encoder, EncoderWriteExt},
+    errors::{DecodeError, DispatchError, EncodeError, FrameError},
+    packets::{
+        chunk::ChunkDataUpdateLight,
+        config::ServerboundPluginMessage,
+        incoming::{handshake::HandShake, keep_alive_response::KeepAliveResponse, player_position::PlayerPosition},
+        login::{EncryptionResponse, LoginStart, Property},
+        outgoing::{keep_alive::KeepAlive, play_disconnect::PlayDisconnect},
+        play::PlayLogin,
+        serverbound::{Handlers, LoginPacket, PlayPacket, ServerboundPacket},
+        Direction, Packet, State,
+    },
+    player::mc_player::Player,
+    session::HttpSessionVerifier,
+    tcp::{
+        codec::{FrameCodec, MAX_FRAME_LEN},
+        server::{Connection, McServer},
+    },
+    types::VarInt,
+    utils::{decompress_frame, prepare_compressed_response, MAX_DATA_LEN},
+};
+use std::{collections::HashSet, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use uuid::Uuid;
+
+fn receive<T: Packet>(bytes: Vec<u8>) -> Result<T, DecodeError> {
+    T::decode_body(&mut bytes.as_slice())
+}
+
+/// Frames `packet` and splits the frame into its id and body
+fn send<T: Packet>(packet: &T) -> (i32, Vec<u8>) {
+    let bytes = FrameCodec::default().encode(packet).unwrap();
+
+    let mut reader = bytes.as_slice();
+    let length = reader.read_length().unwrap();
+    assert_eq!(length, reader.len());
+
+    let id = reader.read_var_i32().unwrap();
+    (id, reader.to_vec())
+}
+
+/// Decodes the body of a sent packet and checks that sending it again gives the same bytes
+fn assert_decodes_back<T: Packet>(packet: &T) {
+    let (id, body) = send(packet);
+    assert_eq!(id, T::ID);
+
+    let mut reader = body.as_slice();
+    let decoded = T::decode_body(&mut reader).unwrap();
+    assert!(reader.is_empty(), "{} bytes left over", reader.len());
+    assert_eq!(send(&decoded).1, body);
+}
+
+fn encode<T: Encoder>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut bytes = vec![];
+    value.encode(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[test]
+fn usernames_are_limited_to_16_characters() {
+    let mut bytes = encode(&"a".repeat(17)).unwrap();
+    bytes.extend_from_slice(Uuid::nil().as_bytes());
+
+    assert!(matches!(
+        receive::<LoginStart>(bytes),
+        Err(DecodeError::StringTooLong {
+            length: 17,
+            max_length: 16
+        })
+    ));
+
+    // Sixteen two byte characters are still a valid name
+    let mut bytes = encode(&"é".repeat(16)).unwrap();
+    bytes.extend_from_slice(Uuid::nil().as_bytes());
+    assert_eq!(receive::<LoginStart>(bytes).unwrap().username, "é".repeat(16));
+}
+
+#[test]
+fn plugin_message_data_is_the_rest_of_the_packet() {
+    let mut bytes = encode(&"minecraft:brand").unwrap();
+    bytes.extend_from_slice(b"\x07vanilla");
+
+    let message = receive::<ServerboundPluginMessage>(bytes).unwrap();
+    assert_eq!(message.channel, "minecraft:brand");
+    assert_eq!(message.data, b"\x07vanilla");
+}
+
+#[test]
+fn signature_is_only_written_when_signed() {
+    let mut property = Property {
+        username: "textures".into(),
+        value: "e30=".into(),
+        is_signed: false,
+        signature: None,
+    };
+    let unsigned = encode(&property).unwrap();
+    assert_eq!(unsigned.last(), Some(&0));
+
+    property.is_signed = true;
+    assert!(matches!(encode(&property), Err(EncodeError::MissingField("signature"))));
+
+    property.signature = Some("sig".into());
+    let mut signed = unsigned;
+    *signed.last_mut().unwrap() = 1;
+    signed.write_var_i32(VarInt(3));
+    signed.extend_from_slice(b"sig");
+    assert_eq!(encode(&property).unwrap(), signed);
+}
+
+#[test]
+fn clientbound_packets_can_be_decoded() {
+    assert_eq!(
+        (KeepAlive::ID, KeepAlive::STATE, KeepAlive::DIRECTION),
+        (0x24, State::Play, Direction::Clientbound)
+    );
+
+    assert_decodes_back(&KeepAlive::new(42));
+    assert_decodes_back(&PlayLogin::default());
+    assert_decodes_back(&ChunkDataUpdateLight::default());
+}
+
+#[test]
+fn serverbound_packets_can_be_encoded() {
+    let position = PlayerPosition {
+        x: 1.5,
+        y: 64.0,
+        z: -3.25,
+        on_ground: true,
+    };
+
+    let (id, body) = send(&position);
+    assert_eq!((id, PlayerPosition::DIRECTION), (0x17, Direction::Serverbound));
+
+    let decoded = receive::<PlayerPosition>(body).unwrap();
+    assert_eq!((decoded.x, decoded.y, decoded.z, decoded.on_ground), (1.5, 64.0, -3.25, true));
+}
+
+fn player_position_body() -> Vec<u8> {
+    let position = PlayerPosition {
+        x: 1.0,
+        y: 2.0,
+        z: 3.0,
+        on_ground: false,
+    };
+    send(&position).1
+}
+
+#[test]
+fn payloads_decode_into_the_variant_of_their_id() {
+    let packet = PlayPacket::decode(0x17, &player_position_body()).unwrap();
+    assert!(matches!(packet, PlayPacket::PlayerPosition(PlayerPosition { y: 2.0, .. })));
+    assert_eq!(packet.id(), PlayerPosition::ID);
+
+    assert!(matches!(
+        PlayPacket::decode(0x7F, &[]),
+        Err(DispatchError::UnknownPacket {
+            state: State::Play,
+            id: 0x7F
+        })
+    ));
+}
+
+#[test]
+fn login_payloads_include_the_encryption_response() {
+    let response = EncryptionResponse {
+        shared_secret: vec![1; 128],
+        verify_token: vec![2; 128],
+    };
+    let packet = LoginPacket::decode(0x01, &send(&response).1).unwrap();
+    assert!(matches!(packet, LoginPacket::EncryptionResponse(EncryptionResponse { ref verify_token, .. }) if verify_token == &[2; 128]));
+}
+
+#[test]
+fn payloads_have_to_match_the_packet_length() {
+    let body = player_position_body();
+    assert!(matches!(
+        PlayPacket::decode(0x17, &body[..body.len() - 1]),
+        Err(DispatchError::PacketTooShort { id: 0x17 })
+    ));
+
+    let mut body = body;
+    body.extend_from_slice(&[0, 0]);
+    assert!(matches!(
+        PlayPacket::decode(0x17, &body),
+        Err(DispatchError::PacketTooLong { id: 0x17, remaining: 2 })
+    ));
+}
+
+async fn record_position(positions: &mut Vec<f64>, position: PlayerPosition) -> Result<(), EncodeError> {
+    positions.push(position.y);
+    Ok(())
+}
+
+#[test]
+fn handlers_are_registered_per_packet_type() {
+    let mut handlers = Handlers::new();
+    handlers.on(record_position);
+
+    let mut positions = vec![];
+    block_on(handlers.dispatch(&mut positions, State::Play, 0x17, &player_position_body())).unwrap();
+    assert_eq!(positions, [2.0]);
+
+    assert!(matches!(
+        block_on(handlers.dispatch(&mut positions, State::Configuration, 0x17, &player_position_body())),
+        Err(DispatchError::UnknownPacket {
+            state: State::Configuration,
+            ..
+        })
+    ));
+    assert!(matches!(
+        block_on(handlers.dispatch(&mut positions, State::Play, KeepAliveResponse::ID, &[0; 8])),
+        Err(DispatchError::Unhandled { state: State::Play, .. })
+    ));
+}
+
+#[test]
+fn the_server_handles_every_serverbound_packet() {
+    let registered: HashSet<_> = McServer::handlers().packets().collect();
+    assert_eq!(registered, HashSet::from_iter(ServerboundPacket::PACKETS.iter().copied()));
+
+    for (state, id) in registered {
+        let decoded = ServerboundPacket::decode(state, id, &[]);
+        assert!(!matches!(decoded, Err(DispatchError::UnknownPacket { .. })), "{state:?} {id:#04x}");
+    }
+}
+
+/// Frames a packet with compression and returns its data length and everything after the frame length
+fn compressed_frame(id: i32, data: Vec<u8>, threshold: usize) -> (usize, Vec<u8>) {
+    let frame = prepare_compressed_response(VarInt(id), data, threshold).unwrap();
+
+    let mut reader = frame.as_slice();
+    assert_eq!(reader.read_length().unwrap(), reader.len());
+    let body = reader.to_vec();
+    (reader.read_length().unwrap(), body)
+}
+
+#[test]
+fn packets_below_the_threshold_are_not_compressed() {
+    let (data_length, body) = compressed_frame(0x24, vec![1, 2, 3], 256);
+    assert_eq!(data_length, 0);
+    assert_eq!(body, [0, 0x24, 1, 2, 3]);
+    assert_eq!(decompress_frame(body, 256).unwrap(), [0x24, 1, 2, 3]);
+}
+
+#[test]
+fn compressed_frames_decompress_to_the_packet() {
+    let data = vec![7; 4096];
+    let (data_length, body) = compressed_frame(0x25, data.clone(), 256);
+    assert_eq!(data_length, data.len() + 1);
+    assert!(body.len() < 100);
+
+    let packet = decompress_frame(body, 256).unwrap();
+    assert_eq!(packet[0], 0x25);
+    assert_eq!(packet[1..], data);
+}
+
+#[test]
+fn data_lengths_are_checked() {
+    let (data_length, body) = compressed_frame(0x25, vec![7; 300], 256);
+    assert_eq!(data_length, 301);
+    assert!(matches!(
+        decompress_frame(body.clone(), 512),
+        Err(DecodeError::InvalidDataLength(301))
+    ));
+
+    // Claims one byte more than what decompresses, 302 as a VarInt
+    let mut lying = vec![0xAE, 0x02];
+    lying.extend_from_slice(&body[2..]);
+    assert!(matches!(
+        decompress_frame(lying, 256),
+        Err(DecodeError::DataLengthMismatch {
+            expected: 302,
+            actual: 301
+        })
+    ));
+
+    let mut too_long = vec![];
+    too_long.write_var_i32(VarInt::from(MAX_DATA_LEN + 1));
+    assert!(matches!(decompress_frame(too_long, 256), Err(DecodeError::InvalidDataLength(_))));
+}
+
+fn read_frame(codec: FrameCodec, bytes: &[u8], state: State) -> Result<Option<(i32, Vec<u8>)>, FrameError> {
+    block_on(codec.read(&mut &bytes[..], state))
+}
+
+#[test]
+fn frames_read_back_into_id_and_payload() {
+    let keep_alive = KeepAlive::new(42);
+    for codec in [FrameCodec::default(), FrameCodec { compression: Some(0) }] {
+        let frame = codec.encode(&keep_alive).unwrap();
+        let (id, payload) = read_frame(codec, &frame, State::Play).unwrap().unwrap();
+        assert_eq!(id, KeepAlive::ID);
+        assert_eq!(payload, 42i64.to_be_bytes());
+    }
+
+    // Closing the connection between two frames is not an error
+    assert!(read_frame(FrameCodec::default(), &[], State::Play).unwrap().is_none());
+}
+
+#[test]
+fn frame_lengths_are_limited_per_state() {
+    let mut frame = vec![];
+    frame.write_var_i32(VarInt(2000));
+    assert!(matches!(
+        read_frame(FrameCodec::default(), &frame, State::Handshake),
+        Err(FrameError::FrameTooLong {
+            state: State::Handshake,
+            length: 2000,
+            max_length: 1024
+        })
+    ));
+
+    let mut frame = vec![];
+    frame.write_var_i32(VarInt::from(MAX_FRAME_LEN + 1));
+    assert!(matches!(
+        read_frame(FrameCodec::default(), &frame, State::Play),
+        Err(FrameError::FrameTooLong { .. })
+    ));
+}
+
+#[test]
+fn malformed_frames_are_errors() {
+    let codec = FrameCodec::default();
+    assert!(matches!(
+        read_frame(codec, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F], State::Play),
+        Err(FrameError::DecodeError(DecodeError::NegativeLength(-1)))
+    ));
+    assert!(matches!(read_frame(codec, &[0x00], State::Play), Err(FrameError::EmptyFrame)));
+    assert!(matches!(
+        read_frame(codec, &[0x05, 0x00, 0x01], State::Play),
+        Err(FrameError::IOError(_))
+    ));
+    assert!(matches!(
+        read_frame(codec, &[0x02, 0xFF, 0xFF], State::Play),
+        Err(FrameError::DecodeError(DecodeError::UnexpectedEnd { .. }))
+    ));
+}
+
+#[tokio::test]
+async fn malformed_frames_only_close_their_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = McServer::with_config(ServerConfig {
+        online_mode: false,
+        ..ServerConfig::default()
+    })
+    .unwrap();
+    tokio::spawn(async move { server.serve(listener).await });
+
+    for _ in 0..2 {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        // 2^31 - 1, the length that used to be allocated as is
+        stream.write_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0x07]).await.unwrap();
+
+        let mut response = vec![];
+        stream.read_to_end(&mut response).await.unwrap();
+        assert!(response.is_empty());
+    }
+}
+
+/// A connection in `state` served over loopback, together with the client's end of it
+async fn connect(state: State, codec: FrameCodec) -> (Connection, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+    let (read, write) = listener.accept().await.unwrap().0.into_split();
+
+    let connection = Connection {
+        state,
+        read: CipherReader::new(read),
+        write: CipherWriter::new(write),
+        players: Default::default(),
+        config: Default::default(),
+        codec,
+        keys: None,
+        session: Arc::new(HttpSessionVerifier::default()),
+        login: None,
+    };
+    (connection, client)
+}
+
+#[tokio::test]
+async fn disconnects_are_compressed_and_encrypted_like_other_packets() {
+    let shared_secret = [7; 16];
+    let codec = FrameCodec { compression: Some(0) };
+    let (mut connection, client) = connect(State::Play, codec).await;
+    connection.write.enable(&shared_secret);
+    assert!(connection.disconnect("Bye").await.unwrap());
+
+    let mut client = CipherReader::new(client);
+    client.enable(&shared_secret);
+    let (id, _) = codec.read(&mut client, State::Play).await.unwrap().unwrap();
+    assert_eq!(id, PlayDisconnect::ID);
+    assert!(codec.read(&mut client, State::Play).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn handshakes_must_ask_for_status_or_login() {
+    let (mut connection, _client) = connect(State::Handshake, FrameCodec::default()).await;
+    let handshake = |next_state| HandShake {
+        protocol_version: VarInt(765),
+        server_address: String::from("localhost"),
+        server_port: 25565,
+        next_state: VarInt(next_state),
+    };
+
+    assert!(matches!(
+        HandShake::handle(&mut connection, handshake(3)).await,
+        Err(DispatchError::InvalidNextState(3))
+    ));
+    assert_eq!(connection.state, State::Handshake);
+
+    HandShake::handle(&mut connection, handshake(2)).await.unwrap();
+    assert_eq!(connection.state, State::Login);
+}
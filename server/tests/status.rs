@@ -0,0 +1,169 @@
+use server::{
+    config::ServerConfig,
+    packets::{incoming::handshake::HandShake, legacy::LegacyPing, status::ServerStatus},
+    tcp::{codec::FrameCodec, server::McServer},
+    types::VarInt,
+};
+use std::{net::SocketAddr, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+fn status() -> ServerStatus {
+    ServerStatus {
+        version_name: String::from("1.20.4"),
+        protocol: 765,
+        motd: String::from("A §cMinecraft§r Server"),
+        online: 3,
+        max_players: 20,
+    }
+}
+
+/// Decodes the reason of a legacy kick packet
+fn kick_reason(response: &[u8]) -> String {
+    assert_eq!(response[0], 0xFF);
+    let length = u16::from_be_bytes([response[1], response[2]]) as usize;
+
+    let units: Vec<u16> = response[3..]
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect();
+    assert_eq!(units.len(), length);
+    String::from_utf16(&units).unwrap()
+}
+
+/// The 1.6 ping, `MC|PingHost` for localhost:25565
+fn ping_host() -> Vec<u8> {
+    let utf16 = |text: &str| -> Vec<u8> {
+        let mut bytes = (text.encode_utf16().count() as u16).to_be_bytes().to_vec();
+        bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+        bytes
+    };
+
+    let mut data = vec![78];
+    data.extend(utf16("localhost"));
+    data.extend(25565i32.to_be_bytes());
+
+    let mut ping = vec![0xFE, 0x01, 0xFA];
+    ping.extend(utf16("MC|PingHost"));
+    ping.extend((data.len() as u16).to_be_bytes());
+    ping.extend(data);
+    ping
+}
+
+#[test]
+fn legacy_pings_are_told_apart_by_their_first_bytes() {
+    assert_eq!(LegacyPing::parse(&[0xFE]), Some(LegacyPing::Beta));
+    assert_eq!(LegacyPing::parse(&[0xFE, 0x01]), Some(LegacyPing::V1_4));
+    assert_eq!(LegacyPing::parse(&ping_host()), Some(LegacyPing::V1_4));
+    assert_eq!(LegacyPing::parse(&[0x10, 0x00, 0xFD, 0x05]), None);
+}
+
+#[test]
+fn legacy_responses_follow_the_kick_format() {
+    assert_eq!(
+        kick_reason(&LegacyPing::V1_4.response(&status())),
+        "§1\u{0}765\u{0}1.20.4\u{0}A §cMinecraft§r Server\u{0}3\u{0}20"
+    );
+    // The beta format is split on §, so it can't be part of the MOTD
+    assert_eq!(kick_reason(&LegacyPing::Beta.response(&status())), "A cMinecraftr Server§3§20");
+
+    // Same for \0 in the 1.4 format
+    let status = ServerStatus {
+        motd: String::from("A\0B"),
+        ..status()
+    };
+    assert_eq!(
+        kick_reason(&LegacyPing::V1_4.response(&status)),
+        "§1\u{0}765\u{0}1.20.4\u{0}AB\u{0}3\u{0}20"
+    );
+}
+
+async fn serve(config: ServerConfig) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { McServer::with_config(config).unwrap().serve(listener).await });
+    address
+}
+
+async fn send_legacy_ping(address: SocketAddr, ping: &[u8]) -> String {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    stream.write_all(ping).await.unwrap();
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await.unwrap();
+    kick_reason(&response)
+}
+
+async fn legacy_ping(ping: &[u8]) -> String {
+    let config = ServerConfig {
+        online_mode: false,
+        motd: String::from("Legacy"),
+        max_players: 20,
+        ..ServerConfig::default()
+    };
+    send_legacy_ping(serve(config).await, ping).await
+}
+
+#[tokio::test]
+async fn legacy_pings_are_answered_before_the_handshake() {
+    assert_eq!(legacy_ping(&ping_host()).await, "§1\u{0}765\u{0}1.20.4\u{0}Legacy\u{0}0\u{0}20");
+    assert_eq!(legacy_ping(&[0xFE]).await, "Legacy§0§20");
+}
+
+#[tokio::test]
+async fn an_idle_client_does_not_hold_up_others() {
+    let address = serve(ServerConfig {
+        online_mode: false,
+        motd: String::from("Legacy"),
+        max_players: 20,
+        ..ServerConfig::default()
+    })
+    .await;
+
+    let _idle = TcpStream::connect(address).await.unwrap();
+    let mut half_sent = TcpStream::connect(address).await.unwrap();
+    half_sent.write_all(&[0x10]).await.unwrap();
+
+    let reason = time::timeout(Duration::from_secs(5), send_legacy_ping(address, &[0xFE])).await;
+    assert_eq!(reason.expect("the ping waited on the idle clients"), "Legacy§0§20");
+}
+
+#[tokio::test]
+async fn silent_clients_are_disconnected() {
+    let address = serve(ServerConfig {
+        online_mode: false,
+        handshake_timeout: Duration::from_millis(100),
+        ..ServerConfig::default()
+    })
+    .await;
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let mut response = vec![];
+    let read = time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response)).await;
+    assert!(read.expect("the silent client was kept open").is_ok());
+    assert!(response.is_empty());
+}
+
+#[tokio::test]
+async fn unknown_packets_before_play_close_the_connection() {
+    let address = serve(ServerConfig::default()).await;
+
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let handshake = HandShake {
+        protocol_version: VarInt(765),
+        server_address: String::from("localhost"),
+        server_port: address.port(),
+        next_state: VarInt(1),
+    };
+    stream.write_all(&FrameCodec::default().encode(&handshake).unwrap()).await.unwrap();
+    // A frame holding nothing but id 0x05, which the status state doesn't have
+    stream.write_all(&[0x01, 0x05]).await.unwrap();
+
+    let mut response = vec![];
+    let read = time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response)).await;
+    assert!(read.expect("the connection was kept open").is_ok());
+    assert!(response.is_empty());
+}